@@ -1,8 +1,45 @@
 pub const DISCRIMINATOR: usize = 8;
 
+// Fixed-point scale of the per-LP reward accumulator
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+// Fixed-point scale of prices returned by read-only instructions
+pub const PRICE_PRECISION: u128 = 1_000_000_000;
+
+// Largest raw (not decimals-adjusted) ratio between the two sides of an initial deposit
+pub const MAX_INITIAL_PRICE_RATIO: u128 = 1_000_000_000_000;
+
+// Smallest LP supply an initial deposit may seed. Later mints are proportional to the
+// supply, so a tiny one would round every following deposit coarsely
+pub const MIN_INITIAL_LP: u64 = 1_000;
+
+// Denominator shared by every basis-point field: fees, discounts, splits and guards
+pub const BPS_DENOMINATOR: u16 = 10000;
+
+// Largest swap fee a pool may charge, 10%
+pub const MAX_FEE_BPS: u16 = BPS_DENOMINATOR / 10;
+
+// Longest path `swap_route` walks; each hop costs roughly a full swap in compute units
+pub const MAX_ROUTE_HOPS: usize = 4;
+
+// Most pools `withdraw_protocol_fees_batch` sweeps in one call. Pools on distinct mints hit
+// the transaction size limit sooner, since every pool brings its own accounts
+pub const MAX_FEE_BATCH_POOLS: usize = 8;
+
+// Length of the year fee yields are annualized over, ignoring leap days
+pub const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+
 pub mod seed_prefix {
   pub const CONFIG: &[u8] = b"config";
   pub const POOL: &[u8] = b"pool";
   pub const LP_MINT: &[u8] = b"lp_mint";
   pub const AUTH: &[u8] = b"auth";
+  pub const VAULT_AUTH: &[u8] = b"vault_auth";
+  pub const VAULT: &[u8] = b"vault";
+  pub const REWARD: &[u8] = b"reward";
+  pub const REWARD_VAULT: &[u8] = b"reward_vault";
+  pub const USER_REWARD: &[u8] = b"user_reward";
+  pub const STAKE_VAULT: &[u8] = b"stake_vault";
+  pub const REGISTRY: &[u8] = b"registry";
+  pub const MINT_POOL_INDEX: &[u8] = b"mint_pool_index";
 }
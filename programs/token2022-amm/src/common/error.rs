@@ -16,6 +16,8 @@ pub enum AMMError {
   #[msg("User not whitelisted")]
   NotWhitelisted,
 
+  // No longer returned, see the per-instruction variants below. Kept so the codes of the
+  // variants after it do not shift
   #[msg("Slippage tolerance exceeded")]
   SlippageExceeded,
 
@@ -33,4 +35,156 @@ pub enum AMMError {
 
   #[msg("Division by zero")]
   DivisionByZero,
+
+  #[msg("Pool price deviates from the expected price")]
+  PriceDeviationExceeded,
+
+  #[msg("Unauthorized")]
+  Unauthorized,
+
+  #[msg("Withdrawal would leave a reserve below the configured minimum")]
+  BelowMinReserve,
+
+  #[msg("Swap amount exceeds the maximum allowed share of the reserve")]
+  SwapTooLarge,
+
+  #[msg("Pool has outstanding LP but an empty reserve")]
+  EmptyReserves,
+
+  #[msg("Vault does not match the reserve side being swapped")]
+  InvalidVault,
+
+  #[msg("Swap executed worse than the price bound")]
+  PriceBoundExceeded,
+
+  #[msg("Initial deposit ratio is out of bounds")]
+  InitialLpOutOfBounds,
+
+  #[msg("Math overflow")]
+  MathOverflow,
+
+  #[msg("Minted LP does not match the expected amount")]
+  UnexpectedLpAmount,
+
+  #[msg("LP mint authority is not the pool authority")]
+  InvalidLpMintAuthority,
+
+  #[msg("Pool fee changed since the swap was quoted")]
+  FeeChanged,
+
+  #[msg("Mint X and mint Y must be different")]
+  DuplicateMint,
+
+  #[msg("Token program does not match the mint owner")]
+  TokenProgramMismatch,
+
+  #[msg("Pool reserves moved beyond the tolerance since the swap was built")]
+  ReservesMoved,
+
+  #[msg("Whitelist has no free entries")]
+  WhitelistFull,
+
+  #[msg("User is already whitelisted")]
+  AlreadyWhitelisted,
+
+  #[msg("Whitelist capacity is out of bounds or below the current entries")]
+  InvalidWhitelistCapacity,
+
+  #[msg("Neither vault is frozen")]
+  VaultsNotFrozen,
+
+  #[msg("Epoch changed since the swap was quoted")]
+  EpochChanged,
+
+  #[msg("Withdraw split must sum to 10000 bps")]
+  InvalidSplit,
+
+  #[msg("Vault authority does not match the pool's vault owner")]
+  InvalidVaultAuthority,
+
+  #[msg("All pools of this authority are paused")]
+  GloballyPaused,
+
+  // No longer returned: LP mints are created without token metadata. Kept so the codes of
+  // the variants after it do not shift
+  #[msg("LP mint has no token metadata updatable by the pool authority")]
+  LpMetadataNotFound,
+
+  #[msg("Pool reserve ratio moved beyond the tolerance since the deposit was quoted")]
+  ReserveRatioChanged,
+
+  #[msg("Reserve product decreased, which would destroy LP value")]
+  InvariantViolation,
+
+  #[msg("More mints carry a transfer fee than the pool allows")]
+  TooManyFeeBearingMints,
+
+  #[msg("Mint pool index is full")]
+  MintPoolIndexFull,
+
+  #[msg("Deposit would take the user's LP balance past the per-user limit")]
+  PositionLimitExceeded,
+
+  #[msg("Amounts are not in the pool's reserve ratio")]
+  NonProportionalAmounts,
+
+  #[msg("Initial deposit mints less LP than the minimum initial supply")]
+  InitialLpTooSmall,
+
+  #[msg("No protocol fee withdrawal is queued")]
+  NoFeeWithdrawalQueued,
+
+  #[msg("Queued protocol fee withdrawal is still timelocked")]
+  FeeWithdrawalLocked,
+
+  #[msg("Pool vaults, reward vault and LP tokens cannot be rescued")]
+  CannotRescuePoolAccount,
+
+  #[msg("Pool authority does not match the PDA derived from the stored bump")]
+  InvalidAuthorityBump,
+
+  #[msg("Route is empty, too long, or its hops and accounts do not line up")]
+  InvalidRoute,
+
+  #[msg("Swap would move the price past the pool's circuit breaker")]
+  CircuitBreakerTripped,
+
+  #[msg("Mint transfer fee exceeds the pool's cap")]
+  TransferFeeTooHigh,
+
+  #[msg("Swap input is worth less than one unit of the output")]
+  InsufficientInputAmount,
+
+  #[msg("Deposit mints fewer LP tokens than the slippage tolerance allows")]
+  DepositSlippageExceeded,
+
+  #[msg("Withdrawal pays out less than the slippage tolerance allows")]
+  WithdrawSlippageExceeded,
+
+  #[msg("Swap pays out less than the slippage tolerance allows")]
+  SwapSlippageExceeded,
+
+  #[msg("Swap input is too small to produce any output after fees")]
+  SwapOutputRoundsToZero,
+
+  #[msg("Account was written by a newer program version")]
+  UnsupportedAccountVersion,
+
+  #[msg("Pool is not stuck")]
+  PoolNotStuck,
+
+  #[msg("A pool vault is still frozen")]
+  VaultFrozen,
+
+  #[msg("Unstake amount exceeds the staked LP")]
+  InsufficientStakedLp,
+
+  #[msg("Swap input exceeds the caller's balance")]
+  InsufficientBalance,
+
+  #[msg("Fee batch is empty, too long, or its pools and accounts do not line up")]
+  InvalidFeeBatch,
+
+  #[msg("Launch fee decay is configured, update target_fee instead of fee")]
+  FeeDecayConfigured,
 }
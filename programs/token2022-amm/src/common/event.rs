@@ -1 +1,178 @@
+use anchor_lang::prelude::*;
 
+// Every event carries the pool's `config` and the block `timestamp` so off-chain consumers
+// can index all of them the same way.
+
+#[event]
+pub struct PoolInitializedEvent {
+  pub config: Pubkey,
+  pub mint_x: Pubkey,
+  pub mint_y: Pubkey,
+  pub fee: u16,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub amount_x: u64,
+  pub amount_y: u64,
+  pub lp_minted: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub lp_burned: u64,
+  pub amount_x: u64,
+  pub amount_y: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct SwapEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub mint_in: Pubkey,
+  pub amount_in: u64,
+  pub amount_out: u64,
+  pub protocol_fee: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdatedEvent {
+  pub config: Pubkey,
+  pub fee: u16,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistUpdatedEvent {
+  pub config: Pubkey,
+  pub enabled: bool,
+  pub entries: u16,
+  pub capacity: u16,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultMigratedEvent {
+  pub config: Pubkey,
+  pub old_vault: Pubkey,
+  pub new_vault: Pubkey,
+  pub amount: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolStuckEvent {
+  pub config: Pubkey,
+  pub vault_x_frozen: bool,
+  pub vault_y_frozen: bool,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUnlockedEvent {
+  pub config: Pubkey,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardFundedEvent {
+  pub config: Pubkey,
+  pub amount: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardClaimedEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub amount: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct LpStakedEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub amount: u64,
+  pub total_staked: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct LpUnstakedEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub amount: u64,
+  pub total_staked: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct GlobalPauseUpdatedEvent {
+  pub authority: Pubkey,
+  pub paused: bool,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerEvent {
+  pub config: Pubkey,
+  pub user: Pubkey,
+  pub mint_in: Pubkey,
+  pub amount_in: u64,
+  pub price_move_bps: u16,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerResetEvent {
+  pub config: Pubkey,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeWithdrawalQueuedEvent {
+  pub config: Pubkey,
+  pub amount_x: u64,
+  pub amount_y: u64,
+  pub unlock_at: i64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeWithdrawalExecutedEvent {
+  pub config: Pubkey,
+  pub amount_x: u64,
+  pub amount_y: u64,
+  pub recipient_x: Pubkey,
+  pub recipient_y: Pubkey,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolSnapshotEvent {
+  pub config: Pubkey,
+  pub snapshot: Pubkey,
+  pub reserve_x: u64,
+  pub reserve_y: u64,
+  pub lp_supply: u64,
+  pub slot: u64,
+  pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensRescuedEvent {
+  pub config: Pubkey,
+  pub mint: Pubkey,
+  pub source: Pubkey,
+  pub amount: u64,
+  pub timestamp: i64,
+}
@@ -0,0 +1,27 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError},
+    instructions::set_whitelist_enabled::emit_whitelist_updated,
+    state::Config,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct UpdateWhitelist<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+}
+
+pub fn handler(ctx: Context<UpdateWhitelist>, user: Pubkey) -> Result<()> {
+  ctx.accounts.config.add_to_whitelist(user)?;
+
+  emit_whitelist_updated(&ctx.accounts.config)
+}
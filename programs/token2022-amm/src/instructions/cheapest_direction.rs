@@ -0,0 +1,119 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, BPS_DENOMINATOR},
+      error::AMMError,
+    },
+    state::{Config, PoolState},
+    utils::{swap::constant_product_out, token::calculate_transfer_fee_excluded_amount},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::Mint as MintInterface,
+};
+
+#[derive(Accounts)]
+pub struct CheapestDirection<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = mint_x,
+    has_one = mint_y
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
+  pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DirectionQuote {
+  /// Whether swapping X for Y loses less to fees than swapping Y for X
+  pub x_to_y: bool,
+  /// What the user receives swapping `amount` of X, net of every fee
+  pub net_out_x_to_y: u64,
+  /// What the user receives swapping `amount` of Y, net of every fee
+  pub net_out_y_to_x: u64,
+  /// Share of the fee-free output lost to the swap fee and both transfer fees, per direction
+  pub fee_drag_bps_x_to_y: u16,
+  pub fee_drag_bps_y_to_x: u16,
+}
+
+// Quotes swapping `amount` in each direction at the fee a swapper without discounts pays
+// and picks the one losing the smaller share of its output to fees. The raw outputs are in
+// different tokens, so the fee drag against the fee-free constant product output is what
+// is compared; ties go to X -> Y
+pub fn handler(ctx: Context<CheapestDirection>, amount: u64) -> Result<DirectionQuote> {
+  let config = &ctx.accounts.config;
+  let pool_state = &ctx.accounts.pool_state;
+  require!(amount > 0, AMMError::InvalidAmount);
+  require!(
+    pool_state.reserve_x > 0 && pool_state.reserve_y > 0,
+    AMMError::EmptyReserves
+  );
+
+  let fee = config.swap_fee_for(&Pubkey::default(), 0, Clock::get()?.unix_timestamp)?;
+  let (net_out_x_to_y, fee_drag_bps_x_to_y) = quote_direction(
+    amount,
+    fee,
+    (pool_state.reserve_x, pool_state.reserve_y),
+    &ctx.accounts.mint_x,
+    &ctx.accounts.mint_y,
+  )?;
+  let (net_out_y_to_x, fee_drag_bps_y_to_x) = quote_direction(
+    amount,
+    fee,
+    (pool_state.reserve_y, pool_state.reserve_x),
+    &ctx.accounts.mint_y,
+    &ctx.accounts.mint_x,
+  )?;
+
+  let quote = DirectionQuote {
+    x_to_y: fee_drag_bps_x_to_y <= fee_drag_bps_y_to_x,
+    net_out_x_to_y,
+    net_out_y_to_x,
+    fee_drag_bps_x_to_y,
+    fee_drag_bps_y_to_x,
+  };
+  msg!(
+    "Fee drag for {}: {} bps X -> Y, {} bps Y -> X",
+    amount,
+    fee_drag_bps_x_to_y,
+    fee_drag_bps_y_to_x
+  );
+
+  Ok(quote)
+}
+
+// (net output, fee drag in bps) of swapping `amount_in`, mirroring `swap`: the input
+// transfer fee comes off what reaches the vault, the output transfer fee off the amount
+// out, and the transfer to the user withholds it again
+fn quote_direction(
+  amount_in: u64,
+  fee: u16,
+  (reserve_in, reserve_out): (u64, u64),
+  mint_in: &InterfaceAccount<MintInterface>,
+  mint_out: &InterfaceAccount<MintInterface>,
+) -> Result<(u64, u16)> {
+  let fee_free_out = constant_product_out(amount_in, reserve_in, reserve_out, 0)?;
+
+  let actual_amount_in = calculate_transfer_fee_excluded_amount(mint_in, amount_in)?.amount;
+  let amount_out_before_fees =
+    constant_product_out(actual_amount_in, reserve_in, reserve_out, fee)?;
+  let amount_out = calculate_transfer_fee_excluded_amount(mint_out, amount_out_before_fees)?.amount;
+  let net_out = calculate_transfer_fee_excluded_amount(mint_out, amount_out)?.amount;
+
+  let fee_drag_bps = if fee_free_out == 0 {
+    0
+  } else {
+    // net_out <= fee_free_out, so the drag is at most BPS_DENOMINATOR
+    ((fee_free_out - net_out) as u128 * BPS_DENOMINATOR as u128 / fee_free_out as u128) as u16
+  };
+
+  Ok((net_out, fee_drag_bps))
+}
@@ -0,0 +1,41 @@
+use {
+  crate::utils::token::{get_unsupported_token_extension, verify_supported_token_mint},
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::Mint as MintInterface,
+};
+
+#[derive(Accounts)]
+pub struct CheckMintSupported<'info> {
+  pub mint: Box<InterfaceAccount<'info, MintInterface>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MintSupport {
+  pub supported: bool,
+  /// Raw `ExtensionType` value of the first disallowed extension, if that is why the mint was rejected
+  pub extension_type: Option<u16>,
+}
+
+pub fn handler(ctx: Context<CheckMintSupported>) -> Result<MintSupport> {
+  let mint = &ctx.accounts.mint;
+
+  let mint_support = match verify_supported_token_mint(mint) {
+    Ok(supported) => MintSupport {
+      supported,
+      extension_type: None,
+    },
+    Err(_) => MintSupport {
+      supported: false,
+      extension_type: get_unsupported_token_extension(mint)?.map(u16::from),
+    },
+  };
+
+  msg!(
+    "Mint {} supported: {}, extension: {:?}",
+    mint.key(),
+    mint_support.supported,
+    mint_support.extension_type
+  );
+
+  Ok(mint_support)
+}
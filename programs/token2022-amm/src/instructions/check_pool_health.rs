@@ -0,0 +1,52 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct CheckPoolHealth<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  #[account(address = pool_state.vault_x)]
+  pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(address = pool_state.vault_y)]
+  pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolHealth {
+  /// `vault_x.amount - reserve_x`
+  pub delta_x: i128,
+  /// `vault_y.amount - reserve_y`
+  pub delta_y: i128,
+  /// Both vaults hold at least their reserve
+  pub healthy: bool,
+}
+
+pub fn handler(ctx: Context<CheckPoolHealth>) -> Result<PoolHealth> {
+  let pool_state = &ctx.accounts.pool_state;
+
+  let delta_x = ctx.accounts.vault_x.amount as i128 - pool_state.reserve_x as i128;
+  let delta_y = ctx.accounts.vault_y.amount as i128 - pool_state.reserve_y as i128;
+
+  Ok(PoolHealth {
+    delta_x,
+    delta_y,
+    healthy: delta_x >= 0 && delta_y >= 0,
+  })
+}
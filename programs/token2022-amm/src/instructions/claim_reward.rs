@@ -0,0 +1,117 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::RewardClaimedEvent},
+    state::{Config, RewardConfig, UserReward},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+      transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+    },
+  },
+};
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+  #[account(mut)]
+  pub user: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::REWARD, config.key().as_ref()],
+    bump = reward_config.bump,
+    has_one = reward_mint,
+    has_one = reward_vault
+  )]
+  pub reward_config: Box<Account<'info, RewardConfig>>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::USER_REWARD, reward_config.key().as_ref(), user.key().as_ref()],
+    bump = user_reward.bump
+  )]
+  pub user_reward: Box<Account<'info, UserReward>>,
+
+  pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(mut)]
+  pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = user,
+    associated_token::mint = reward_mint,
+    associated_token::authority = user,
+    associated_token::token_program = token_program,
+  )]
+  pub user_reward_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+// Pays out the reward earned by the caller's staked LP, see `stake_lp`
+pub fn handler(ctx: Context<ClaimReward>) -> Result<()> {
+  let reward_config = &mut ctx.accounts.reward_config;
+  let user_reward = &mut ctx.accounts.user_reward;
+
+  reward_config.update(Clock::get()?.unix_timestamp)?;
+  user_reward.settle(reward_config.reward_per_lp_stored)?;
+
+  // Pay out what the vault can cover, anything beyond stays pending until it is funded
+  let payout = user_reward
+    .pending_reward
+    .min(ctx.accounts.reward_vault.amount);
+  if payout == 0 {
+    msg!("No reward to claim");
+    return Ok(());
+  }
+
+  let config = &ctx.accounts.config;
+  let config_key = config.key();
+  let auth_seeds = &[seed_prefix::AUTH, config_key.as_ref(), &[config.auth_bump]];
+  let signer = &[&auth_seeds[..]];
+
+  let transfer_ctx = CpiContext::new_with_signer(
+    ctx.accounts.token_program.to_account_info(),
+    TransferChecked {
+      from: ctx.accounts.reward_vault.to_account_info(),
+      mint: ctx.accounts.reward_mint.to_account_info(),
+      to: ctx.accounts.user_reward_token.to_account_info(),
+      authority: ctx.accounts.pool_authority.to_account_info(),
+    },
+    signer,
+  );
+  transfer_checked(transfer_ctx, payout, ctx.accounts.reward_mint.decimals)?;
+
+  user_reward.pending_reward = user_reward
+    .pending_reward
+    .checked_sub(payout)
+    .ok_or(AMMError::InvalidAmount)?;
+
+  msg!("Claimed {} reward tokens", payout);
+
+  emit!(RewardClaimedEvent {
+    config: config_key,
+    user: ctx.accounts.user.key(),
+    amount: payout,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,142 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::DepositEvent},
+    state::{Config, PoolState},
+    utils::math::lp_for_proportional_deposit,
+  },
+  anchor_lang::{prelude::*, solana_program::program_option::COption},
+  anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, Mint as MintInterface, MintTo, TokenAccount, TokenInterface},
+  },
+};
+
+#[derive(Accounts)]
+pub struct CompoundProtocolFees<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized,
+    constraint = !config.locked @ AMMError::PoolLocked
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::LP_MINT, config.key().as_ref()],
+    bump = config.lp_bump,
+    constraint = lp_mint.mint_authority == COption::Some(pool_authority.key())
+      @ AMMError::InvalidLpMintAuthority
+  )]
+  pub lp_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    init_if_needed,
+    payer = authority,
+    associated_token::mint = lp_mint,
+    associated_token::authority = authority,
+    associated_token::token_program = token_program_lp,
+  )]
+  pub authority_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program_lp: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+// Reinvests accrued protocol fees as liquidity owned by the authority. The fee tokens are
+// already in the vaults, so nothing is transferred: the ratio-matched part of the fees moves
+// from the protocol counters into the reserves and LP is minted for it at the current ratio,
+// like a proportional deposit. The unmatched remainder, and anything queued for withdrawal,
+// stays accrued
+pub fn handler(ctx: Context<CompoundProtocolFees>) -> Result<()> {
+  let config = &ctx.accounts.config;
+  let pool_state = &mut ctx.accounts.pool_state;
+
+  let available = (
+    pool_state
+      .protocol_fees_x
+      .saturating_sub(config.queued_fee_x),
+    pool_state
+      .protocol_fees_y
+      .saturating_sub(config.queued_fee_y),
+  );
+  let (lp_tokens_to_mint, (amount_x, amount_y)) = lp_for_proportional_deposit(
+    available,
+    (pool_state.reserve_x, pool_state.reserve_y),
+    pool_state.lp_supply,
+  )?;
+  require!(lp_tokens_to_mint > 0, AMMError::InvalidAmount);
+
+  let config_key = config.key();
+  let auth_seeds = &[seed_prefix::AUTH, config_key.as_ref(), &[config.auth_bump]];
+  let signer = &[&auth_seeds[..]];
+
+  let mint_ctx = CpiContext::new_with_signer(
+    ctx.accounts.token_program_lp.to_account_info(),
+    MintTo {
+      mint: ctx.accounts.lp_mint.to_account_info(),
+      to: ctx.accounts.authority_lp_token.to_account_info(),
+      authority: ctx.accounts.pool_authority.to_account_info(),
+    },
+    signer,
+  );
+  mint_to(mint_ctx, lp_tokens_to_mint)?;
+
+  // Matched amounts never exceed what was available, so the counters cannot underflow
+  pool_state.protocol_fees_x = pool_state
+    .protocol_fees_x
+    .checked_sub(amount_x)
+    .ok_or(AMMError::InvalidAmount)?;
+  pool_state.protocol_fees_y = pool_state
+    .protocol_fees_y
+    .checked_sub(amount_y)
+    .ok_or(AMMError::InvalidAmount)?;
+  pool_state.reserve_x = pool_state
+    .reserve_x
+    .checked_add(amount_x)
+    .ok_or(AMMError::InvalidAmount)?;
+  pool_state.reserve_y = pool_state
+    .reserve_y
+    .checked_add(amount_y)
+    .ok_or(AMMError::InvalidAmount)?;
+  pool_state.lp_supply = pool_state
+    .lp_supply
+    .checked_add(lp_tokens_to_mint)
+    .ok_or(AMMError::InvalidAmount)?;
+
+  msg!(
+    "Compounded {} X / {} Y of protocol fees into {} LP",
+    amount_x,
+    amount_y,
+    lp_tokens_to_mint
+  );
+
+  emit!(DepositEvent {
+    config: config_key,
+    user: ctx.accounts.authority.key(),
+    amount_x,
+    amount_y,
+    lp_minted: lp_tokens_to_mint,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
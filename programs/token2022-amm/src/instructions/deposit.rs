@@ -1,9 +1,19 @@
 use {
   crate::{
-    common::error::AMMError,
-    state::{Config, PoolState},
+    common::{
+      constant::{BPS_DENOMINATOR, MAX_INITIAL_PRICE_RATIO, MIN_INITIAL_LP},
+      error::AMMError,
+      event::DepositEvent,
+    },
+    state::{AuthorityRegistry, Config, PoolState},
+    utils::{
+      math::{div_ceil, ensure_product_non_decreasing, imbalance_lp, proportional_lp, sqrt_lp},
+      token::{
+        calculate_transfer_fee_excluded_amount, is_native_mint, reverify_pool_mints, wrap_lamports,
+      },
+    },
   },
-  anchor_lang::prelude::*,
+  anchor_lang::{prelude::*, solana_program::program_option::COption},
   anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
@@ -11,6 +21,7 @@ use {
       TransferChecked,
     },
   },
+  std::cmp::Ordering,
 };
 
 #[derive(Accounts)]
@@ -32,29 +43,36 @@ pub struct Deposit<'info> {
     )]
   pub pool_state: Box<Account<'info, PoolState>>,
 
-  /// CHECK: PDA authority for the pool
+  /// CHECK: PDA authority for the pool, derived from the stored bump so a mismatch is
+  /// reported before the LP mint CPI it signs
   #[account(
-        seeds = [b"auth", config.key().as_ref()],
-        bump = config.auth_bump
+        constraint = config.pool_authority_key(&config.key())? == pool_authority.key()
+          @ AMMError::InvalidAuthorityBump
     )]
   pub pool_authority: UncheckedAccount<'info>,
 
+  /// CHECK: Owner of the pool vaults
+  #[account(address = config.vault_authority)]
+  pub vault_authority: UncheckedAccount<'info>,
+
   pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
   pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
 
   #[account(
         mut,
-        associated_token::mint = mint_x,
-        associated_token::authority = pool_authority,
-        associated_token::token_program = token_program_x,
+        address = pool_state.vault_x,
+        token::mint = mint_x,
+        token::authority = vault_authority,
+        token::token_program = token_program_x,
     )]
   pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(
         mut,
-        associated_token::mint = mint_y,
-        associated_token::authority = pool_authority,
-        associated_token::token_program = token_program_y,
+        address = pool_state.vault_y,
+        token::mint = mint_y,
+        token::authority = vault_authority,
+        token::token_program = token_program_y,
     )]
   pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -77,7 +95,9 @@ pub struct Deposit<'info> {
   #[account(
         mut,
         seeds = [b"lp_mint", config.key().as_ref()],
-        bump = config.lp_bump
+        bump = config.lp_bump,
+        constraint = lp_mint.mint_authority == COption::Some(pool_authority.key())
+          @ AMMError::InvalidLpMintAuthority
     )]
   pub lp_mint: Box<InterfaceAccount<'info, MintInterface>>,
 
@@ -90,6 +110,13 @@ pub struct Deposit<'info> {
     )]
   pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
+  // Kill switch shared by every pool of the config authority, checked when supplied
+  #[account(
+        seeds = [b"registry", config.authority.as_ref()],
+        bump = registry.bump,
+    )]
+  pub registry: Option<Box<Account<'info, AuthorityRegistry>>>,
+
   pub token_program_x: Interface<'info, TokenInterface>,
   pub token_program_y: Interface<'info, TokenInterface>,
   pub token_program_lp: Interface<'info, TokenInterface>,
@@ -97,7 +124,70 @@ pub struct Deposit<'info> {
   pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out: u64) -> Result<()> {
+// Reserves the client quoted the deposit against, and how far the pool ratio may move from
+// them before the deposit is rejected
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct ExpectedReserveRatio {
+  pub reserve_x: u64,
+  pub reserve_y: u64,
+  pub max_reserve_ratio_deviation_bps: u16,
+}
+
+// Pool conditions the client quoted the deposit against. Each one supplied must still hold
+// or the deposit is refused; `None` skips that check
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct DepositGuards {
+  /// How far the deposit ratio may stray from the pool price, in bps
+  pub max_price_deviation_bps: Option<u16>,
+  pub expected_lp: Option<u64>,
+  pub expected_reserve_ratio: Option<ExpectedReserveRatio>,
+}
+
+pub fn handler(
+  ctx: Context<Deposit>,
+  amount_x: u64,
+  amount_y: u64,
+  min_lp_out: u64,
+  guards: DepositGuards,
+  wrap_sol: bool,
+) -> Result<()> {
+  let DepositGuards {
+    max_price_deviation_bps,
+    expected_lp,
+    expected_reserve_ratio,
+  } = guards;
+
+  if let Some(registry) = &ctx.accounts.registry {
+    registry.ensure_active()?;
+  }
+
+  // Fund the wrapped SOL side from the user's lamports, so SOL can be deposited without
+  // wrapping it in a separate transaction first
+  if wrap_sol {
+    let (user_token, token_program, amount) = if is_native_mint(&ctx.accounts.mint_x.key()) {
+      (
+        &ctx.accounts.user_token_x,
+        &ctx.accounts.token_program_x,
+        amount_x,
+      )
+    } else if is_native_mint(&ctx.accounts.mint_y.key()) {
+      (
+        &ctx.accounts.user_token_y,
+        &ctx.accounts.token_program_y,
+        amount_y,
+      )
+    } else {
+      return Err(AMMError::InvalidMint.into());
+    };
+    wrap_lamports(
+      ctx.accounts.system_program.to_account_info(),
+      token_program.to_account_info(),
+      ctx.accounts.user.to_account_info(),
+      user_token.to_account_info(),
+      amount,
+    )?;
+  }
+
   let pool_state = &mut ctx.accounts.pool_state;
   let config = &ctx.accounts.config;
 
@@ -105,37 +195,22 @@ pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out:
   require!(amount_x > 0 && amount_y > 0, AMMError::InvalidAmount);
 
   // Check whitelist if enabled
-  if let Some(whitelist) = &config.white_list_lp {
-    let user_key = ctx.accounts.user.key();
-    require!(whitelist.contains(&user_key), AMMError::NotWhitelisted);
-  }
+  require!(
+    config.admits_depositor(&ctx.accounts.user.key()),
+    AMMError::NotWhitelisted
+  );
 
-  // Calculate actual amounts after transfer fees
-  let actual_amount_x = if let Some(epoch_transfer_fee) =
-    crate::utils::token::get_epoch_transfer_fee(&ctx.accounts.mint_x)?
-  {
-    let transfer_fee = epoch_transfer_fee
-      .calculate_fee(amount_x)
-      .ok_or(AMMError::TransferFeeCalculationError)?;
-    amount_x
-      .checked_sub(transfer_fee)
-      .ok_or(AMMError::InvalidAmount)?
-  } else {
-    amount_x
-  };
+  // Token-2022 mints can change after init, so they are checked again before taking new
+  // liquidity. Withdrawals skip this so LPs can always exit
+  reverify_pool_mints([
+    (&ctx.accounts.mint_x, config.mint_x_legacy),
+    (&ctx.accounts.mint_y, config.mint_y_legacy),
+  ])?;
 
-  let actual_amount_y = if let Some(epoch_transfer_fee) =
-    crate::utils::token::get_epoch_transfer_fee(&ctx.accounts.mint_y)?
-  {
-    let transfer_fee = epoch_transfer_fee
-      .calculate_fee(amount_y)
-      .ok_or(AMMError::TransferFeeCalculationError)?;
-    amount_y
-      .checked_sub(transfer_fee)
-      .ok_or(AMMError::InvalidAmount)?
-  } else {
-    amount_y
-  };
+  // Calculate actual amounts after transfer fees
+  let deposited_x = calculate_transfer_fee_excluded_amount(&ctx.accounts.mint_x, amount_x)?;
+  let deposited_y = calculate_transfer_fee_excluded_amount(&ctx.accounts.mint_y, amount_y)?;
+  let (actual_amount_x, actual_amount_y) = (deposited_x.amount, deposited_y.amount);
 
   let lp_tokens_to_mint = if pool_state.lp_supply == 0 {
     // Initial deposit - use geometric mean of actual amounts
@@ -143,40 +218,137 @@ pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out:
       actual_amount_x > 0 && actual_amount_y > 0,
       AMMError::InvalidAmount
     );
-    let initial_lp = (actual_amount_x as u128)
-      .checked_mul(actual_amount_y as u128)
-      .ok_or(AMMError::InvalidAmount)?
-      .integer_sqrt() as u64;
 
-    require!(initial_lp >= min_lp_out, AMMError::SlippageExceeded);
-    require!(initial_lp > 0, AMMError::InvalidAmount);
-    initial_lp
-  } else {
-    // Proportional deposit based on actual amounts
+    // The initial deposit sets the price and the LP unit. Its LP is bounded by capping the
+    // raw ratio between the sides, so extreme decimal mismatches can't seed an LP supply that
+    // is negligible next to one reserve, and by MIN_INITIAL_LP below; either would make later
+    // proportional mints round away most of a deposit
+    let min_amount = actual_amount_x.min(actual_amount_y);
+    let max_amount = actual_amount_x.max(actual_amount_y);
     require!(
-      pool_state.reserve_x > 0 && pool_state.reserve_y > 0,
-      AMMError::InsufficientLiquidity
+      max_amount as u128
+        <= (min_amount as u128)
+          .checked_mul(MAX_INITIAL_PRICE_RATIO)
+          .ok_or(AMMError::InvalidAmount)?,
+      AMMError::InitialLpOutOfBounds
     );
 
-    let lp_from_x = (actual_amount_x as u128)
-      .checked_mul(pool_state.lp_supply as u128)
-      .ok_or(AMMError::InvalidAmount)?
-      .checked_div(pool_state.reserve_x as u128)
-      .ok_or(AMMError::InvalidAmount)? as u64;
+    let initial_lp = u64::try_from(sqrt_lp(
+      (actual_amount_x as u128)
+        .checked_mul(actual_amount_y as u128)
+        .ok_or(AMMError::InvalidAmount)?,
+      config.lp_rounding,
+    ))
+    .map_err(|_| AMMError::MathOverflow)?;
+
+    require!(initial_lp >= MIN_INITIAL_LP, AMMError::InitialLpTooSmall);
+    require!(initial_lp >= min_lp_out, AMMError::DepositSlippageExceeded);
+    require!(initial_lp > 0, AMMError::InvalidAmount);
+    initial_lp
+  } else {
+    // Proportional deposit based on actual amounts. An empty reserve fails here, before the
+    // guards below divide by it
+    let (lp_from_x, lp_from_y) = proportional_lp(
+      (actual_amount_x, actual_amount_y),
+      (pool_state.reserve_x, pool_state.reserve_y),
+      pool_state.lp_supply,
+      config.lp_rounding,
+    )?;
+
+    // A swap landing just before the deposit shifts the ratio LP is minted at; min_lp_out
+    // alone can't tell that apart from the quote, so compare against the quoted reserves
+    if let Some(expected) = expected_reserve_ratio {
+      let deviation_bps = price_deviation_bps(
+        expected.reserve_x,
+        expected.reserve_y,
+        pool_state.reserve_x,
+        pool_state.reserve_y,
+      )?;
+      require!(
+        deviation_bps <= expected.max_reserve_ratio_deviation_bps as u128,
+        AMMError::ReserveRatioChanged
+      );
+    }
 
-    let lp_from_y = (actual_amount_y as u128)
-      .checked_mul(pool_state.lp_supply as u128)
-      .ok_or(AMMError::InvalidAmount)?
-      .checked_div(pool_state.reserve_y as u128)
-      .ok_or(AMMError::InvalidAmount)? as u64;
+    // Reject if the pool was already seeded at a materially different price than the one
+    // implied by the deposited amounts (e.g. a front-run initial deposit at a bad ratio)
+    if let Some(max_price_deviation_bps) = max_price_deviation_bps {
+      let deviation_bps = price_deviation_bps(
+        actual_amount_x,
+        actual_amount_y,
+        pool_state.reserve_x,
+        pool_state.reserve_y,
+      )?;
+      require!(
+        deviation_bps <= max_price_deviation_bps as u128,
+        AMMError::PriceDeviationExceeded
+      );
+    }
 
-    let lp_tokens = lp_from_x.min(lp_from_y);
-    require!(lp_tokens >= min_lp_out, AMMError::SlippageExceeded);
+    // The binding side is the one worth fewer LP at the pool ratio: all of it is deposited
+    // balanced, and the other side's amount beyond what that needs is the excess, credited
+    // as a single-sided add charged the imbalance fee. When both sides are worth the same LP
+    // neither is in excess: the deposit is proportional and mints exactly `balanced_lp`, with
+    // any remainder below one LP unit (from rounding) staying in the reserves as before
+    let balanced_lp = lp_from_x.min(lp_from_y);
+    let excess_side = match lp_from_x.cmp(&lp_from_y) {
+      Ordering::Greater => Some((pool_state.reserve_x, actual_amount_x)),
+      Ordering::Less => Some((pool_state.reserve_y, actual_amount_y)),
+      Ordering::Equal => None,
+    };
+
+    let lp_tokens = if let Some((reserve, actual_amount)) = excess_side {
+      // Round the amount used by the balanced part up, in the pool's favor
+      let amount_used = u64::try_from(div_ceil(
+        (balanced_lp as u128)
+          .checked_mul(reserve as u128)
+          .ok_or(AMMError::InvalidAmount)?,
+        pool_state.lp_supply as u128,
+      )?)
+      .map_err(|_| AMMError::MathOverflow)?;
+      let excess_amount = actual_amount.saturating_sub(amount_used);
+
+      let excess_lp = imbalance_lp(
+        excess_amount,
+        reserve
+          .checked_add(amount_used)
+          .ok_or(AMMError::InvalidAmount)?,
+        pool_state
+          .lp_supply
+          .checked_add(balanced_lp)
+          .ok_or(AMMError::InvalidAmount)?,
+        config.imbalance_fee_bps,
+      )?;
+      msg!(
+        "Imbalanced deposit: {} excess tokens credited {} LP",
+        excess_amount,
+        excess_lp
+      );
+
+      balanced_lp
+        .checked_add(excess_lp)
+        .ok_or(AMMError::InvalidAmount)?
+    } else {
+      balanced_lp
+    };
+    require!(lp_tokens >= min_lp_out, AMMError::DepositSlippageExceeded);
     require!(lp_tokens > 0, AMMError::InvalidAmount);
     lp_tokens
   };
 
+  // Routers that computed the LP off-chain can assert it; any drift between their math and
+  // the program's fails loudly here instead of minting a different amount
+  if let Some(expected_lp) = expected_lp {
+    require!(
+      lp_tokens_to_mint == expected_lp,
+      AMMError::UnexpectedLpAmount
+    );
+  }
+
+  config.ensure_within_position_limit(ctx.accounts.user_lp_token.amount, lp_tokens_to_mint)?;
+
   // Transfer tokens from user to vault
+  let vaults_before = (ctx.accounts.vault_x.amount, ctx.accounts.vault_y.amount);
   let transfer_x_ctx = CpiContext::new(
     ctx.accounts.token_program_x.to_account_info(),
     TransferChecked {
@@ -199,6 +371,24 @@ pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out:
   );
   transfer_checked(transfer_y_ctx, amount_y, ctx.accounts.mint_y.decimals)?;
 
+  // What actually arrived, net of whatever transfer fee the token program withheld
+  ctx.accounts.vault_x.reload()?;
+  ctx.accounts.vault_y.reload()?;
+  let received = (
+    ctx
+      .accounts
+      .vault_x
+      .amount
+      .checked_sub(vaults_before.0)
+      .ok_or(AMMError::InvalidAmount)?,
+    ctx
+      .accounts
+      .vault_y
+      .amount
+      .checked_sub(vaults_before.1)
+      .ok_or(AMMError::InvalidAmount)?,
+  );
+
   // Mint LP tokens to user
   let config_key = config.key();
   let auth_seeds = &[b"auth", config_key.as_ref(), &[config.auth_bump]];
@@ -216,6 +406,7 @@ pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out:
   mint_to(mint_ctx, lp_tokens_to_mint)?;
 
   // Update pool state with actual amounts after fees
+  let reserves_before = (pool_state.reserve_x, pool_state.reserve_y);
   pool_state.reserve_x = pool_state
     .reserve_x
     .checked_add(actual_amount_x)
@@ -228,6 +419,24 @@ pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out:
     .lp_supply
     .checked_add(lp_tokens_to_mint)
     .ok_or(AMMError::InvalidAmount)?;
+  // LP was minted against reserves credited with the fee-excluded amounts computed up
+  // front. The tokens that really reached the vaults must back at least that product
+  let backed_reserves = (
+    reserves_before
+      .0
+      .checked_add(received.0)
+      .ok_or(AMMError::InvalidAmount)?,
+    reserves_before
+      .1
+      .checked_add(received.1)
+      .ok_or(AMMError::InvalidAmount)?,
+  );
+  ensure_product_non_decreasing(
+    (pool_state.reserve_x, pool_state.reserve_y),
+    backed_reserves,
+  )?;
+  pool_state.record_transfer_fee(true, deposited_x.transfer_fee)?;
+  pool_state.record_transfer_fee(false, deposited_y.transfer_fee)?;
 
   msg!(
     "Deposited {} token X, {} token Y, minted {} LP tokens",
@@ -236,28 +445,39 @@ pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out:
     lp_tokens_to_mint
   );
 
-  Ok(())
-}
+  emit!(DepositEvent {
+    config: config.key(),
+    user: ctx.accounts.user.key(),
+    amount_x,
+    amount_y,
+    lp_minted: lp_tokens_to_mint,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
 
-// Helper trait for integer square root
-trait IntegerSquareRoot {
-  fn integer_sqrt(self) -> Self;
+  Ok(())
 }
 
-impl IntegerSquareRoot for u128 {
-  fn integer_sqrt(self) -> Self {
-    if self < 2 {
-      return self;
-    }
-
-    let mut x = self;
-    let mut y = (self + 1) / 2;
+// Relative difference, in basis points, between the pool price (reserve_y / reserve_x)
+// and the price implied by the deposited amounts (amount_y / amount_x)
+fn price_deviation_bps(
+  amount_x: u64,
+  amount_y: u64,
+  reserve_x: u64,
+  reserve_y: u64,
+) -> Result<u128> {
+  let pool_cross = (reserve_y as u128)
+    .checked_mul(amount_x as u128)
+    .ok_or(AMMError::InvalidAmount)?;
+  let expected_cross = (amount_y as u128)
+    .checked_mul(reserve_x as u128)
+    .ok_or(AMMError::InvalidAmount)?;
 
-    while y < x {
-      x = y;
-      y = (x + self / x) / 2;
-    }
+  let deviation_bps = pool_cross
+    .abs_diff(expected_cross)
+    .checked_mul(BPS_DENOMINATOR as u128)
+    .ok_or(AMMError::InvalidAmount)?
+    .checked_div(expected_cross)
+    .ok_or(AMMError::DivisionByZero)?;
 
-    x
-  }
+  Ok(deviation_bps)
 }
@@ -0,0 +1,328 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, BPS_DENOMINATOR},
+      error::AMMError,
+      event::DepositEvent,
+    },
+    instructions::{deposit::Deposit, swap::compute_swap},
+    state::Config,
+    utils::{
+      math::{compute_zap_swap_amount, ensure_product_non_decreasing, proportional_lp},
+      swap::{constant_product_out, min_swap_amount_in, SwapDirection},
+      token::{calculate_transfer_fee_excluded_amount, reverify_pool_mints},
+    },
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    mint_to, transfer_checked, Mint as MintInterface, MintTo, TransferChecked,
+  },
+};
+
+// Deposits both tokens at any ratio. The excess side is first swapped against the pool,
+// paying the swap fee and the output mint's transfer fee as a standalone swap would, so
+// the remainder goes in at the pool ratio and little is left as unbalanced dust
+pub fn handler(ctx: Context<Deposit>, amount_x: u64, amount_y: u64, min_lp_out: u64) -> Result<()> {
+  if let Some(registry) = &ctx.accounts.registry {
+    registry.ensure_active()?;
+  }
+
+  let pool_state = &mut ctx.accounts.pool_state;
+  let config = &ctx.accounts.config;
+
+  require!(amount_x > 0 || amount_y > 0, AMMError::InvalidAmount);
+
+  // Check whitelist if enabled
+  require!(
+    config.admits_depositor(&ctx.accounts.user.key()),
+    AMMError::NotWhitelisted
+  );
+
+  // Same mint re-check as `deposit`
+  reverify_pool_mints([
+    (&ctx.accounts.mint_x, config.mint_x_legacy),
+    (&ctx.accounts.mint_y, config.mint_y_legacy),
+  ])?;
+
+  // Balancing needs a pool price to balance against
+  require!(
+    pool_state.lp_supply > 0 && pool_state.reserve_x > 0 && pool_state.reserve_y > 0,
+    AMMError::EmptyReserves
+  );
+
+  let deposited_x = calculate_transfer_fee_excluded_amount(&ctx.accounts.mint_x, amount_x)?;
+  let deposited_y = calculate_transfer_fee_excluded_amount(&ctx.accounts.mint_y, amount_y)?;
+  let (actual_amount_x, actual_amount_y) = (deposited_x.amount, deposited_y.amount);
+
+  // The side whose share of the deposit is larger than its share of the pool is swapped
+  let x_in_excess = (actual_amount_x as u128)
+    .checked_mul(pool_state.reserve_y as u128)
+    .ok_or(AMMError::InvalidAmount)?
+    > (actual_amount_y as u128)
+      .checked_mul(pool_state.reserve_x as u128)
+      .ok_or(AMMError::InvalidAmount)?;
+
+  let (excess_amount, other_amount, reserve_in, reserve_out) = if x_in_excess {
+    (
+      actual_amount_x,
+      actual_amount_y,
+      pool_state.reserve_x,
+      pool_state.reserve_y,
+    )
+  } else {
+    (
+      actual_amount_y,
+      actual_amount_x,
+      pool_state.reserve_y,
+      pool_state.reserve_x,
+    )
+  };
+  let (direction, mint_in, mint_out, vault_out) = if x_in_excess {
+    (
+      SwapDirection::XToY,
+      &ctx.accounts.mint_x,
+      &ctx.accounts.mint_y,
+      &ctx.accounts.vault_y,
+    )
+  } else {
+    (
+      SwapDirection::YToX,
+      &ctx.accounts.mint_y,
+      &ctx.accounts.mint_x,
+      &ctx.accounts.vault_x,
+    )
+  };
+
+  // The internal swap pays the fee this user would pay on a standalone swap
+  let user = ctx.accounts.user.key();
+  let lp_balance = ctx.accounts.user_lp_token.amount;
+  let fee = config.swap_fee_for(&user, lp_balance, Clock::get()?.unix_timestamp)?;
+  let swap_amount = if other_amount == 0 {
+    compute_zap_swap_amount(reserve_in, excess_amount, fee)?
+  } else {
+    balance_swap_amount(
+      config,
+      fee,
+      excess_amount,
+      other_amount,
+      reserve_in,
+      reserve_out,
+      mint_out,
+    )?
+  };
+
+  // The swap itself is priced and checked exactly as `swap` would, guards included. One too
+  // small to trade is skipped and the deposit goes in as supplied
+  let tradable = swap_amount >= min_swap_amount_in(reserve_in, reserve_out)?
+    && constant_product_out(swap_amount, reserve_in, reserve_out, fee)? > 0;
+  let (swap_amount, reserve_in_after, reserve_out_after, received, protocol_fee, lp_fee) =
+    if tradable {
+      let amounts = compute_swap(
+        config,
+        pool_state,
+        direction,
+        &user,
+        lp_balance,
+        mint_in,
+        mint_out,
+        vault_out.amount,
+        swap_amount,
+        0,
+        None,
+      )?;
+      require!(
+        !config.breaks_circuit(amounts.price_move_bps),
+        AMMError::CircuitBreakerTripped
+      );
+
+      // The swapped tokens never leave the vaults, so the transfer fees a standalone swap
+      // would withhold stay there outside the reserves
+      (
+        swap_amount,
+        reserve_in
+          .checked_add(amounts.amount_in_to_reserve)
+          .ok_or(AMMError::InvalidAmount)?,
+        reserve_out
+          .checked_sub(amounts.amount_out_before_fees)
+          .ok_or(AMMError::InvalidAmount)?,
+        amounts
+          .amount_out
+          .checked_sub(amounts.transfer_fee_out)
+          .ok_or(AMMError::InvalidAmount)?,
+        amounts.protocol_fee,
+        amounts
+          .fee_amount
+          .checked_sub(amounts.protocol_fee)
+          .ok_or(AMMError::InvalidAmount)?,
+      )
+    } else {
+      (0, reserve_in, reserve_out, 0, 0, 0)
+    };
+
+  // Deposit after the internal swap
+  let deposit_in = excess_amount - swap_amount;
+  let deposit_out = other_amount
+    .checked_add(received)
+    .ok_or(AMMError::InvalidAmount)?;
+
+  let (lp_from_in, lp_from_out) = proportional_lp(
+    (deposit_in, deposit_out),
+    (reserve_in_after, reserve_out_after),
+    pool_state.lp_supply,
+    config.lp_rounding,
+  )?;
+  let lp_tokens_to_mint = lp_from_in.min(lp_from_out);
+
+  require!(
+    lp_tokens_to_mint >= min_lp_out,
+    AMMError::DepositSlippageExceeded
+  );
+  require!(lp_tokens_to_mint > 0, AMMError::InvalidAmount);
+  config.ensure_within_position_limit(ctx.accounts.user_lp_token.amount, lp_tokens_to_mint)?;
+
+  // Transfer tokens from user to vault
+  if amount_x > 0 {
+    let transfer_x_ctx = CpiContext::new(
+      ctx.accounts.token_program_x.to_account_info(),
+      TransferChecked {
+        from: ctx.accounts.user_token_x.to_account_info(),
+        mint: ctx.accounts.mint_x.to_account_info(),
+        to: ctx.accounts.vault_x.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+      },
+    );
+    transfer_checked(transfer_x_ctx, amount_x, ctx.accounts.mint_x.decimals)?;
+  }
+
+  if amount_y > 0 {
+    let transfer_y_ctx = CpiContext::new(
+      ctx.accounts.token_program_y.to_account_info(),
+      TransferChecked {
+        from: ctx.accounts.user_token_y.to_account_info(),
+        mint: ctx.accounts.mint_y.to_account_info(),
+        to: ctx.accounts.vault_y.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+      },
+    );
+    transfer_checked(transfer_y_ctx, amount_y, ctx.accounts.mint_y.decimals)?;
+  }
+
+  // Mint LP tokens to user
+  let config_key = config.key();
+  let auth_seeds = &[seed_prefix::AUTH, config_key.as_ref(), &[config.auth_bump]];
+  let signer = &[&auth_seeds[..]];
+
+  let mint_ctx = CpiContext::new_with_signer(
+    ctx.accounts.token_program_lp.to_account_info(),
+    MintTo {
+      mint: ctx.accounts.lp_mint.to_account_info(),
+      to: ctx.accounts.user_lp_token.to_account_info(),
+      authority: ctx.accounts.pool_authority.to_account_info(),
+    },
+    signer,
+  );
+  mint_to(mint_ctx, lp_tokens_to_mint)?;
+
+  // Any dust left after balancing stays in the reserves
+  let reserve_in_final = reserve_in_after
+    .checked_add(deposit_in)
+    .ok_or(AMMError::InvalidAmount)?;
+  let reserve_out_final = reserve_out_after
+    .checked_add(deposit_out)
+    .ok_or(AMMError::InvalidAmount)?;
+
+  let reserves_before = (pool_state.reserve_x, pool_state.reserve_y);
+  if x_in_excess {
+    pool_state.reserve_x = reserve_in_final;
+    pool_state.reserve_y = reserve_out_final;
+    pool_state.protocol_fees_x = pool_state
+      .protocol_fees_x
+      .checked_add(protocol_fee)
+      .ok_or(AMMError::InvalidAmount)?;
+  } else {
+    pool_state.reserve_y = reserve_in_final;
+    pool_state.reserve_x = reserve_out_final;
+    pool_state.protocol_fees_y = pool_state
+      .protocol_fees_y
+      .checked_add(protocol_fee)
+      .ok_or(AMMError::InvalidAmount)?;
+  }
+  pool_state.accrue_lp_fee(x_in_excess, lp_fee)?;
+  pool_state.lp_supply = pool_state
+    .lp_supply
+    .checked_add(lp_tokens_to_mint)
+    .ok_or(AMMError::InvalidAmount)?;
+  pool_state.record_transfer_fee(true, deposited_x.transfer_fee)?;
+  pool_state.record_transfer_fee(false, deposited_y.transfer_fee)?;
+  // The internal swap alone keeps the product from falling; the deposit only adds to it
+  ensure_product_non_decreasing(
+    reserves_before,
+    (pool_state.reserve_x, pool_state.reserve_y),
+  )?;
+
+  msg!(
+    "Balanced deposit swapped {} in for {} out, deposited {} token X, {} token Y, minted {} LP tokens",
+    swap_amount,
+    received,
+    amount_x,
+    amount_y,
+    lp_tokens_to_mint
+  );
+
+  emit!(DepositEvent {
+    config: config_key,
+    user: ctx.accounts.user.key(),
+    amount_x,
+    amount_y,
+    lp_minted: lp_tokens_to_mint,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
+
+// Largest swap of the excess side that leaves the deposit at or just above the pool
+// ratio. Swapping more only moves the imbalance to the other side, so the resulting LP is
+// maximal at this point. The balance condition is monotonic in the swap size, so a binary
+// search over [0, excess_amount] finds it. A one-sided deposit has the closed form of
+// `compute_zap_swap_amount` instead
+fn balance_swap_amount(
+  config: &Config,
+  fee: u16,
+  excess_amount: u64,
+  other_amount: u64,
+  reserve_in: u64,
+  reserve_out: u64,
+  mint_out: &InterfaceAccount<MintInterface>,
+) -> Result<u64> {
+  let mut best = 0;
+  let (mut low, mut high) = (1u64, excess_amount);
+
+  while low <= high {
+    let amount_in = low + (high - low) / 2;
+    let amount_out = constant_product_out(amount_in, reserve_in, reserve_out, fee)?;
+    let received = calculate_transfer_fee_excluded_amount(mint_out, amount_out)?.amount;
+    let protocol_fee = config.protocol_fee_for(
+      amount_in,
+      u64::try_from((amount_in as u128) * fee as u128 / BPS_DENOMINATOR as u128)
+        .map_err(|_| AMMError::MathOverflow)?,
+    )?;
+
+    // (excess - s) / (reserve_in + s) >= (other + received) / (reserve_out - out)
+    let excess_side = ((excess_amount - amount_in) as u128)
+      .checked_mul((reserve_out - amount_out) as u128)
+      .ok_or(AMMError::InvalidAmount)?;
+    let other_side = ((other_amount as u128) + received as u128)
+      .checked_mul((reserve_in as u128) + (amount_in - protocol_fee) as u128)
+      .ok_or(AMMError::InvalidAmount)?;
+
+    if excess_side >= other_side {
+      best = amount_in;
+      low = amount_in + 1;
+    } else {
+      high = amount_in - 1;
+    }
+  }
+
+  Ok(best)
+}
@@ -0,0 +1,158 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::FeeWithdrawalExecutedEvent},
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+      transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+    },
+  },
+};
+
+#[derive(Accounts)]
+pub struct ExecuteFeeWithdrawal<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: Owner of the pool vaults
+  #[account(address = config.vault_authority)]
+  pub vault_authority: UncheckedAccount<'info>,
+
+  #[account(address = config.mint_x @ AMMError::InvalidMint)]
+  pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
+  #[account(address = config.mint_y @ AMMError::InvalidMint)]
+  pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    mut,
+    address = pool_state.vault_x,
+    token::mint = mint_x,
+    token::authority = vault_authority,
+    token::token_program = token_program_x,
+  )]
+  pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    address = pool_state.vault_y,
+    token::mint = mint_y,
+    token::authority = vault_authority,
+    token::token_program = token_program_y,
+  )]
+  pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  /// CHECK: Wallet the fees are paid to, picked by the authority
+  pub recipient: UncheckedAccount<'info>,
+
+  // Created on first use, with rent paid by the authority
+  #[account(
+    init_if_needed,
+    payer = authority,
+    associated_token::mint = mint_x,
+    associated_token::authority = recipient,
+    associated_token::token_program = token_program_x,
+  )]
+  pub recipient_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = authority,
+    associated_token::mint = mint_y,
+    associated_token::authority = recipient,
+    associated_token::token_program = token_program_y,
+  )]
+  pub recipient_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program_x: Interface<'info, TokenInterface>,
+  pub token_program_y: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+// Second step of withdrawing protocol fees: moves the queued amounts out of the vaults to the
+// recipient's associated token accounts once their timelock has passed, and clears the queue
+pub fn handler(ctx: Context<ExecuteFeeWithdrawal>) -> Result<()> {
+  let config = &mut ctx.accounts.config;
+  let pool_state = &mut ctx.accounts.pool_state;
+
+  let (amount_x, amount_y) = config.take_queued_fee_withdrawal(Clock::get()?.unix_timestamp)?;
+
+  // Queued amounts were checked against the accrued fees, which only shrink here
+  pool_state.protocol_fees_x = pool_state
+    .protocol_fees_x
+    .checked_sub(amount_x)
+    .ok_or(AMMError::InsufficientLiquidity)?;
+  pool_state.protocol_fees_y = pool_state
+    .protocol_fees_y
+    .checked_sub(amount_y)
+    .ok_or(AMMError::InsufficientLiquidity)?;
+
+  let config_key = config.key();
+  let (vault_seed, vault_bump) = config.vault_authority_seed();
+  let auth_seeds = &[vault_seed, config_key.as_ref(), &[vault_bump]];
+  let signer = &[&auth_seeds[..]];
+
+  for (token_program, vault, mint, recipient, amount) in [
+    (
+      &ctx.accounts.token_program_x,
+      &ctx.accounts.vault_x,
+      &ctx.accounts.mint_x,
+      &ctx.accounts.recipient_x,
+      amount_x,
+    ),
+    (
+      &ctx.accounts.token_program_y,
+      &ctx.accounts.vault_y,
+      &ctx.accounts.mint_y,
+      &ctx.accounts.recipient_y,
+      amount_y,
+    ),
+  ] {
+    if amount == 0 {
+      continue;
+    }
+
+    let transfer_ctx = CpiContext::new_with_signer(
+      token_program.to_account_info(),
+      TransferChecked {
+        from: vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: recipient.to_account_info(),
+        authority: ctx.accounts.vault_authority.to_account_info(),
+      },
+      signer,
+    );
+    transfer_checked(transfer_ctx, amount, mint.decimals)?;
+  }
+
+  msg!("Withdrew protocol fees of {} X, {} Y", amount_x, amount_y);
+
+  emit!(FeeWithdrawalExecutedEvent {
+    config: config_key,
+    amount_x,
+    amount_y,
+    recipient_x: ctx.accounts.recipient_x.key(),
+    recipient_y: ctx.accounts.recipient_y.key(),
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
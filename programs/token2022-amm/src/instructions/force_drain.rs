@@ -0,0 +1,133 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::PoolStuckEvent},
+    state::{Config, PoolState},
+  },
+  anchor_lang::{prelude::*, solana_program::program_option::COption},
+  anchor_spl::token_interface::{
+    thaw_account, Mint as MintInterface, ThawAccount, TokenAccount, TokenInterface,
+  },
+};
+
+#[derive(Accounts)]
+pub struct ForceDrain<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(address = config.mint_x @ AMMError::InvalidMint)]
+  pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
+  #[account(address = config.mint_y @ AMMError::InvalidMint)]
+  pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    mut,
+    address = pool_state.vault_x,
+    token::mint = mint_x,
+    token::token_program = token_program_x,
+  )]
+  pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    address = pool_state.vault_y,
+    token::mint = mint_y,
+    token::token_program = token_program_y,
+  )]
+  pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program_x: Interface<'info, TokenInterface>,
+  pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+// Last resort for pools whose vaults were frozen by the mint's freeze authority. Vaults the
+// pool authority can thaw are thawed; any vault left frozen marks the pool stuck and locks
+// it, so deposits, swaps and withdrawals stop against a pool that cannot move funds. Real
+// recovery of a frozen vault depends on the mint's freeze authority thawing it.
+pub fn handler(ctx: Context<ForceDrain>) -> Result<()> {
+  require!(
+    ctx.accounts.vault_x.is_frozen() || ctx.accounts.vault_y.is_frozen(),
+    AMMError::VaultsNotFrozen
+  );
+
+  let config_key = ctx.accounts.config.key();
+  let auth_seeds = &[
+    seed_prefix::AUTH,
+    config_key.as_ref(),
+    &[ctx.accounts.config.auth_bump],
+  ];
+  let signer = &[&auth_seeds[..]];
+
+  let mut frozen = [false; 2];
+  for (index, (vault, mint, token_program)) in [
+    (
+      &ctx.accounts.vault_x,
+      &ctx.accounts.mint_x,
+      &ctx.accounts.token_program_x,
+    ),
+    (
+      &ctx.accounts.vault_y,
+      &ctx.accounts.mint_y,
+      &ctx.accounts.token_program_y,
+    ),
+  ]
+  .into_iter()
+  .enumerate()
+  {
+    if !vault.is_frozen() {
+      continue;
+    }
+
+    if mint.freeze_authority == COption::Some(ctx.accounts.pool_authority.key()) {
+      let thaw_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        ThawAccount {
+          account: vault.to_account_info(),
+          mint: mint.to_account_info(),
+          authority: ctx.accounts.pool_authority.to_account_info(),
+        },
+        signer,
+      );
+      thaw_account(thaw_ctx)?;
+      msg!("Thawed vault {}", vault.key());
+    } else {
+      msg!(
+        "Vault {} is frozen and cannot be thawed by the pool",
+        vault.key()
+      );
+      frozen[index] = true;
+    }
+  }
+
+  if frozen.contains(&true) {
+    ctx.accounts.config.mark_stuck();
+
+    emit!(PoolStuckEvent {
+      config: config_key,
+      vault_x_frozen: frozen[0],
+      vault_y_frozen: frozen[1],
+      timestamp: Clock::get()?.unix_timestamp,
+    });
+  }
+
+  Ok(())
+}
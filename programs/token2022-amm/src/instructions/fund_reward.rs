@@ -0,0 +1,70 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::RewardFundedEvent},
+    state::{Config, RewardConfig},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+  },
+};
+
+#[derive(Accounts)]
+pub struct FundReward<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::REWARD, config.key().as_ref()],
+    bump = reward_config.bump,
+    has_one = reward_mint,
+    has_one = reward_vault
+  )]
+  pub reward_config: Box<Account<'info, RewardConfig>>,
+
+  pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(mut)]
+  pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    token::mint = reward_mint,
+    token::authority = authority,
+    token::token_program = token_program,
+  )]
+  pub authority_reward_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn handler(ctx: Context<FundReward>, amount: u64) -> Result<()> {
+  require!(amount > 0, AMMError::InvalidAmount);
+
+  let transfer_ctx = CpiContext::new(
+    ctx.accounts.token_program.to_account_info(),
+    TransferChecked {
+      from: ctx.accounts.authority_reward_token.to_account_info(),
+      mint: ctx.accounts.reward_mint.to_account_info(),
+      to: ctx.accounts.reward_vault.to_account_info(),
+      authority: ctx.accounts.authority.to_account_info(),
+    },
+  );
+  transfer_checked(transfer_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+  msg!("Funded reward vault with {} tokens", amount);
+
+  emit!(RewardFundedEvent {
+    config: ctx.accounts.config.key(),
+    amount,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,48 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+    utils::math::annualized_fee_bps,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct GetApr<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+}
+
+// The program has no common unit to value X against Y in, so each token gets its own yield:
+// LP fees earned in that token against its current reserve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolApr {
+  /// LP fees earned in token X since creation, annualized, in basis points of `reserve_x`
+  pub apr_x_bps: u64,
+  /// LP fees earned in token Y since creation, annualized, in basis points of `reserve_y`
+  pub apr_y_bps: u64,
+  /// Seconds since the pool was created, the period the estimate extrapolates from
+  pub elapsed: i64,
+}
+
+pub fn handler(ctx: Context<GetApr>) -> Result<PoolApr> {
+  let pool_state = &ctx.accounts.pool_state;
+  let elapsed = Clock::get()?
+    .unix_timestamp
+    .saturating_sub(ctx.accounts.config.created_at);
+
+  Ok(PoolApr {
+    apr_x_bps: annualized_fee_bps(pool_state.cumulative_fee_x, pool_state.reserve_x, elapsed),
+    apr_y_bps: annualized_fee_bps(pool_state.cumulative_fee_y, pool_state.reserve_y, elapsed),
+    elapsed,
+  })
+}
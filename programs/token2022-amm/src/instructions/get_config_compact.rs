@@ -0,0 +1,37 @@
+use {
+  crate::{common::constant::seed_prefix, state::Config},
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct GetConfigCompact<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+}
+
+/// Trading parameters of a pool without the whitelist and other bulky fields, for
+/// clients polling a pool often
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConfigCompact {
+  pub seed: u64,
+  pub mint_x: Pubkey,
+  pub mint_y: Pubkey,
+  /// Configured swap fee in bps
+  pub fee: u16,
+  pub locked: bool,
+}
+
+pub fn handler(ctx: Context<GetConfigCompact>) -> Result<ConfigCompact> {
+  let config = &ctx.accounts.config;
+
+  Ok(ConfigCompact {
+    seed: config.seed,
+    mint_x: config.mint_x,
+    mint_y: config.mint_y,
+    fee: config.fee,
+    locked: config.locked,
+  })
+}
@@ -0,0 +1,55 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::TokenAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(amount_in: u64, user: Pubkey)]
+pub struct GetEffectiveFee<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  // Same LP holdings proof a swap by `user` would pass
+  #[account(
+    constraint = user_lp_token.mint == pool_state.lp_mint,
+    constraint = user_lp_token.owner == user,
+  )]
+  pub user_lp_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
+
+// Resolves the swap fee in bps `user` would pay, using the same resolution as `swap`. The
+// fee does not depend on the swap size today; `amount_in` keeps the interface stable for
+// size-dependent components.
+pub fn handler(ctx: Context<GetEffectiveFee>, amount_in: u64, user: Pubkey) -> Result<u16> {
+  let lp_balance = ctx
+    .accounts
+    .user_lp_token
+    .as_ref()
+    .map_or(0, |user_lp_token| user_lp_token.amount);
+  let fee = ctx
+    .accounts
+    .config
+    .swap_fee_for(&user, lp_balance, Clock::get()?.unix_timestamp)?;
+
+  msg!(
+    "Effective fee for {} swapping {}: {} bps",
+    user,
+    amount_in,
+    fee
+  );
+
+  Ok(fee)
+}
@@ -0,0 +1,64 @@
+use {
+  crate::{common::constant::seed_prefix, state::Config},
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct GetFeeSchedule<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+}
+
+/// Every fee component of a pool, all in bps unless noted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct FeeSchedule {
+  /// Configured swap fee, used once the launch decay is over or when it is disabled
+  pub fee: u16,
+  /// Swap fee charged right now, before per-user discounts
+  pub current_base_fee: u16,
+  pub launch_fee: u16,
+  pub target_fee: u16,
+  /// Unix timestamp the launch decay starts from
+  pub created_at: i64,
+  /// Length of the launch decay in seconds (0 when disabled)
+  pub decay_duration: i64,
+  /// Fee on the excess side of an imbalanced deposit
+  pub imbalance_fee_bps: u16,
+  /// Share of the swap fee routed to the protocol
+  pub protocol_fee_bps: u16,
+  /// Smallest swap input the protocol share applies to, in input token units
+  pub protocol_fee_min_amount: u64,
+  /// LP balance a swapper needs to get the LP discount
+  pub lp_discount_threshold: u64,
+  /// Discount on the swap fee for LP holders
+  pub lp_discount_bps: u16,
+  /// Market maker paying `mm_fee_bps` instead of the swap fee (default key: none)
+  pub privileged_mm: Pubkey,
+  pub mm_fee_bps: u16,
+  /// Floor the resolved swap fee never goes below
+  pub min_effective_fee_bps: u16,
+}
+
+pub fn handler(ctx: Context<GetFeeSchedule>) -> Result<FeeSchedule> {
+  let config = &ctx.accounts.config;
+
+  Ok(FeeSchedule {
+    fee: config.fee,
+    current_base_fee: config.base_fee(Clock::get()?.unix_timestamp),
+    launch_fee: config.launch_fee,
+    target_fee: config.target_fee,
+    created_at: config.created_at,
+    decay_duration: config.decay_duration,
+    imbalance_fee_bps: config.imbalance_fee_bps,
+    protocol_fee_bps: config.protocol_fee_bps,
+    protocol_fee_min_amount: config.protocol_fee_min_amount,
+    lp_discount_threshold: config.lp_discount_threshold,
+    lp_discount_bps: config.lp_discount_bps,
+    privileged_mm: config.privileged_mm,
+    mm_fee_bps: config.mm_fee_bps,
+    min_effective_fee_bps: config.min_effective_fee_bps,
+  })
+}
@@ -0,0 +1,50 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct GetFeeStats<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct FeeStats {
+  /// Swap fees earned by LPs since creation, in raw token X units
+  pub cumulative_fee_x: u128,
+  /// Swap fees earned by LPs since creation, in raw token Y units
+  pub cumulative_fee_y: u128,
+  /// Token-2022 transfer fees withheld on vault transfers since creation, in raw token X units
+  pub cumulative_transfer_fee_x: u128,
+  /// Token-2022 transfer fees withheld on vault transfers since creation, in raw token Y units
+  pub cumulative_transfer_fee_y: u128,
+  /// Seconds since the pool was created, the period the counters cover
+  pub elapsed: i64,
+}
+
+pub fn handler(ctx: Context<GetFeeStats>) -> Result<FeeStats> {
+  let pool_state = &ctx.accounts.pool_state;
+
+  Ok(FeeStats {
+    cumulative_fee_x: pool_state.cumulative_fee_x,
+    cumulative_fee_y: pool_state.cumulative_fee_y,
+    cumulative_transfer_fee_x: pool_state.cumulative_transfer_fee_x,
+    cumulative_transfer_fee_y: pool_state.cumulative_transfer_fee_y,
+    elapsed: Clock::get()?
+      .unix_timestamp
+      .saturating_sub(ctx.accounts.config.created_at),
+  })
+}
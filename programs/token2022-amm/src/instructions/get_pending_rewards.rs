@@ -0,0 +1,54 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError},
+    state::{Config, RewardConfig, UserReward},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct GetPendingRewards<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  // Absent until the pool's rewards are initialized
+  #[account(
+    seeds = [seed_prefix::REWARD, config.key().as_ref()],
+    bump = reward_config.bump
+  )]
+  pub reward_config: Option<Box<Account<'info, RewardConfig>>>,
+
+  // Absent until `user` first stakes
+  pub user_reward: Option<Box<Account<'info, UserReward>>>,
+}
+
+// Reward `user` would be owed by a `claim_reward` now, settled the same way without
+// writing the checkpoint. Zero without a reward schedule or before the user's first stake.
+// The claim itself pays no more than the reward vault holds
+pub fn handler(ctx: Context<GetPendingRewards>, user: Pubkey) -> Result<u64> {
+  let (reward_config, user_reward) = match (&ctx.accounts.reward_config, &ctx.accounts.user_reward)
+  {
+    (Some(reward_config), Some(user_reward)) => (reward_config, user_reward),
+    (None, _) => {
+      msg!("Rewards are not initialized for this pool");
+      return Ok(0);
+    }
+    (Some(_), None) => return Ok(0),
+  };
+  require!(
+    user_reward.user == user && user_reward.reward_config == reward_config.key(),
+    AMMError::Unauthorized
+  );
+
+  let reward_per_lp = reward_config.reward_per_lp(Clock::get()?.unix_timestamp)?;
+  let mut settled = UserReward::clone(user_reward);
+  settled.settle(reward_per_lp)?;
+
+  msg!("Pending reward for {}: {}", user, settled.pending_reward);
+
+  Ok(settled.pending_reward)
+}
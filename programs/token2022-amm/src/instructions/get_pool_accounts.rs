@@ -0,0 +1,63 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct GetPoolAccounts<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolAddresses {
+  pub config: Pubkey,
+  pub pool_state: Pubkey,
+  pub pool_authority: Pubkey,
+  /// Owner of the vaults, the pool authority unless the pool has a separate vault authority
+  pub vault_authority: Pubkey,
+  pub lp_mint: Pubkey,
+  pub mint_x: Pubkey,
+  pub mint_y: Pubkey,
+  /// Current vaults, which may differ from the derived ATAs after a migration or for PDA vaults
+  pub vault_x: Pubkey,
+  pub vault_y: Pubkey,
+}
+
+// Every account a client needs to build pool instructions, read from the pool instead of
+// re-derived client-side
+pub fn handler(ctx: Context<GetPoolAccounts>) -> Result<PoolAddresses> {
+  let config = &ctx.accounts.config;
+  let pool_state = &ctx.accounts.pool_state;
+  let config_key = config.key();
+
+  let pool_authority = Pubkey::create_program_address(
+    &[seed_prefix::AUTH, config_key.as_ref(), &[config.auth_bump]],
+    ctx.program_id,
+  )
+  .map_err(ProgramError::from)?;
+
+  Ok(PoolAddresses {
+    config: config_key,
+    pool_state: pool_state.key(),
+    pool_authority,
+    vault_authority: config.vault_authority,
+    lp_mint: pool_state.lp_mint,
+    mint_x: config.mint_x,
+    mint_y: config.mint_y,
+    vault_x: pool_state.vault_x,
+    vault_y: pool_state.vault_y,
+  })
+}
@@ -0,0 +1,22 @@
+use {
+  crate::{common::constant::seed_prefix, state::MintPoolIndex},
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct GetPoolsForMint<'info> {
+  #[account(
+    seeds = [seed_prefix::MINT_POOL_INDEX, mint.as_ref()],
+    bump = mint_pool_index.bump
+  )]
+  pub mint_pool_index: Box<Account<'info, MintPoolIndex>>,
+}
+
+// Configs of the indexed pools trading `mint`, in creation order
+pub fn handler(ctx: Context<GetPoolsForMint>, mint: Pubkey) -> Result<Vec<Pubkey>> {
+  let pools = ctx.accounts.mint_pool_index.pools.clone();
+  msg!("{} indexed pools for mint {}", pools.len(), mint);
+
+  Ok(pools)
+}
@@ -0,0 +1,40 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+    utils::swap::{price_impact_bps, SwapDirection},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::Mint as MintInterface,
+};
+
+#[derive(Accounts)]
+pub struct GetPriceImpact<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  pub mint_in: Box<InterfaceAccount<'info, MintInterface>>,
+  pub mint_out: Box<InterfaceAccount<'info, MintInterface>>,
+}
+
+// Price impact in bps a swap of `amount_in` from `mint_in` to `mint_out` would have, for
+// slippage previews. Fees are left out; `get_effective_fee` reports those
+pub fn handler(ctx: Context<GetPriceImpact>, amount_in: u64) -> Result<u16> {
+  let direction = SwapDirection::resolve(
+    &ctx.accounts.config,
+    &ctx.accounts.mint_in.key(),
+    &ctx.accounts.mint_out.key(),
+  )?;
+  let (reserve_in, reserve_out) = direction.reserves(&ctx.accounts.pool_state);
+
+  price_impact_bps(reserve_in, reserve_out, amount_in)
+}
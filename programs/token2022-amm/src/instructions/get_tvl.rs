@@ -0,0 +1,80 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, PRICE_PRECISION},
+      error::AMMError,
+    },
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::Mint as MintInterface,
+};
+
+#[derive(Accounts)]
+pub struct GetTvl<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = mint_x,
+    has_one = mint_y
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
+  pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolTvl {
+  pub reserve_x: u64,
+  pub reserve_y: u64,
+  pub decimals_x: u8,
+  pub decimals_y: u8,
+  /// Decimals-normalized price of one X in Y, scaled by `PRICE_PRECISION` (0 for an empty pool)
+  pub price_x_in_y: u128,
+}
+
+pub fn handler(ctx: Context<GetTvl>) -> Result<PoolTvl> {
+  let pool_state = &ctx.accounts.pool_state;
+  let decimals_x = ctx.accounts.mint_x.decimals;
+  let decimals_y = ctx.accounts.mint_y.decimals;
+
+  let price_x_in_y = if pool_state.reserve_x == 0 || pool_state.reserve_y == 0 {
+    0
+  } else {
+    // (reserve_y / 10^decimals_y) / (reserve_x / 10^decimals_x)
+    (pool_state.reserve_y as u128)
+      .checked_mul(
+        10u128
+          .checked_pow(decimals_x as u32)
+          .ok_or(AMMError::InvalidAmount)?,
+      )
+      .ok_or(AMMError::InvalidAmount)?
+      .checked_mul(PRICE_PRECISION)
+      .ok_or(AMMError::InvalidAmount)?
+      .checked_div(
+        (pool_state.reserve_x as u128)
+          .checked_mul(
+            10u128
+              .checked_pow(decimals_y as u32)
+              .ok_or(AMMError::InvalidAmount)?,
+          )
+          .ok_or(AMMError::InvalidAmount)?,
+      )
+      .ok_or(AMMError::DivisionByZero)?
+  };
+
+  Ok(PoolTvl {
+    reserve_x: pool_state.reserve_x,
+    reserve_y: pool_state.reserve_y,
+    decimals_x,
+    decimals_y,
+    price_x_in_y,
+  })
+}
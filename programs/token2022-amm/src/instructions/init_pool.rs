@@ -1,11 +1,12 @@
 use {
   crate::{
     common::{
-      constant::{seed_prefix, DISCRIMINATOR},
+      constant::{seed_prefix, BPS_DENOMINATOR, DISCRIMINATOR, MAX_FEE_BPS},
       error::AMMError,
+      event::PoolInitializedEvent,
     },
-    state::{Config, InitConfigParams, InitPoolStateParams, PoolState, MAX_WHITE_LIST_LP},
-    utils::token::verify_supported_token_mint,
+    state::{Config, InitConfigParams, InitPoolStateParams, MintPoolIndex, PoolState},
+    utils::token::{get_epoch_transfer_fee, is_legacy_token_mint, verify_supported_token_mint},
   },
   anchor_lang::prelude::*,
   anchor_spl::{
@@ -38,7 +39,19 @@ pub struct InitializePool<'info> {
   )]
   pub pool_state: Box<Account<'info, PoolState>>,
 
+  // Neither trading mint can alias the LP mint or a vault: those are PDAs only this init
+  // creates, so before it they hold no mint and fail to load here, whatever the seed
   pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
+  // Checked here so a duplicate mint or a mismatched token program fails with its own
+  // error before the vault inits below trip over it. Distinctness goes first: one mint
+  // passed next to two different token programs is reported as a duplicate
+  #[account(
+    constraint = mint_y.key() != mint_x.key() @ AMMError::DuplicateMint,
+    constraint = *mint_x.to_account_info().owner == token_program_x.key()
+      @ AMMError::TokenProgramMismatch,
+    constraint = *mint_y.to_account_info().owner == token_program_y.key()
+      @ AMMError::TokenProgramMismatch,
+  )]
   pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
 
   #[account(
@@ -59,11 +72,15 @@ pub struct InitializePool<'info> {
   )]
   pub pool_authority: UncheckedAccount<'info>,
 
+  /// CHECK: Owner of the vaults, validated in the handler: the pool authority, or the
+  /// vault authority PDA when `separate_vault_authority` is set
+  pub vault_authority: UncheckedAccount<'info>,
+
   #[account(
     init,
     payer = authority,
     associated_token::mint = mint_x,
-    associated_token::authority = pool_authority,
+    associated_token::authority = vault_authority,
     associated_token::token_program = token_program_x,
   )]
   pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
@@ -72,11 +89,30 @@ pub struct InitializePool<'info> {
     init,
     payer = authority,
     associated_token::mint = mint_y,
-    associated_token::authority = pool_authority,
+    associated_token::authority = vault_authority,
     associated_token::token_program = token_program_y,
   )]
   pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
+  // Discovery indexes of each mint, the new pool is listed in those supplied
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = DISCRIMINATOR + MintPoolIndex::INIT_SPACE,
+    seeds = [seed_prefix::MINT_POOL_INDEX, mint_x.key().as_ref()],
+    bump
+  )]
+  pub mint_x_pool_index: Option<Box<Account<'info, MintPoolIndex>>>,
+
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = DISCRIMINATOR + MintPoolIndex::INIT_SPACE,
+    seeds = [seed_prefix::MINT_POOL_INDEX, mint_y.key().as_ref()],
+    bump
+  )]
+  pub mint_y_pool_index: Option<Box<Account<'info, MintPoolIndex>>>,
+
   pub token_program_x: Interface<'info, TokenInterface>,
   pub token_program_y: Interface<'info, TokenInterface>,
   pub token_program_lp: Interface<'info, TokenInterface>,
@@ -88,58 +124,36 @@ pub fn handler(
   ctx: Context<InitializePool>,
   seed: u64,
   fee: u16,
-  white_list_lp: Option<[Pubkey; MAX_WHITE_LIST_LP]>,
+  white_list_lp: Option<Vec<Pubkey>>,
+  separate_vault_authority: bool,
+  max_fee_bearing_sides: u8,
+  max_transfer_fee_bps: u16,
 ) -> Result<()> {
   let config = &mut ctx.accounts.config;
   let pool_state = &mut ctx.accounts.pool_state;
 
-  // Validate fee is within reasonable bounds (max 10% = 1000 basis points)
-  require!(fee <= 1000, AMMError::InvalidAmount);
-
-  // Verify both tokens are supported (legacy SPL or Token-2022 with allowed extensions)
-  let mint_x_supported = verify_supported_token_mint(&ctx.accounts.mint_x)?;
-  let mint_y_supported = verify_supported_token_mint(&ctx.accounts.mint_y)?;
-
-  require!(
-    mint_x_supported && mint_y_supported,
-    AMMError::NotAllowedTokenExtension
-  );
+  validate_pool_params(
+    fee,
+    &ctx.accounts.mint_x,
+    &ctx.accounts.mint_y,
+    max_fee_bearing_sides,
+    max_transfer_fee_bps,
+  )?;
 
-  // Ensure mint_x and mint_y are different
+  // Vaults are owned by the main authority unless a separate vault authority was asked for
+  let (vault_authority, vault_auth_bump) = if separate_vault_authority {
+    Pubkey::find_program_address(
+      &[seed_prefix::VAULT_AUTH, config.key().as_ref()],
+      ctx.program_id,
+    )
+  } else {
+    (ctx.accounts.pool_authority.key(), ctx.bumps.pool_authority)
+  };
   require!(
-    ctx.accounts.mint_x.key() != ctx.accounts.mint_y.key(),
-    AMMError::InvalidMint
+    ctx.accounts.vault_authority.key() == vault_authority,
+    AMMError::InvalidVaultAuthority
   );
 
-  // Additional security: check for consistent token programs
-  let mint_x_info = ctx.accounts.mint_x.to_account_info();
-  let mint_y_info = ctx.accounts.mint_y.to_account_info();
-
-  // Verify token programs match the mint owners
-  if *mint_x_info.owner == anchor_spl::token::Token::id() {
-    require!(
-      ctx.accounts.token_program_x.key() == anchor_spl::token::Token::id(),
-      AMMError::InvalidMint
-    );
-  } else {
-    require!(
-      ctx.accounts.token_program_x.key() == anchor_spl::token_2022::Token2022::id(),
-      AMMError::InvalidMint
-    );
-  }
-
-  if *mint_y_info.owner == anchor_spl::token::Token::id() {
-    require!(
-      ctx.accounts.token_program_y.key() == anchor_spl::token::Token::id(),
-      AMMError::InvalidMint
-    );
-  } else {
-    require!(
-      ctx.accounts.token_program_y.key() == anchor_spl::token_2022::Token2022::id(),
-      AMMError::InvalidMint
-    );
-  }
-
   let params_init_config: InitConfigParams = InitConfigParams {
     seed,
     authority: ctx.accounts.authority.key(),
@@ -150,16 +164,115 @@ pub fn handler(
     auth_bump: ctx.bumps.pool_authority,
     config_bump: ctx.bumps.config,
     lp_bump: ctx.bumps.lp_mint,
+    created_at: Clock::get()?.unix_timestamp,
+    vault_authority,
+    vault_auth_bump,
+    separate_vault_authority,
+    mint_x_legacy: is_legacy_token_mint(&ctx.accounts.mint_x),
+    mint_y_legacy: is_legacy_token_mint(&ctx.accounts.mint_y),
   };
-  config.init(params_init_config);
-
   let params_init_pool_state = InitPoolStateParams {
     config: config.key(),
     lp_mint: ctx.accounts.lp_mint.key(),
     vault_x: ctx.accounts.vault_x.key(),
     vault_y: ctx.accounts.vault_y.key(),
   };
+  init_pool_accounts(
+    config,
+    pool_state,
+    params_init_config,
+    params_init_pool_state,
+    [
+      (
+        ctx.accounts.mint_x_pool_index.as_deref_mut(),
+        ctx.bumps.mint_x_pool_index,
+      ),
+      (
+        ctx.accounts.mint_y_pool_index.as_deref_mut(),
+        ctx.bumps.mint_y_pool_index,
+      ),
+    ],
+  )
+}
+
+// Shared by every pool init path once its accounts exist: writes the config and pool state,
+// lists the pool in the discovery index of each mint whose index was supplied, and
+// announces it
+pub(crate) fn init_pool_accounts(
+  config: &mut Account<Config>,
+  pool_state: &mut Account<PoolState>,
+  params_init_config: InitConfigParams,
+  params_init_pool_state: InitPoolStateParams,
+  mint_pool_indexes: [(Option<&mut Account<MintPoolIndex>>, Option<u8>); 2],
+) -> Result<()> {
+  let fee = params_init_config.fee;
+  config.init(params_init_config)?;
   pool_state.init(params_init_pool_state);
 
+  let config_key = config.key();
+  for ((index, bump), mint) in mint_pool_indexes
+    .into_iter()
+    .zip([config.mint_x, config.mint_y])
+  {
+    if let (Some(index), Some(bump)) = (index, bump) {
+      index.add_pool(mint, config_key, bump)?;
+    }
+  }
+
+  emit!(PoolInitializedEvent {
+    config: config_key,
+    mint_x: config.mint_x,
+    mint_y: config.mint_y,
+    fee,
+    timestamp: config.created_at,
+  });
+
+  Ok(())
+}
+
+// Shared by every pool init path: fee bounds, supported mints and the operator's caps on
+// the mints' transfer fees. Distinct mints and their token programs are account
+// constraints
+pub(crate) fn validate_pool_params<'info>(
+  fee: u16,
+  mint_x: &InterfaceAccount<'info, MintInterface>,
+  mint_y: &InterfaceAccount<'info, MintInterface>,
+  max_fee_bearing_sides: u8,
+  max_transfer_fee_bps: u16,
+) -> Result<()> {
+  // Validate fee is within reasonable bounds
+  require!(fee <= MAX_FEE_BPS, AMMError::InvalidAmount);
+
+  // Verify both tokens are supported (legacy SPL or Token-2022 with allowed extensions)
+  let mint_x_supported = verify_supported_token_mint(mint_x)?;
+  let mint_y_supported = verify_supported_token_mint(mint_y)?;
+  require!(
+    mint_x_supported && mint_y_supported,
+    AMMError::NotAllowedTokenExtension
+  );
+
+  // Operators wanting simpler accounting can cap how many sides charge a transfer fee
+  require!(max_fee_bearing_sides <= 2, AMMError::InvalidAmount);
+  // They can also cap the transfer fee itself. Only the fee of the current epoch is seen
+  // here: the mint's fee authority can raise it later, which this check does not prevent
+  require!(
+    max_transfer_fee_bps <= BPS_DENOMINATOR,
+    AMMError::InvalidAmount
+  );
+  let mut fee_bearing_sides = 0u8;
+  for mint in [mint_x, mint_y] {
+    if let Some(transfer_fee) = get_epoch_transfer_fee(mint)? {
+      require!(
+        u16::from(transfer_fee.transfer_fee_basis_points) <= max_transfer_fee_bps,
+        AMMError::TransferFeeTooHigh
+      );
+      fee_bearing_sides += 1;
+    }
+  }
+  require!(
+    fee_bearing_sides <= max_fee_bearing_sides,
+    AMMError::TooManyFeeBearingMints
+  );
+
   Ok(())
 }
@@ -0,0 +1,178 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, DISCRIMINATOR},
+      error::AMMError,
+    },
+    instructions::init_pool::{init_pool_accounts, validate_pool_params},
+    state::{Config, InitConfigParams, InitPoolStateParams, MintPoolIndex, PoolState},
+    utils::token::is_legacy_token_mint,
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{Mint as MintInterface, TokenAccount, TokenInterface},
+};
+
+// Same as `InitializePool`, but the vaults are plain token accounts at PDAs seeded by
+// config + mint instead of associated token accounts of the pool authority
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct InitializePoolWithPdaVaults<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    init,
+    payer = authority,
+    space = DISCRIMINATOR + Config::INIT_SPACE,
+    seeds = [seed_prefix::CONFIG, seed.to_le_bytes().as_ref()],
+    bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    init,
+    payer = authority,
+    space = DISCRIMINATOR + PoolState::INIT_SPACE,
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  // Cannot alias the LP mint or a vault, for the same reason as in `InitializePool`
+  pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
+  // Checked here so a duplicate mint or a mismatched token program fails with its own
+  // error before the vault inits below trip over it. Distinctness goes first: one mint
+  // passed next to two different token programs is reported as a duplicate
+  #[account(
+    constraint = mint_y.key() != mint_x.key() @ AMMError::DuplicateMint,
+    constraint = *mint_x.to_account_info().owner == token_program_x.key()
+      @ AMMError::TokenProgramMismatch,
+    constraint = *mint_y.to_account_info().owner == token_program_y.key()
+      @ AMMError::TokenProgramMismatch,
+  )]
+  pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    init,
+    payer = authority,
+    mint::decimals = 6,
+    mint::authority = pool_authority,
+    mint::token_program = token_program_lp,
+    seeds = [seed_prefix::LP_MINT, config.key().as_ref()],
+    bump
+  )]
+  pub lp_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    payer = authority,
+    token::mint = mint_x,
+    token::authority = pool_authority,
+    token::token_program = token_program_x,
+    seeds = [seed_prefix::VAULT, config.key().as_ref(), mint_x.key().as_ref()],
+    bump
+  )]
+  pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init,
+    payer = authority,
+    token::mint = mint_y,
+    token::authority = pool_authority,
+    token::token_program = token_program_y,
+    seeds = [seed_prefix::VAULT, config.key().as_ref(), mint_y.key().as_ref()],
+    bump
+  )]
+  pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  // Discovery indexes of each mint, the new pool is listed in those supplied
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = DISCRIMINATOR + MintPoolIndex::INIT_SPACE,
+    seeds = [seed_prefix::MINT_POOL_INDEX, mint_x.key().as_ref()],
+    bump
+  )]
+  pub mint_x_pool_index: Option<Box<Account<'info, MintPoolIndex>>>,
+
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = DISCRIMINATOR + MintPoolIndex::INIT_SPACE,
+    seeds = [seed_prefix::MINT_POOL_INDEX, mint_y.key().as_ref()],
+    bump
+  )]
+  pub mint_y_pool_index: Option<Box<Account<'info, MintPoolIndex>>>,
+
+  pub token_program_x: Interface<'info, TokenInterface>,
+  pub token_program_y: Interface<'info, TokenInterface>,
+  pub token_program_lp: Interface<'info, TokenInterface>,
+  pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+  ctx: Context<InitializePoolWithPdaVaults>,
+  seed: u64,
+  fee: u16,
+  white_list_lp: Option<Vec<Pubkey>>,
+  max_fee_bearing_sides: u8,
+  max_transfer_fee_bps: u16,
+) -> Result<()> {
+  let config = &mut ctx.accounts.config;
+  let pool_state = &mut ctx.accounts.pool_state;
+
+  validate_pool_params(
+    fee,
+    &ctx.accounts.mint_x,
+    &ctx.accounts.mint_y,
+    max_fee_bearing_sides,
+    max_transfer_fee_bps,
+  )?;
+
+  let params_init_config = InitConfigParams {
+    seed,
+    authority: ctx.accounts.authority.key(),
+    mint_x: ctx.accounts.mint_x.key(),
+    mint_y: ctx.accounts.mint_y.key(),
+    fee,
+    white_list_lp,
+    auth_bump: ctx.bumps.pool_authority,
+    config_bump: ctx.bumps.config,
+    lp_bump: ctx.bumps.lp_mint,
+    created_at: Clock::get()?.unix_timestamp,
+    vault_authority: ctx.accounts.pool_authority.key(),
+    vault_auth_bump: ctx.bumps.pool_authority,
+    separate_vault_authority: false,
+    mint_x_legacy: is_legacy_token_mint(&ctx.accounts.mint_x),
+    mint_y_legacy: is_legacy_token_mint(&ctx.accounts.mint_y),
+  };
+  let params_init_pool_state = InitPoolStateParams {
+    config: config.key(),
+    lp_mint: ctx.accounts.lp_mint.key(),
+    vault_x: ctx.accounts.vault_x.key(),
+    vault_y: ctx.accounts.vault_y.key(),
+  };
+  init_pool_accounts(
+    config,
+    pool_state,
+    params_init_config,
+    params_init_pool_state,
+    [
+      (
+        ctx.accounts.mint_x_pool_index.as_deref_mut(),
+        ctx.bumps.mint_x_pool_index,
+      ),
+      (
+        ctx.accounts.mint_y_pool_index.as_deref_mut(),
+        ctx.bumps.mint_y_pool_index,
+      ),
+    ],
+  )
+}
@@ -0,0 +1,105 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, DISCRIMINATOR},
+      error::AMMError,
+    },
+    state::{Config, InitRewardConfigParams, PoolState, RewardConfig},
+    utils::token::verify_supported_token_mint,
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{Mint as MintInterface, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+pub struct InitializeReward<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump,
+    has_one = lp_mint
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    payer = authority,
+    space = DISCRIMINATOR + RewardConfig::INIT_SPACE,
+    seeds = [seed_prefix::REWARD, config.key().as_ref()],
+    bump
+  )]
+  pub reward_config: Box<Account<'info, RewardConfig>>,
+
+  #[account(mint::token_program = token_program)]
+  pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  // A PDA token account rather than an ATA, so a reward mint equal to one of the
+  // trading mints can never alias a pool vault
+  #[account(
+    init,
+    payer = authority,
+    token::mint = reward_mint,
+    token::authority = pool_authority,
+    token::token_program = token_program,
+    seeds = [seed_prefix::REWARD_VAULT, config.key().as_ref()],
+    bump
+  )]
+  pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mint::token_program = token_program_lp)]
+  pub lp_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  // Holds the LP staked for rewards, only staked LP earns
+  #[account(
+    init,
+    payer = authority,
+    token::mint = lp_mint,
+    token::authority = pool_authority,
+    token::token_program = token_program_lp,
+    seeds = [seed_prefix::STAKE_VAULT, config.key().as_ref()],
+    bump
+  )]
+  pub stake_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub token_program_lp: Interface<'info, TokenInterface>,
+  pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<InitializeReward>, reward_rate_per_second: u64) -> Result<()> {
+  require!(
+    verify_supported_token_mint(&ctx.accounts.reward_mint)?,
+    AMMError::NotAllowedTokenExtension
+  );
+
+  let params = InitRewardConfigParams {
+    config: ctx.accounts.config.key(),
+    reward_mint: ctx.accounts.reward_mint.key(),
+    reward_vault: ctx.accounts.reward_vault.key(),
+    stake_vault: ctx.accounts.stake_vault.key(),
+    reward_rate_per_second,
+    now: Clock::get()?.unix_timestamp,
+    bump: ctx.bumps.reward_config,
+    vault_bump: ctx.bumps.reward_vault,
+    stake_vault_bump: ctx.bumps.stake_vault,
+  };
+  ctx.accounts.reward_config.init(params);
+
+  Ok(())
+}
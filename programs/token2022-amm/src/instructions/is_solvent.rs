@@ -0,0 +1,64 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+    utils::math::vault_shortfall,
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct IsSolvent<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  #[account(address = pool_state.vault_x)]
+  pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(address = pool_state.vault_y)]
+  pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolSolvency {
+  /// Both vaults cover their reserve plus the accrued protocol fees
+  pub solvent: bool,
+  /// Amount vault X is missing to cover `reserve_x + protocol_fees_x`
+  pub shortfall_x: u64,
+  /// Amount vault Y is missing to cover `reserve_y + protocol_fees_y`
+  pub shortfall_y: u64,
+}
+
+// Unlike check_pool_health, which compares the vaults to the reserves only, this counts
+// every claim on the vaults: a vault that covers its reserve but not the fees owed on top
+// of it is insolvent
+pub fn handler(ctx: Context<IsSolvent>) -> Result<PoolSolvency> {
+  let pool_state = &ctx.accounts.pool_state;
+
+  let shortfall_x = vault_shortfall(
+    ctx.accounts.vault_x.amount,
+    pool_state.reserve_x,
+    pool_state.protocol_fees_x,
+  );
+  let shortfall_y = vault_shortfall(
+    ctx.accounts.vault_y.amount,
+    pool_state.reserve_y,
+    pool_state.protocol_fees_y,
+  );
+
+  Ok(PoolSolvency {
+    solvent: shortfall_x == 0 && shortfall_y == 0,
+    shortfall_x,
+    shortfall_y,
+  })
+}
@@ -0,0 +1,55 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError},
+    state::{Config, CONFIG_VERSION},
+    utils::migration::{migrate_account, Migrate},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  /// CHECK: May predate the current `Config` layout and fail to deserialize, so its address
+  /// and authority are checked in the handler from the data read under its own version
+  #[account(mut, owner = crate::ID)]
+  pub config: UncheckedAccount<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, MigrateConfig<'info>>) -> Result<()> {
+  let config_info = ctx.accounts.config.as_ref();
+  {
+    let config = Config::deserialize_versioned(&config_info.try_borrow_data()?)?;
+    let expected = Pubkey::create_program_address(
+      &[
+        seed_prefix::CONFIG,
+        config.seed.to_le_bytes().as_ref(),
+        &[config.config_bump],
+      ],
+      ctx.program_id,
+    )
+    .map_err(|_| ErrorCode::ConstraintSeeds)?;
+    require_keys_eq!(config_info.key(), expected, ErrorCode::ConstraintSeeds);
+    require_keys_eq!(
+      config.authority,
+      ctx.accounts.authority.key(),
+      AMMError::Unauthorized
+    );
+  }
+
+  if migrate_account::<Config>(
+    config_info,
+    ctx.accounts.authority.as_ref(),
+    ctx.accounts.system_program.as_ref(),
+  )? {
+    msg!("Migrated config to version {}", CONFIG_VERSION);
+  } else {
+    msg!("Config already at version {}", CONFIG_VERSION);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,47 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError},
+    state::{Config, PoolState, POOL_STATE_VERSION},
+    utils::migration::migrate_account,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct MigratePoolState<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  /// CHECK: May predate the current `PoolState` layout and fail to deserialize, so only the
+  /// address and owner are checked here; the discriminator is checked on migration
+  #[account(
+    mut,
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump,
+    owner = crate::ID
+  )]
+  pub pool_state: UncheckedAccount<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, MigratePoolState<'info>>) -> Result<()> {
+  if migrate_account::<PoolState>(
+    ctx.accounts.pool_state.as_ref(),
+    ctx.accounts.authority.as_ref(),
+    ctx.accounts.system_program.as_ref(),
+  )? {
+    msg!("Migrated pool state to version {}", POOL_STATE_VERSION);
+  } else {
+    msg!("Pool state already at version {}", POOL_STATE_VERSION);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,154 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::VaultMigratedEvent},
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+  },
+};
+
+pub const MIGRATE_VAULT_X: u8 = 0;
+pub const MIGRATE_VAULT_Y: u8 = 1;
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  /// CHECK: Owner of the pool vaults
+  #[account(address = config.vault_authority)]
+  pub vault_authority: UncheckedAccount<'info>,
+
+  pub mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    mut,
+    token::mint = mint,
+    token::authority = vault_authority,
+    token::token_program = token_program,
+  )]
+  pub old_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  // Seeded by the vault it replaces so a vault can be migrated any number of times
+  #[account(
+    init,
+    payer = authority,
+    token::mint = mint,
+    token::authority = vault_authority,
+    token::token_program = token_program,
+    seeds = [seed_prefix::VAULT, config.key().as_ref(), old_vault.key().as_ref()],
+    bump
+  )]
+  pub new_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateVault>, which: u8) -> Result<()> {
+  let config = &ctx.accounts.config;
+  let pool_state = &mut ctx.accounts.pool_state;
+
+  let (expected_mint, current_vault, reserve, protocol_fees) = match which {
+    MIGRATE_VAULT_X => (
+      config.mint_x,
+      pool_state.vault_x,
+      pool_state.reserve_x,
+      pool_state.protocol_fees_x,
+    ),
+    MIGRATE_VAULT_Y => (
+      config.mint_y,
+      pool_state.vault_y,
+      pool_state.reserve_y,
+      pool_state.protocol_fees_y,
+    ),
+    _ => return Err(AMMError::InvalidVault.into()),
+  };
+  require!(
+    ctx.accounts.mint.key() == expected_mint,
+    AMMError::InvalidMint
+  );
+  require!(
+    ctx.accounts.old_vault.key() == current_vault,
+    AMMError::InvalidVault
+  );
+
+  let amount = ctx.accounts.old_vault.amount;
+  if amount > 0 {
+    let config_key = config.key();
+    let (vault_seed, vault_bump) = config.vault_authority_seed();
+    let auth_seeds = &[vault_seed, config_key.as_ref(), &[vault_bump]];
+    let signer = &[&auth_seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+      ctx.accounts.token_program.to_account_info(),
+      TransferChecked {
+        from: ctx.accounts.old_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.new_vault.to_account_info(),
+        authority: ctx.accounts.vault_authority.to_account_info(),
+      },
+      signer,
+    );
+    transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+  }
+
+  // A transfer fee on the move could leave the new vault short of what it owes: the reserve
+  // and the protocol fees accrued next to it. Fail the whole migration rather than switch
+  // the pool to an undercollateralized vault
+  let owed = reserve
+    .checked_add(protocol_fees)
+    .ok_or(AMMError::MathOverflow)?;
+  ctx.accounts.new_vault.reload()?;
+  require!(
+    ctx.accounts.new_vault.amount >= owed,
+    AMMError::InsufficientLiquidity
+  );
+
+  let new_vault = ctx.accounts.new_vault.key();
+  if which == MIGRATE_VAULT_X {
+    pool_state.vault_x = new_vault;
+  } else {
+    pool_state.vault_y = new_vault;
+  }
+
+  msg!(
+    "Migrated vault {} -> {} ({} tokens)",
+    current_vault,
+    new_vault,
+    amount
+  );
+
+  emit!(VaultMigratedEvent {
+    config: config.key(),
+    old_vault: current_vault,
+    new_vault,
+    amount,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -1,6 +1,61 @@
+pub mod add_to_whitelist;
+pub mod cheapest_direction;
+pub mod check_mint_supported;
+pub mod check_pool_health;
+pub mod claim_reward;
+pub mod compound_protocol_fees;
 pub mod deposit;
+pub mod deposit_and_balance;
+pub mod execute_fee_withdrawal;
+pub mod force_drain;
+pub mod fund_reward;
+pub mod get_apr;
+pub mod get_config_compact;
+pub mod get_effective_fee;
+pub mod get_fee_schedule;
+pub mod get_fee_stats;
+pub mod get_pending_rewards;
+pub mod get_pool_accounts;
+pub mod get_pools_for_mint;
+pub mod get_price_impact;
+pub mod get_tvl;
 pub mod init_pool;
+pub mod init_pool_with_pda_vaults;
+pub mod initialize_reward;
+pub mod is_solvent;
+pub mod migrate_config;
+pub mod migrate_pool_state;
+pub mod migrate_vault;
+pub mod pause_all;
+pub mod queue_fee_withdrawal;
+pub mod quote_withdraw_exact;
+pub mod remove_from_whitelist;
+pub mod rescue_tokens;
+pub mod reset_circuit_breaker;
+pub mod resize_whitelist;
+pub mod set_whitelist_enabled;
+pub mod snapshot;
+pub mod stake_lp;
 pub mod swap;
+pub mod swap_route;
+pub mod swap_with_price_bound;
+pub mod unlock_pool;
+pub mod unpause_all;
+pub mod unstake_lp;
+pub mod update_config;
 pub mod withdraw;
+pub mod withdraw_protocol_fees_batch;
+pub mod withdraw_split;
 
-pub use {deposit::*, init_pool::*, swap::*, withdraw::*};
+pub use {
+  add_to_whitelist::*, cheapest_direction::*, check_mint_supported::*, check_pool_health::*,
+  claim_reward::*, compound_protocol_fees::*, deposit::*, execute_fee_withdrawal::*,
+  force_drain::*, fund_reward::*, get_apr::*, get_config_compact::*, get_effective_fee::*,
+  get_fee_schedule::*, get_fee_stats::*, get_pending_rewards::*, get_pool_accounts::*,
+  get_pools_for_mint::*, get_price_impact::*, get_tvl::*, init_pool::*,
+  init_pool_with_pda_vaults::*, initialize_reward::*, is_solvent::*, migrate_config::*,
+  migrate_pool_state::*, migrate_vault::*, pause_all::*, queue_fee_withdrawal::*,
+  quote_withdraw_exact::*, rescue_tokens::*, reset_circuit_breaker::*, resize_whitelist::*,
+  set_whitelist_enabled::*, snapshot::*, stake_lp::*, swap::*, swap_route::*, unlock_pool::*,
+  unstake_lp::*, update_config::*, withdraw::*, withdraw_protocol_fees_batch::*, withdraw_split::*,
+};
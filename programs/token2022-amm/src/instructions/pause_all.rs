@@ -0,0 +1,49 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, DISCRIMINATOR},
+      event::GlobalPauseUpdatedEvent,
+    },
+    state::AuthorityRegistry,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct SetGlobalPause<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  // Keyed by the signer, so only the authority of the pools can flip their shared switch
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = DISCRIMINATOR + AuthorityRegistry::INIT_SPACE,
+    seeds = [seed_prefix::REGISTRY, authority.key().as_ref()],
+    bump
+  )]
+  pub registry: Box<Account<'info, AuthorityRegistry>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SetGlobalPause>) -> Result<()> {
+  set_global_paused(ctx, true)
+}
+
+// Shared by pause_all and unpause_all
+pub(crate) fn set_global_paused(ctx: Context<SetGlobalPause>, paused: bool) -> Result<()> {
+  let authority = ctx.accounts.authority.key();
+  ctx
+    .accounts
+    .registry
+    .set_global_paused(authority, paused, ctx.bumps.registry);
+
+  emit!(GlobalPauseUpdatedEvent {
+    authority,
+    paused,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,52 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::FeeWithdrawalQueuedEvent},
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct QueueFeeWithdrawal<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+}
+
+// First step of withdrawing protocol fees: announces the amounts, which
+// `execute_fee_withdrawal` can only move once `fee_withdrawal_delay` has passed
+pub fn handler(ctx: Context<QueueFeeWithdrawal>, amount_x: u64, amount_y: u64) -> Result<()> {
+  let pool_state = &ctx.accounts.pool_state;
+  require!(
+    amount_x <= pool_state.protocol_fees_x && amount_y <= pool_state.protocol_fees_y,
+    AMMError::InsufficientLiquidity
+  );
+
+  let now = Clock::get()?.unix_timestamp;
+  let unlock_at = ctx
+    .accounts
+    .config
+    .queue_fee_withdrawal(amount_x, amount_y, now)?;
+
+  emit!(FeeWithdrawalQueuedEvent {
+    config: ctx.accounts.config.key(),
+    amount_x,
+    amount_y,
+    unlock_at,
+    timestamp: now,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,48 @@
+use {
+  crate::{
+    common::constant::seed_prefix,
+    state::{Config, PoolState},
+    utils::math::lp_for_exact_withdrawal,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct QuoteWithdrawExact<'info> {
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+}
+
+// LP a `withdraw` has to burn to release exactly `amount_x_out` / `amount_y_out` from the
+// reserves, before the mints' transfer fees. Fails unless the amounts match the current
+// reserve ratio
+pub fn handler(
+  ctx: Context<QuoteWithdrawExact>,
+  amount_x_out: u64,
+  amount_y_out: u64,
+) -> Result<u64> {
+  let pool_state = &ctx.accounts.pool_state;
+  let lp_amount = lp_for_exact_withdrawal(
+    (amount_x_out, amount_y_out),
+    (pool_state.reserve_x, pool_state.reserve_y),
+    pool_state.lp_supply,
+  )?;
+
+  msg!(
+    "Withdrawing {} X / {} Y burns {} LP",
+    amount_x_out,
+    amount_y_out,
+    lp_amount
+  );
+
+  Ok(lp_amount)
+}
@@ -0,0 +1,12 @@
+use {
+  crate::instructions::{
+    add_to_whitelist::UpdateWhitelist, set_whitelist_enabled::emit_whitelist_updated,
+  },
+  anchor_lang::prelude::*,
+};
+
+pub fn handler(ctx: Context<UpdateWhitelist>, user: Pubkey) -> Result<()> {
+  ctx.accounts.config.remove_from_whitelist(user)?;
+
+  emit_whitelist_updated(&ctx.accounts.config)
+}
@@ -0,0 +1,117 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::TokensRescuedEvent},
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+  },
+};
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RescueTokens<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(
+    address = mint @ AMMError::InvalidMint,
+    mint::token_program = token_program,
+  )]
+  pub stray_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    mut,
+    token::mint = stray_mint,
+    token::authority = pool_authority,
+    token::token_program = token_program,
+  )]
+  pub stray_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    token::mint = stray_mint,
+    token::authority = authority,
+    token::token_program = token_program,
+  )]
+  pub authority_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Recovers tokens sent by mistake to an account owned by the pool authority. Everything the
+// pool itself keeps under that authority is off limits: the trading vaults, the reward vault
+// and LP tokens, whose mint the same authority controls
+pub fn handler(ctx: Context<RescueTokens>, mint: Pubkey) -> Result<()> {
+  let config = &ctx.accounts.config;
+  let pool_state = &ctx.accounts.pool_state;
+  let stray_token = &ctx.accounts.stray_token;
+  let config_key = config.key();
+
+  let (reward_vault, _) = Pubkey::find_program_address(
+    &[seed_prefix::REWARD_VAULT, config_key.as_ref()],
+    ctx.program_id,
+  );
+  let lp_mint = Pubkey::create_program_address(
+    &[seed_prefix::LP_MINT, config_key.as_ref(), &[config.lp_bump]],
+    ctx.program_id,
+  )
+  .map_err(|_| AMMError::InvalidLpMintAuthority)?;
+  require!(
+    stray_token.key() != pool_state.vault_x
+      && stray_token.key() != pool_state.vault_y
+      && stray_token.key() != reward_vault
+      && mint != lp_mint,
+    AMMError::CannotRescuePoolAccount
+  );
+
+  let amount = stray_token.amount;
+  require!(amount > 0, AMMError::InvalidAmount);
+
+  let auth_seeds = &[seed_prefix::AUTH, config_key.as_ref(), &[config.auth_bump]];
+  let signer = &[&auth_seeds[..]];
+
+  let transfer_ctx = CpiContext::new_with_signer(
+    ctx.accounts.token_program.to_account_info(),
+    TransferChecked {
+      from: stray_token.to_account_info(),
+      mint: ctx.accounts.stray_mint.to_account_info(),
+      to: ctx.accounts.authority_token.to_account_info(),
+      authority: ctx.accounts.pool_authority.to_account_info(),
+    },
+    signer,
+  );
+  transfer_checked(transfer_ctx, amount, ctx.accounts.stray_mint.decimals)?;
+
+  msg!("Rescued {} tokens of mint {}", amount, mint);
+
+  emit!(TokensRescuedEvent {
+    config: config_key,
+    mint,
+    source: stray_token.key(),
+    amount,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,33 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::CircuitBreakerResetEvent},
+    state::Config,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct ResetCircuitBreaker<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+}
+
+// Reopens a pool locked by the circuit breaker once the authority has reviewed the move
+pub fn handler(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+  let config = &mut ctx.accounts.config;
+  config.reset_circuit_breaker()?;
+
+  emit!(CircuitBreakerResetEvent {
+    config: config.key(),
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,36 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError},
+    instructions::set_whitelist_enabled::emit_whitelist_updated,
+    state::Config,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+#[instruction(new_capacity: u16)]
+pub struct ResizeWhitelist<'info> {
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized,
+    realloc = Config::space_for_whitelist(new_capacity),
+    realloc::payer = authority,
+    realloc::zero = false,
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+// Grows (or shrinks) the config account to hold `new_capacity` whitelist entries. The
+// authority pays the extra rent, and is refunded when shrinking.
+pub fn handler(ctx: Context<ResizeWhitelist>, new_capacity: u16) -> Result<()> {
+  ctx.accounts.config.resize_whitelist(new_capacity)?;
+
+  emit_whitelist_updated(&ctx.accounts.config)
+}
@@ -0,0 +1,39 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::WhitelistUpdatedEvent},
+    state::Config,
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct SetWhitelistEnabled<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+}
+
+pub fn handler(ctx: Context<SetWhitelistEnabled>, enabled: bool) -> Result<()> {
+  ctx.accounts.config.set_whitelist_enabled(enabled);
+
+  emit_whitelist_updated(&ctx.accounts.config)
+}
+
+// Shared by every instruction that changes the whitelist
+pub(crate) fn emit_whitelist_updated(config: &Account<Config>) -> Result<()> {
+  emit!(WhitelistUpdatedEvent {
+    config: config.key(),
+    enabled: config.whitelist_enabled,
+    entries: config.white_list_lp.len() as u16,
+    capacity: config.whitelist_capacity,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,60 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, DISCRIMINATOR},
+      event::PoolSnapshotEvent,
+    },
+    state::{Config, PoolSnapshot, PoolState},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct Snapshot<'info> {
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  // Fresh keypair account supplied by the caller; each snapshot gets its own
+  #[account(
+    init,
+    payer = payer,
+    space = DISCRIMINATOR + PoolSnapshot::INIT_SPACE
+  )]
+  pub pool_snapshot: Box<Account<'info, PoolSnapshot>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+// Anyone can take a snapshot; the record is only as trustworthy as this program writing it,
+// which the account's owner proves
+pub fn handler(ctx: Context<Snapshot>) -> Result<()> {
+  let pool_state = &ctx.accounts.pool_state;
+  let clock = Clock::get()?;
+
+  let pool_snapshot = &mut ctx.accounts.pool_snapshot;
+  pool_snapshot.record(ctx.accounts.config.key(), pool_state, &clock);
+
+  emit!(PoolSnapshotEvent {
+    config: pool_snapshot.config,
+    snapshot: pool_snapshot.key(),
+    reserve_x: pool_snapshot.reserve_x,
+    reserve_y: pool_snapshot.reserve_y,
+    lp_supply: pool_snapshot.lp_supply,
+    slot: pool_snapshot.slot,
+    timestamp: pool_snapshot.timestamp,
+  });
+
+  Ok(())
+}
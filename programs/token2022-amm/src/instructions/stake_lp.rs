@@ -0,0 +1,115 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, DISCRIMINATOR},
+      error::AMMError,
+      event::LpStakedEvent,
+    },
+    state::{Config, PoolState, RewardConfig, UserReward},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+  },
+};
+
+#[derive(Accounts)]
+pub struct StakeLp<'info> {
+  #[account(mut)]
+  pub user: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump,
+    has_one = lp_mint
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::REWARD, config.key().as_ref()],
+    bump = reward_config.bump,
+    has_one = stake_vault
+  )]
+  pub reward_config: Box<Account<'info, RewardConfig>>,
+
+  #[account(
+    init_if_needed,
+    payer = user,
+    space = DISCRIMINATOR + UserReward::INIT_SPACE,
+    seeds = [seed_prefix::USER_REWARD, reward_config.key().as_ref(), user.key().as_ref()],
+    bump
+  )]
+  pub user_reward: Box<Account<'info, UserReward>>,
+
+  pub lp_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    mut,
+    token::mint = lp_mint,
+    token::authority = user,
+    token::token_program = token_program_lp,
+  )]
+  pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub stake_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program_lp: Interface<'info, TokenInterface>,
+  pub system_program: Program<'info, System>,
+}
+
+// Locks LP in the stake vault to earn rewards. What was earned on the previous stake is
+// settled first, so the added LP only earns from now on
+pub fn handler(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+  require!(amount > 0, AMMError::InvalidAmount);
+
+  let reward_config = &mut ctx.accounts.reward_config;
+  let user_reward = &mut ctx.accounts.user_reward;
+
+  let now = Clock::get()?.unix_timestamp;
+  reward_config.update(now)?;
+
+  if !user_reward.is_initialized() {
+    // Nothing is owed for the time before the user first staked
+    user_reward.init(
+      reward_config.key(),
+      ctx.accounts.user.key(),
+      reward_config.reward_per_lp_stored,
+      ctx.bumps.user_reward,
+    );
+  }
+  user_reward.settle(reward_config.reward_per_lp_stored)?;
+
+  let transfer_ctx = CpiContext::new(
+    ctx.accounts.token_program_lp.to_account_info(),
+    TransferChecked {
+      from: ctx.accounts.user_lp_token.to_account_info(),
+      mint: ctx.accounts.lp_mint.to_account_info(),
+      to: ctx.accounts.stake_vault.to_account_info(),
+      authority: ctx.accounts.user.to_account_info(),
+    },
+  );
+  transfer_checked(transfer_ctx, amount, ctx.accounts.lp_mint.decimals)?;
+
+  user_reward.stake(amount)?;
+  reward_config.stake(amount)?;
+
+  msg!("Staked {} LP tokens", amount);
+
+  emit!(LpStakedEvent {
+    config: ctx.accounts.config.key(),
+    user: ctx.accounts.user.key(),
+    amount,
+    total_staked: reward_config.total_staked,
+    timestamp: now,
+  });
+
+  Ok(())
+}
@@ -1,7 +1,18 @@
 use {
   crate::{
-    common::error::AMMError,
-    state::{Config, PoolState},
+    common::{
+      constant::{BPS_DENOMINATOR, PRICE_PRECISION},
+      error::AMMError,
+      event::{CircuitBreakerEvent, SwapEvent},
+    },
+    state::{AuthorityRegistry, Config, PoolState},
+    utils::{
+      swap::{
+        constant_product_out, fit_to_vault, max_swap_amount_in, min_swap_amount_in,
+        spot_price_move_bps, swap_fee_amount, SwapDirection,
+      },
+      token::{calculate_transfer_fee_excluded_amount, ensure_pool_vault, reverify_pool_mints},
+    },
   },
   anchor_lang::prelude::*,
   anchor_spl::{
@@ -17,7 +28,9 @@ pub struct Swap<'info> {
   #[account(mut)]
   pub user: Signer<'info>,
 
+  // Mutable for the circuit breaker to lock the pool
   #[account(
+        mut,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
         constraint = !config.locked @ AMMError::PoolLocked
@@ -38,22 +51,28 @@ pub struct Swap<'info> {
     )]
   pub pool_authority: UncheckedAccount<'info>,
 
+  /// CHECK: Owner of the pool vaults
+  #[account(address = config.vault_authority)]
+  pub vault_authority: UncheckedAccount<'info>,
+
+  // The LP token is never a leg of the pool; checked here so it fails before the vault
+  // constraints below
+  #[account(constraint = mint_in.key() != pool_state.lp_mint @ AMMError::InvalidMint)]
   pub mint_in: Box<InterfaceAccount<'info, MintInterface>>,
+  #[account(constraint = mint_out.key() != pool_state.lp_mint @ AMMError::InvalidMint)]
   pub mint_out: Box<InterfaceAccount<'info, MintInterface>>,
 
   #[account(
         mut,
-        constraint = vault_in.key() == pool_state.vault_x || vault_in.key() == pool_state.vault_y,
         constraint = vault_in.mint == mint_in.key(),
-        constraint = vault_in.owner == pool_authority.key(),
+        constraint = vault_in.owner == vault_authority.key(),
     )]
   pub vault_in: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(
         mut,
-        constraint = vault_out.key() == pool_state.vault_x || vault_out.key() == pool_state.vault_y,
         constraint = vault_out.mint == mint_out.key(),
-        constraint = vault_out.owner == pool_authority.key(),
+        constraint = vault_out.owner == vault_authority.key(),
         constraint = vault_in.key() != vault_out.key(),
     )]
   pub vault_out: Box<InterfaceAccount<'info, TokenAccount>>,
@@ -72,125 +91,170 @@ pub struct Swap<'info> {
     )]
   pub user_token_out: Box<InterfaceAccount<'info, TokenAccount>>,
 
+  // Optional proof of LP holdings for the fee discount
+  #[account(
+        constraint = user_lp_token.mint == pool_state.lp_mint,
+        constraint = user_lp_token.owner == user.key(),
+    )]
+  pub user_lp_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+  // Kill switch shared by every pool of the config authority, checked when supplied
+  #[account(
+        seeds = [b"registry", config.authority.as_ref()],
+        bump = registry.bump,
+    )]
+  pub registry: Option<Box<Account<'info, AuthorityRegistry>>>,
+
   pub token_program_x: Interface<'info, TokenInterface>,
   pub token_program_y: Interface<'info, TokenInterface>,
   pub associated_token_program: Program<'info, AssociatedToken>,
   pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
-  let pool_state = &mut ctx.accounts.pool_state;
-  let config = &ctx.accounts.config;
-
-  require!(amount_in > 0, AMMError::InvalidAmount);
+// Pool conditions the client quoted the swap against. Each one supplied must still hold or
+// the swap is refused; `None` skips that check
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SwapGuards {
+  pub expected_fee: Option<u16>,
+  pub expected_reserve_x: Option<u64>,
+  pub expected_reserve_y: Option<u64>,
+  /// How far either reserve may move from the expected one, in bps of it
+  pub reserve_tolerance_bps: u16,
+  pub expected_epoch: Option<u64>,
+}
 
-  // Verify mint constraints
-  require!(
-    ctx.accounts.mint_in.key() == config.mint_x || ctx.accounts.mint_in.key() == config.mint_y,
-    AMMError::InvalidMint
-  );
-  require!(
-    ctx.accounts.mint_out.key() == config.mint_x || ctx.accounts.mint_out.key() == config.mint_y,
-    AMMError::InvalidMint
-  );
-  require!(
-    ctx.accounts.mint_in.key() != ctx.accounts.mint_out.key(),
-    AMMError::InvalidMint
-  );
+pub fn handler(
+  ctx: Context<Swap>,
+  amount_in: u64,
+  min_amount_out: u64,
+  guards: SwapGuards,
+) -> Result<()> {
+  let SwapGuards {
+    expected_fee,
+    expected_reserve_x,
+    expected_reserve_y,
+    reserve_tolerance_bps,
+    expected_epoch,
+  } = guards;
+
+  // The client quoted against `expected_fee`; refuse to execute if the fee this swapper
+  // would actually pay (decay, discounts and floor included) moved since
+  if let Some(expected_fee) = expected_fee {
+    let lp_balance = ctx
+      .accounts
+      .user_lp_token
+      .as_ref()
+      .map_or(0, |user_lp_token| user_lp_token.amount);
+    let fee = ctx.accounts.config.swap_fee_for(
+      &ctx.accounts.user.key(),
+      lp_balance,
+      Clock::get()?.unix_timestamp,
+    )?;
+    require!(fee == expected_fee, AMMError::FeeChanged);
+  }
 
-  // Determine which direction we're swapping
-  let (reserve_in, reserve_out, is_x_to_y) = if ctx.accounts.mint_in.key() == config.mint_x {
+  // Transfer fees are looked up by epoch, so a swap landing in a later epoch than quoted can
+  // pay a different (scheduled) transfer fee than the client assumed
+  if let Some(expected_epoch) = expected_epoch {
     require!(
-      ctx.accounts.mint_out.key() == config.mint_y,
-      AMMError::InvalidMint
+      Clock::get()?.epoch == expected_epoch,
+      AMMError::EpochChanged
     );
-    (pool_state.reserve_x, pool_state.reserve_y, true)
-  } else if ctx.accounts.mint_in.key() == config.mint_y {
-    require!(
-      ctx.accounts.mint_out.key() == config.mint_x,
-      AMMError::InvalidMint
-    );
-    (pool_state.reserve_y, pool_state.reserve_x, false)
-  } else {
-    return Err(AMMError::InvalidMint.into());
-  };
-
-  require!(
-    reserve_in > 0 && reserve_out > 0,
-    AMMError::InsufficientLiquidity
-  );
-
-  // For Token2022 tokens with transfer fees, the vault balance might be less than reserves
-  // due to fees being collected, so we use a more lenient check
-  // We ensure the vault has at least enough for the output amount
-  require!(
-    ctx.accounts.vault_out.amount > 0,
-    AMMError::InsufficientLiquidity
-  );
-
-  // Calculate output amount using constant product formula with AMM fee
-  // amount_out = (amount_in * (10000 - fee) * reserve_out) / ((reserve_in * 10000) + (amount_in * (10000 - fee)))
-
-  // Account for transfer fees on input token
-  let actual_amount_in = if let Some(epoch_transfer_fee) =
-    crate::utils::token::get_epoch_transfer_fee(&ctx.accounts.mint_in)?
-  {
-    let transfer_fee = epoch_transfer_fee
-      .calculate_fee(amount_in)
-      .ok_or(AMMError::TransferFeeCalculationError)?;
-    amount_in
-      .checked_sub(transfer_fee)
-      .ok_or(AMMError::InvalidAmount)?
-  } else {
-    amount_in
-  };
+  }
 
-  let fee_adjusted_amount_in = (actual_amount_in as u128)
-    .checked_mul(
-      (10000u128)
-        .checked_sub(config.fee as u128)
-        .ok_or(AMMError::InvalidAmount)?,
-    )
-    .ok_or(AMMError::InvalidAmount)?;
+  // Likewise for the reserves the client snapshotted when building the transaction
+  let pool_state = &ctx.accounts.pool_state;
+  for (expected, current) in [
+    (expected_reserve_x, pool_state.reserve_x),
+    (expected_reserve_y, pool_state.reserve_y),
+  ] {
+    if let Some(expected) = expected {
+      require!(
+        reserve_within_tolerance(expected, current, reserve_tolerance_bps),
+        AMMError::ReservesMoved
+      );
+    }
+  }
 
-  let numerator = fee_adjusted_amount_in
-    .checked_mul(reserve_out as u128)
-    .ok_or(AMMError::InvalidAmount)?;
+  process_swap(ctx, amount_in, min_amount_out, None)
+}
 
-  let denominator = (reserve_in as u128)
-    .checked_mul(10000u128)
-    .ok_or(AMMError::InvalidAmount)?
-    .checked_add(fee_adjusted_amount_in)
-    .ok_or(AMMError::InvalidAmount)?;
+fn reserve_within_tolerance(expected: u64, current: u64, tolerance_bps: u16) -> bool {
+  let deviation = expected.abs_diff(current) as u128;
+  deviation * BPS_DENOMINATOR as u128 <= (expected as u128) * tolerance_bps as u128
+}
 
-  require!(denominator > 0, AMMError::InvalidAmount);
-  let amount_out_before_fees = numerator
-    .checked_div(denominator)
-    .ok_or(AMMError::InvalidAmount)? as u64;
+// Shared swap path. `max_price` optionally bounds the average execution price, expressed
+// as input paid per unit of output, decimals-normalized and scaled by PRICE_PRECISION
+pub(crate) fn process_swap(
+  ctx: Context<Swap>,
+  amount_in: u64,
+  min_amount_out: u64,
+  max_price: Option<u128>,
+) -> Result<()> {
+  if let Some(registry) = &ctx.accounts.registry {
+    registry.ensure_active()?;
+  }
 
-  // Account for transfer fees on output token
-  let amount_out = if let Some(epoch_transfer_fee) =
-    crate::utils::token::get_epoch_transfer_fee(&ctx.accounts.mint_out)?
-  {
-    let transfer_fee = epoch_transfer_fee
-      .calculate_fee(amount_out_before_fees)
-      .ok_or(AMMError::TransferFeeCalculationError)?;
-    amount_out_before_fees
-      .checked_sub(transfer_fee)
-      .ok_or(AMMError::InvalidAmount)?
-  } else {
-    amount_out_before_fees
-  };
+  let pool_state = &mut ctx.accounts.pool_state;
+  let config = &ctx.accounts.config;
 
-  require!(amount_out >= min_amount_out, AMMError::SlippageExceeded);
-  require!(amount_out > 0, AMMError::InsufficientOutputAmount);
-  require!(amount_out <= reserve_out, AMMError::InsufficientLiquidity);
+  require!(amount_in > 0, AMMError::InvalidAmount);
 
-  // Ensure vault has enough tokens for the swap (accounting for potential transfer fees)
-  require!(
-    ctx.accounts.vault_out.amount >= amount_out,
-    AMMError::InsufficientLiquidity
-  );
+  let direction = SwapDirection::resolve(
+    config,
+    &ctx.accounts.mint_in.key(),
+    &ctx.accounts.mint_out.key(),
+  )?;
+  let is_x_to_y = direction.is_x_to_y();
+
+  // Bind each vault to the pool's stored vault for its mint, so the reserves chosen for
+  // pricing always belong to the vaults that move tokens
+  ensure_pool_vault(
+    config,
+    pool_state,
+    &ctx.accounts.vault_in,
+    &ctx.accounts.mint_in.key(),
+  )?;
+  ensure_pool_vault(
+    config,
+    pool_state,
+    &ctx.accounts.vault_out,
+    &ctx.accounts.mint_out.key(),
+  )?;
+
+  // A Token-2022 mint can change after init (e.g. a new transfer hook program), so it is
+  // checked again before trading against it
+  reverify_pool_mints([
+    (
+      &ctx.accounts.mint_in,
+      config.is_legacy_mint(&ctx.accounts.mint_in.key()),
+    ),
+    (
+      &ctx.accounts.mint_out,
+      config.is_legacy_mint(&ctx.accounts.mint_out.key()),
+    ),
+  ])?;
+
+  let lp_balance = ctx
+    .accounts
+    .user_lp_token
+    .as_ref()
+    .map_or(0, |user_lp_token| user_lp_token.amount);
+  let amounts = compute_swap(
+    config,
+    pool_state,
+    direction,
+    &ctx.accounts.user.key(),
+    lp_balance,
+    &ctx.accounts.mint_in,
+    &ctx.accounts.mint_out,
+    ctx.accounts.vault_out.amount,
+    amount_in,
+    min_amount_out,
+    max_price,
+  )?;
+  let amount_out = amounts.amount_out;
 
   // Determine which token programs to use based on swap direction
   let (token_program_in, token_program_out) = if is_x_to_y {
@@ -199,6 +263,34 @@ pub fn handler(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Resul
     (&ctx.accounts.token_program_y, &ctx.accounts.token_program_x)
   };
 
+  // A swap moving the price further than the breaker allows is not executed. Failing would
+  // roll the lock back too, so the pool is locked and the instruction succeeds without
+  // moving any funds either way. Only a caller actually holding the input can trip it; the
+  // authority reviews the move and reopens the pool with `reset_circuit_breaker`
+  if config.breaks_circuit(amounts.price_move_bps) {
+    require!(
+      ctx.accounts.user_token_in.amount >= amount_in,
+      AMMError::InsufficientBalance
+    );
+
+    let config_key = config.key();
+    ctx
+      .accounts
+      .config
+      .trip_circuit_breaker(amounts.price_move_bps);
+
+    emit!(CircuitBreakerEvent {
+      config: config_key,
+      user: ctx.accounts.user.key(),
+      mint_in: ctx.accounts.mint_in.key(),
+      amount_in,
+      price_move_bps: amounts.price_move_bps,
+      timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    return Ok(());
+  }
+
   // Transfer input tokens from user to vault
   let transfer_in_ctx = CpiContext::new(
     token_program_in.to_account_info(),
@@ -213,7 +305,8 @@ pub fn handler(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Resul
 
   // Transfer output tokens from vault to user
   let config_key = config.key();
-  let auth_seeds = &[b"auth", config_key.as_ref(), &[config.auth_bump]];
+  let (vault_seed, vault_bump) = config.vault_authority_seed();
+  let auth_seeds = &[vault_seed, config_key.as_ref(), &[vault_bump]];
   let signer = &[&auth_seeds[..]];
 
   let transfer_out_ctx = CpiContext::new_with_signer(
@@ -222,38 +315,251 @@ pub fn handler(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Resul
       from: ctx.accounts.vault_out.to_account_info(),
       mint: ctx.accounts.mint_out.to_account_info(),
       to: ctx.accounts.user_token_out.to_account_info(),
-      authority: ctx.accounts.pool_authority.to_account_info(),
+      authority: ctx.accounts.vault_authority.to_account_info(),
     },
     signer,
   );
   transfer_checked(transfer_out_ctx, amount_out, ctx.accounts.mint_out.decimals)?;
 
+  apply_swap(pool_state, is_x_to_y, &amounts)?;
+
+  msg!(
+    "Swapped {} tokens in for {} tokens out",
+    amount_in,
+    amount_out
+  );
+
+  emit!(SwapEvent {
+    config: config_key,
+    user: ctx.accounts.user.key(),
+    mint_in: ctx.accounts.mint_in.key(),
+    amount_in,
+    amount_out,
+    protocol_fee: amounts.protocol_fee,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
+
+/// What a swap moves, computed before any token leaves an account
+pub(crate) struct SwapAmounts {
+  /// Paid out of the output vault
+  pub amount_out: u64,
+  /// Drop of the output reserve, before the output transfer fee
+  pub amount_out_before_fees: u64,
+  /// Growth of the input reserve: the input net of its transfer fee and the protocol fee
+  pub amount_in_to_reserve: u64,
+  /// Whole swap fee, in input tokens
+  pub fee_amount: u64,
+  /// Protocol's cut of `fee_amount`, kept in the vault outside the reserves
+  pub protocol_fee: u64,
+  /// Spot price move the swap causes, for the circuit breaker
+  pub price_move_bps: u16,
+  /// Transfer fees withheld on the way into the input vault and out of the output vault
+  pub transfer_fee_in: u64,
+  pub transfer_fee_out: u64,
+}
+
+// Prices a swap of `amount_in` in `direction` and runs every check on its amounts, down to
+// the slippage and price bounds. Shared by single swaps and each hop of a route; it moves
+// no tokens and leaves the pool untouched
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn compute_swap(
+  config: &Config,
+  pool_state: &PoolState,
+  direction: SwapDirection,
+  user: &Pubkey,
+  lp_balance: u64,
+  mint_in: &InterfaceAccount<'_, MintInterface>,
+  mint_out: &InterfaceAccount<'_, MintInterface>,
+  vault_out_amount: u64,
+  amount_in: u64,
+  min_amount_out: u64,
+  max_price: Option<u128>,
+) -> Result<SwapAmounts> {
+  let (reserve_in, reserve_out) = direction.reserves(pool_state);
+
+  require!(
+    reserve_in > 0 && reserve_out > 0,
+    AMMError::InsufficientLiquidity
+  );
+
+  // Dust inputs only buy rounding, so the floor is what buys a unit of output at spot
+  require!(
+    amount_in >= min_swap_amount_in(reserve_in, reserve_out)?,
+    AMMError::InsufficientInputAmount
+  );
+
+  // Bound single-trade price impact relative to the input reserve
+  if config.max_swap_ratio_bps < BPS_DENOMINATOR {
+    let max_amount_in = max_swap_amount_in(reserve_in, config.max_swap_ratio_bps)?;
+    require!(amount_in <= max_amount_in, AMMError::SwapTooLarge);
+  }
+
+  // For Token2022 tokens with transfer fees, the vault balance might be less than reserves
+  // due to fees being collected, so we use a more lenient check
+  // We ensure the vault has at least enough for the output amount
+  require!(vault_out_amount > 0, AMMError::InsufficientLiquidity);
+
+  // Output follows the constant product formula with the AMM fee, see constant_product_out
+
+  // Account for transfer fees on input token
+  let transfer_fee_in =
+    if let Some(epoch_transfer_fee) = crate::utils::token::get_epoch_transfer_fee(mint_in)? {
+      epoch_transfer_fee
+        .calculate_fee(amount_in)
+        .ok_or(AMMError::TransferFeeCalculationError)?
+    } else {
+      0
+    };
+  let actual_amount_in = amount_in
+    .checked_sub(transfer_fee_in)
+    .ok_or(AMMError::InvalidAmount)?;
+  // Nothing left to adjust for the pool fee once the input transfer fee is taken
+  require!(actual_amount_in > 0, AMMError::SwapOutputRoundsToZero);
+
+  let fee = config.swap_fee_for(user, lp_balance, Clock::get()?.unix_timestamp)?;
+
+  // The protocol's cut of the swap fee stays in the vault but outside the reserves
+  let fee_amount = swap_fee_amount(actual_amount_in, fee)?;
+  let protocol_fee = config.protocol_fee_for(amount_in, fee_amount)?;
+  let amount_in_to_reserve = actual_amount_in
+    .checked_sub(protocol_fee)
+    .ok_or(AMMError::InvalidAmount)?;
+  let amount_out_before_fees =
+    constant_product_out(actual_amount_in, reserve_in, reserve_out, fee)?;
+  // A fee-adjusted input too small to move the curve by a whole unit is refused here, before
+  // the slippage and output guards below can report it as something else
+  require!(amount_out_before_fees > 0, AMMError::SwapOutputRoundsToZero);
+
+  // Account for transfer fees on output token
+  let amount_out =
+    if let Some(epoch_transfer_fee) = crate::utils::token::get_epoch_transfer_fee(mint_out)? {
+      let transfer_fee = epoch_transfer_fee
+        .calculate_fee(amount_out_before_fees)
+        .ok_or(AMMError::TransferFeeCalculationError)?;
+      amount_out_before_fees
+        .checked_sub(transfer_fee)
+        .ok_or(AMMError::InvalidAmount)?
+    } else {
+      amount_out_before_fees
+    };
+
+  // Ensure vault has enough tokens for the swap. A shortfall within the configured grace
+  // is transfer fee rounding: pay out what the vault holds, and let the checks below
+  // apply to the reduced amount. The reserve still drops by the full amount
+  let amount_out = fit_to_vault(amount_out, vault_out_amount, config.vault_coverage_grace)?;
+
+  require!(amount_out >= min_amount_out, AMMError::SwapSlippageExceeded);
+  require!(amount_out > 0, AMMError::InsufficientOutputAmount);
+  require!(amount_out <= reserve_out, AMMError::InsufficientLiquidity);
+
+  if let Some(max_price) = max_price {
+    let price = execution_price(amount_in, amount_out, mint_in.decimals, mint_out.decimals)?;
+    require!(price <= max_price, AMMError::PriceBoundExceeded);
+  }
+
+  // The user must receive something once the output transfer fee is withheld,
+  // otherwise they would pay for the input transfer for a zero receipt
+  let received = calculate_transfer_fee_excluded_amount(mint_out, amount_out)?;
+  require!(received.amount > 0, AMMError::InsufficientOutputAmount);
+
+  let price_move_bps = spot_price_move_bps(
+    (reserve_in, reserve_out),
+    (
+      reserve_in
+        .checked_add(amount_in_to_reserve)
+        .ok_or(AMMError::InvalidAmount)?,
+      reserve_out.saturating_sub(amount_out_before_fees),
+    ),
+  )?;
+
+  Ok(SwapAmounts {
+    amount_out,
+    amount_out_before_fees,
+    amount_in_to_reserve,
+    fee_amount,
+    protocol_fee,
+    price_move_bps,
+    transfer_fee_in,
+    transfer_fee_out: received.transfer_fee,
+  })
+}
+
+// Books an executed swap into the pool: reserves, protocol fees and the LP fee counter
+pub(crate) fn apply_swap(
+  pool_state: &mut PoolState,
+  is_x_to_y: bool,
+  amounts: &SwapAmounts,
+) -> Result<()> {
   // Update pool reserves - use actual amounts after fees
   if is_x_to_y {
     pool_state.reserve_x = pool_state
       .reserve_x
-      .checked_add(actual_amount_in)
+      .checked_add(amounts.amount_in_to_reserve)
+      .ok_or(AMMError::InvalidAmount)?;
+    pool_state.protocol_fees_x = pool_state
+      .protocol_fees_x
+      .checked_add(amounts.protocol_fee)
       .ok_or(AMMError::InvalidAmount)?;
     pool_state.reserve_y = pool_state
       .reserve_y
-      .checked_sub(amount_out_before_fees)
+      .checked_sub(amounts.amount_out_before_fees)
       .ok_or(AMMError::InvalidAmount)?;
   } else {
     pool_state.reserve_y = pool_state
       .reserve_y
-      .checked_add(actual_amount_in)
+      .checked_add(amounts.amount_in_to_reserve)
+      .ok_or(AMMError::InvalidAmount)?;
+    pool_state.protocol_fees_y = pool_state
+      .protocol_fees_y
+      .checked_add(amounts.protocol_fee)
       .ok_or(AMMError::InvalidAmount)?;
     pool_state.reserve_x = pool_state
       .reserve_x
-      .checked_sub(amount_out_before_fees)
+      .checked_sub(amounts.amount_out_before_fees)
       .ok_or(AMMError::InvalidAmount)?;
   }
+  pool_state.accrue_lp_fee(
+    is_x_to_y,
+    amounts
+      .fee_amount
+      .checked_sub(amounts.protocol_fee)
+      .ok_or(AMMError::InvalidAmount)?,
+  )?;
+  pool_state.record_transfer_fee(is_x_to_y, amounts.transfer_fee_in)?;
+  pool_state.record_transfer_fee(!is_x_to_y, amounts.transfer_fee_out)
+}
 
-  msg!(
-    "Swapped {} tokens in for {} tokens out",
-    amount_in,
-    amount_out
-  );
+// Average price of the swap as input per unit of output, normalized for the mints'
+// decimals: amount_in * 10^decimals_out * PRICE_PRECISION / (amount_out * 10^decimals_in)
+fn execution_price(
+  amount_in: u64,
+  amount_out: u64,
+  decimals_in: u8,
+  decimals_out: u8,
+) -> Result<u128> {
+  let numerator = (amount_in as u128)
+    .checked_mul(
+      10u128
+        .checked_pow(decimals_out as u32)
+        .ok_or(AMMError::InvalidAmount)?,
+    )
+    .ok_or(AMMError::InvalidAmount)?
+    .checked_mul(PRICE_PRECISION)
+    .ok_or(AMMError::InvalidAmount)?;
+  let denominator = (amount_out as u128)
+    .checked_mul(
+      10u128
+        .checked_pow(decimals_in as u32)
+        .ok_or(AMMError::InvalidAmount)?,
+    )
+    .ok_or(AMMError::InvalidAmount)?;
 
-  Ok(())
+  Ok(
+    numerator
+      .checked_div(denominator)
+      .ok_or(AMMError::DivisionByZero)?,
+  )
 }
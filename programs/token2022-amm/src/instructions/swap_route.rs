@@ -0,0 +1,212 @@
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, MAX_ROUTE_HOPS},
+      error::AMMError,
+      event::SwapEvent,
+    },
+    instructions::swap::{apply_swap, compute_swap},
+    state::{AuthorityRegistry, Config, PoolState},
+    utils::{
+      swap::SwapDirection,
+      token::{calculate_transfer_fee_excluded_amount, ensure_pool_vault, reverify_pool_mints},
+    },
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+  },
+};
+
+// Accounts each hop takes from `remaining_accounts`, in this order: config, pool state,
+// vault authority, output mint, input vault, output vault, the user's output token
+// account and the output mint's token program
+const HOP_ACCOUNTS: usize = 8;
+
+/// One pool of a route, traded from `mint_in` to `mint_out`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RouteHop {
+  pub mint_in: Pubkey,
+  pub mint_out: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+  pub user: Signer<'info>,
+
+  pub mint_in: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+        mut,
+        constraint = user_token_in.mint == mint_in.key(),
+        constraint = user_token_in.owner == user.key(),
+    )]
+  pub user_token_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(address = *mint_in.to_account_info().owner)]
+  pub token_program_in: Interface<'info, TokenInterface>,
+
+  // Kill switch of the pool authority of the route, checked when supplied like for `swap`.
+  // Every hop's pool must then belong to that authority, so an unrelated registry cannot
+  // stand in for it
+  #[account(
+        seeds = [seed_prefix::REGISTRY, registry.authority.as_ref()],
+        bump = registry.bump,
+    )]
+  pub registry: Option<Box<Account<'info, AuthorityRegistry>>>,
+}
+
+// Swaps through a path of pools, each hop's output feeding the next hop's input. Every hop
+// is priced and checked like a single swap, with the amount actually received after the
+// output transfer fee passed on; only the last hop carries `min_amount_out`. A hop that
+// would trip its pool's circuit breaker fails the whole route, since locking the pool
+// midway would strand the user in an intermediate token
+pub fn handler<'info>(
+  ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+  path: Vec<RouteHop>,
+  amount_in: u64,
+  min_amount_out: u64,
+) -> Result<()> {
+  require!(
+    !path.is_empty() && path.len() <= MAX_ROUTE_HOPS,
+    AMMError::InvalidRoute
+  );
+  require!(
+    ctx.remaining_accounts.len() == path.len() * HOP_ACCOUNTS,
+    AMMError::InvalidRoute
+  );
+  require!(
+    path[0].mint_in == ctx.accounts.mint_in.key(),
+    AMMError::InvalidRoute
+  );
+  for pair in path.windows(2) {
+    require!(pair[0].mint_out == pair[1].mint_in, AMMError::InvalidRoute);
+  }
+  require!(amount_in > 0, AMMError::InvalidAmount);
+  if let Some(registry) = &ctx.accounts.registry {
+    registry.ensure_active()?;
+  }
+
+  let user = &ctx.accounts.user;
+  let mut mint_in = (*ctx.accounts.mint_in).clone();
+  let mut user_token_in = ctx.accounts.user_token_in.to_account_info();
+  let mut token_program_in = ctx.accounts.token_program_in.to_account_info();
+  let mut hop_amount_in = amount_in;
+
+  for (index, (hop, accounts)) in path
+    .iter()
+    .zip(ctx.remaining_accounts.chunks(HOP_ACCOUNTS))
+    .enumerate()
+  {
+    let config = Account::<Config>::try_from(&accounts[0])?;
+    let mut pool_state = Account::<PoolState>::try_from(&accounts[1])?;
+    let vault_authority = &accounts[2];
+    let mint_out = InterfaceAccount::<MintInterface>::try_from(&accounts[3])?;
+    let vault_in = InterfaceAccount::<TokenAccount>::try_from(&accounts[4])?;
+    let vault_out = InterfaceAccount::<TokenAccount>::try_from(&accounts[5])?;
+    let user_token_out = InterfaceAccount::<TokenAccount>::try_from(&accounts[6])?;
+    let token_program_out = &accounts[7];
+
+    require!(!config.locked, AMMError::PoolLocked);
+    if let Some(registry) = &ctx.accounts.registry {
+      require_keys_eq!(registry.authority, config.authority, AMMError::InvalidRoute);
+    }
+    require!(
+      pool_state.config == config.key()
+        && vault_authority.key() == config.vault_authority
+        && mint_out.key() == hop.mint_out
+        && user_token_out.mint == mint_out.key()
+        && user_token_out.owner == user.key()
+        && token_program_out.key() == *mint_out.to_account_info().owner,
+      AMMError::InvalidRoute
+    );
+
+    let direction = SwapDirection::resolve(&config, &mint_in.key(), &mint_out.key())?;
+    ensure_pool_vault(&config, &pool_state, &vault_in, &mint_in.key())?;
+    ensure_pool_vault(&config, &pool_state, &vault_out, &mint_out.key())?;
+    reverify_pool_mints([
+      (&mint_in, config.is_legacy_mint(&mint_in.key())),
+      (&mint_out, config.is_legacy_mint(&mint_out.key())),
+    ])?;
+
+    let hop_min_amount_out = if index == path.len() - 1 {
+      min_amount_out
+    } else {
+      0
+    };
+    // Routes carry no LP proof, so no hop gets the LP holder discount
+    let amounts = compute_swap(
+      &config,
+      &pool_state,
+      direction,
+      &user.key(),
+      0,
+      &mint_in,
+      &mint_out,
+      vault_out.amount,
+      hop_amount_in,
+      hop_min_amount_out,
+      None,
+    )?;
+    require!(
+      !config.breaks_circuit(amounts.price_move_bps),
+      AMMError::CircuitBreakerTripped
+    );
+
+    let transfer_in_ctx = CpiContext::new(
+      token_program_in.clone(),
+      TransferChecked {
+        from: user_token_in.clone(),
+        mint: mint_in.to_account_info(),
+        to: vault_in.to_account_info(),
+        authority: user.to_account_info(),
+      },
+    );
+    transfer_checked(transfer_in_ctx, hop_amount_in, mint_in.decimals)?;
+
+    let config_key = config.key();
+    let (vault_seed, vault_bump) = config.vault_authority_seed();
+    let auth_seeds = &[vault_seed, config_key.as_ref(), &[vault_bump]];
+    let signer = &[&auth_seeds[..]];
+
+    let transfer_out_ctx = CpiContext::new_with_signer(
+      token_program_out.clone(),
+      TransferChecked {
+        from: vault_out.to_account_info(),
+        mint: mint_out.to_account_info(),
+        to: user_token_out.to_account_info(),
+        authority: vault_authority.clone(),
+      },
+      signer,
+    );
+    transfer_checked(transfer_out_ctx, amounts.amount_out, mint_out.decimals)?;
+
+    // Accounts loaded by hand are not written back by Anchor
+    apply_swap(&mut pool_state, direction.is_x_to_y(), &amounts)?;
+    pool_state.exit(ctx.program_id)?;
+
+    emit!(SwapEvent {
+      config: config_key,
+      user: user.key(),
+      mint_in: mint_in.key(),
+      amount_in: hop_amount_in,
+      amount_out: amounts.amount_out,
+      protocol_fee: amounts.protocol_fee,
+      timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    hop_amount_in = calculate_transfer_fee_excluded_amount(&mint_out, amounts.amount_out)?.amount;
+    mint_in = mint_out;
+    user_token_in = user_token_out.to_account_info();
+    token_program_in = token_program_out.clone();
+  }
+
+  msg!(
+    "Routed {} tokens in through {} pools for {} tokens out",
+    amount_in,
+    path.len(),
+    hop_amount_in
+  );
+
+  Ok(())
+}
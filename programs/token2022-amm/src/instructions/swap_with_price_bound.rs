@@ -0,0 +1,10 @@
+use {
+  crate::instructions::swap::{process_swap, Swap},
+  anchor_lang::prelude::*,
+};
+
+// Swap bounded by the worst acceptable average price (input per unit of output,
+// decimals-normalized, scaled by PRICE_PRECISION) instead of a minimum output
+pub fn handler(ctx: Context<Swap>, amount_in: u64, max_price: u128) -> Result<()> {
+  process_swap(ctx, amount_in, 0, Some(max_price))
+}
@@ -0,0 +1,49 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::PoolUnlockedEvent},
+    state::{Config, PoolState},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct UnlockPool<'info> {
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  #[account(address = pool_state.vault_x)]
+  pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+  #[account(address = pool_state.vault_y)]
+  pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Reopens a pool `force_drain` marked stuck once the mint's freeze authority has thawed both
+// vaults. Anyone may call it, so LPs can get their funds out without waiting on the pool
+// authority; it only ever reopens a pool whose vaults can move funds again
+pub fn handler(ctx: Context<UnlockPool>) -> Result<()> {
+  require!(
+    !ctx.accounts.vault_x.is_frozen() && !ctx.accounts.vault_y.is_frozen(),
+    AMMError::VaultFrozen
+  );
+
+  let config = &mut ctx.accounts.config;
+  config.unlock()?;
+
+  emit!(PoolUnlockedEvent {
+    config: config.key(),
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
+  Ok(())
+}
@@ -0,0 +1,8 @@
+use {
+  crate::instructions::pause_all::{set_global_paused, SetGlobalPause},
+  anchor_lang::prelude::*,
+};
+
+pub fn handler(ctx: Context<SetGlobalPause>) -> Result<()> {
+  set_global_paused(ctx, false)
+}
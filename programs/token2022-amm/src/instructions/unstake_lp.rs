@@ -0,0 +1,110 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::LpUnstakedEvent},
+    state::{Config, PoolState, RewardConfig, UserReward},
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+  },
+};
+
+#[derive(Accounts)]
+pub struct UnstakeLp<'info> {
+  pub user: Signer<'info>,
+
+  #[account(
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump
+  )]
+  pub config: Box<Account<'info, Config>>,
+
+  #[account(
+    seeds = [seed_prefix::POOL, config.key().as_ref()],
+    bump,
+    has_one = lp_mint
+  )]
+  pub pool_state: Box<Account<'info, PoolState>>,
+
+  /// CHECK: PDA authority for the pool
+  #[account(
+    seeds = [seed_prefix::AUTH, config.key().as_ref()],
+    bump = config.auth_bump
+  )]
+  pub pool_authority: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::REWARD, config.key().as_ref()],
+    bump = reward_config.bump,
+    has_one = stake_vault
+  )]
+  pub reward_config: Box<Account<'info, RewardConfig>>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::USER_REWARD, reward_config.key().as_ref(), user.key().as_ref()],
+    bump = user_reward.bump
+  )]
+  pub user_reward: Box<Account<'info, UserReward>>,
+
+  pub lp_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+  #[account(
+    mut,
+    token::mint = lp_mint,
+    token::authority = user,
+    token::token_program = token_program_lp,
+  )]
+  pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub stake_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program_lp: Interface<'info, TokenInterface>,
+}
+
+// Returns staked LP to the user. The reward earned on it up to now stays pending for
+// `claim_reward`
+pub fn handler(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+  require!(amount > 0, AMMError::InvalidAmount);
+
+  let reward_config = &mut ctx.accounts.reward_config;
+  let user_reward = &mut ctx.accounts.user_reward;
+
+  let now = Clock::get()?.unix_timestamp;
+  reward_config.update(now)?;
+  user_reward.settle(reward_config.reward_per_lp_stored)?;
+
+  user_reward.unstake(amount)?;
+  reward_config.unstake(amount)?;
+
+  let config = &ctx.accounts.config;
+  let config_key = config.key();
+  let auth_seeds = &[seed_prefix::AUTH, config_key.as_ref(), &[config.auth_bump]];
+  let signer = &[&auth_seeds[..]];
+
+  let transfer_ctx = CpiContext::new_with_signer(
+    ctx.accounts.token_program_lp.to_account_info(),
+    TransferChecked {
+      from: ctx.accounts.stake_vault.to_account_info(),
+      mint: ctx.accounts.lp_mint.to_account_info(),
+      to: ctx.accounts.user_lp_token.to_account_info(),
+      authority: ctx.accounts.pool_authority.to_account_info(),
+    },
+    signer,
+  );
+  transfer_checked(transfer_ctx, amount, ctx.accounts.lp_mint.decimals)?;
+
+  msg!("Unstaked {} LP tokens", amount);
+
+  emit!(LpUnstakedEvent {
+    config: config_key,
+    user: ctx.accounts.user.key(),
+    amount,
+    total_staked: reward_config.total_staked,
+    timestamp: now,
+  });
+
+  Ok(())
+}
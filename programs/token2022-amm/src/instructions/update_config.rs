@@ -0,0 +1,34 @@
+use {
+  crate::{
+    common::{constant::seed_prefix, error::AMMError, event::ConfigUpdatedEvent},
+    state::{Config, UpdateConfigParams},
+  },
+  anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [seed_prefix::CONFIG, config.seed.to_le_bytes().as_ref()],
+    bump = config.config_bump,
+    has_one = authority @ AMMError::Unauthorized
+  )]
+  pub config: Box<Account<'info, Config>>,
+}
+
+pub fn handler(ctx: Context<UpdateConfig>, params: UpdateConfigParams) -> Result<()> {
+  let config = &mut ctx.accounts.config;
+  let now = Clock::get()?.unix_timestamp;
+  config.update(params, now)?;
+
+  emit!(ConfigUpdatedEvent {
+    config: config.key(),
+    fee: config.fee,
+    timestamp: now,
+  });
+
+  Ok(())
+}
@@ -1,14 +1,18 @@
 use {
   crate::{
-    common::error::AMMError,
-    state::{Config, PoolState},
+    common::{constant::BPS_DENOMINATOR, error::AMMError, event::WithdrawEvent},
+    state::{AuthorityRegistry, Config, PoolState},
+    utils::{
+      math::reserve_share,
+      token::{calculate_transfer_fee_excluded_amount, is_native_mint},
+    },
   },
   anchor_lang::prelude::*,
   anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-      burn, transfer_checked, Burn, Mint as MintInterface, TokenAccount, TokenInterface,
-      TransferChecked,
+      burn, close_account, transfer_checked, Burn, CloseAccount, Mint as MintInterface,
+      TokenAccount, TokenInterface, TransferChecked,
     },
   },
 };
@@ -39,22 +43,28 @@ pub struct Withdraw<'info> {
     )]
   pub pool_authority: UncheckedAccount<'info>,
 
+  /// CHECK: Owner of the pool vaults
+  #[account(address = config.vault_authority)]
+  pub vault_authority: UncheckedAccount<'info>,
+
   pub mint_x: Box<InterfaceAccount<'info, MintInterface>>,
   pub mint_y: Box<InterfaceAccount<'info, MintInterface>>,
 
   #[account(
         mut,
-        associated_token::mint = mint_x,
-        associated_token::authority = pool_authority,
-        associated_token::token_program = token_program_x,
+        address = pool_state.vault_x,
+        token::mint = mint_x,
+        token::authority = vault_authority,
+        token::token_program = token_program_x,
     )]
   pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(
         mut,
-        associated_token::mint = mint_y,
-        associated_token::authority = pool_authority,
-        associated_token::token_program = token_program_y,
+        address = pool_state.vault_y,
+        token::mint = mint_y,
+        token::authority = vault_authority,
+        token::token_program = token_program_y,
     )]
   pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -89,6 +99,13 @@ pub struct Withdraw<'info> {
     )]
   pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
+  // Kill switch shared by every pool of the config authority, checked when supplied
+  #[account(
+        seeds = [b"registry", config.authority.as_ref()],
+        bump = registry.bump,
+    )]
+  pub registry: Option<Box<Account<'info, AuthorityRegistry>>>,
+
   pub token_program_x: Interface<'info, TokenInterface>,
   pub token_program_y: Interface<'info, TokenInterface>,
   pub token_program_lp: Interface<'info, TokenInterface>,
@@ -101,29 +118,72 @@ pub fn handler(
   lp_amount: u64,
   min_amount_x: u64,
   min_amount_y: u64,
+  close_lp_account: bool,
+  unwrap_sol: bool,
+) -> Result<()> {
+  process_withdraw(
+    ctx.accounts,
+    lp_amount,
+    min_amount_x,
+    min_amount_y,
+    close_lp_account,
+    unwrap_sol,
+    None,
+  )
+}
+
+// Shares of each token's output, in bps, sent to the user's accounts and to the
+// recipients respectively
+pub(crate) struct SplitShares<'a, 'info> {
+  pub split_x_bps: [u16; 2],
+  pub split_y_bps: [u16; 2],
+  pub recipient_x: &'a InterfaceAccount<'info, TokenAccount>,
+  pub recipient_y: &'a InterfaceAccount<'info, TokenAccount>,
+}
+
+// Shared withdraw path. Without a split everything goes to the user's token accounts
+pub(crate) fn process_withdraw<'info>(
+  accounts: &mut Withdraw<'info>,
+  lp_amount: u64,
+  min_amount_x: u64,
+  min_amount_y: u64,
+  close_lp_account: bool,
+  unwrap_sol: bool,
+  split: Option<SplitShares<'_, 'info>>,
 ) -> Result<()> {
-  let pool_state = &mut ctx.accounts.pool_state;
-  let config = &ctx.accounts.config;
+  if let Some(registry) = &accounts.registry {
+    registry.ensure_active()?;
+  }
+
+  let pool_state = &mut accounts.pool_state;
+  let config = &accounts.config;
 
   require!(lp_amount > 0, AMMError::InvalidAmount);
   require!(pool_state.lp_supply > 0, AMMError::InsufficientLiquidity);
 
   // Calculate proportional withdrawal amounts
-  let amount_x = (lp_amount as u128)
-    .checked_mul(pool_state.reserve_x as u128)
-    .ok_or(AMMError::InvalidAmount)?
-    .checked_div(pool_state.lp_supply as u128)
-    .ok_or(AMMError::InvalidAmount)? as u64;
-
-  let amount_y = (lp_amount as u128)
-    .checked_mul(pool_state.reserve_y as u128)
-    .ok_or(AMMError::InvalidAmount)?
-    .checked_div(pool_state.lp_supply as u128)
-    .ok_or(AMMError::InvalidAmount)? as u64;
+  let amount_x = reserve_share(lp_amount, pool_state.reserve_x, pool_state.lp_supply)?;
+  let amount_y = reserve_share(lp_amount, pool_state.reserve_y, pool_state.lp_supply)?;
+
+  // Keep the pool priceable: unless all outstanding LP is burned, reserves must stay above the floor
+  if lp_amount < pool_state.lp_supply {
+    let remaining_x = pool_state
+      .reserve_x
+      .checked_sub(amount_x)
+      .ok_or(AMMError::InvalidAmount)?;
+    let remaining_y = pool_state
+      .reserve_y
+      .checked_sub(amount_y)
+      .ok_or(AMMError::InvalidAmount)?;
+    require!(
+      remaining_x >= config.min_reserve && remaining_y >= config.min_reserve,
+      AMMError::BelowMinReserve
+    );
+  }
 
   // Calculate actual amounts user will receive after transfer fees
   let actual_amount_x = if let Some(epoch_transfer_fee) =
-    crate::utils::token::get_epoch_transfer_fee(&ctx.accounts.mint_x)?
+    crate::utils::token::get_epoch_transfer_fee(&accounts.mint_x)?
   {
     let transfer_fee = epoch_transfer_fee
       .calculate_fee(amount_x)
@@ -136,7 +196,7 @@ pub fn handler(
   };
 
   let actual_amount_y = if let Some(epoch_transfer_fee) =
-    crate::utils::token::get_epoch_transfer_fee(&ctx.accounts.mint_y)?
+    crate::utils::token::get_epoch_transfer_fee(&accounts.mint_y)?
   {
     let transfer_fee = epoch_transfer_fee
       .calculate_fee(amount_y)
@@ -149,48 +209,106 @@ pub fn handler(
   };
 
   // Check slippage against actual amounts received
-  require!(actual_amount_x >= min_amount_x, AMMError::SlippageExceeded);
-  require!(actual_amount_y >= min_amount_y, AMMError::SlippageExceeded);
+  require!(
+    actual_amount_x >= min_amount_x,
+    AMMError::WithdrawSlippageExceeded
+  );
+  require!(
+    actual_amount_y >= min_amount_y,
+    AMMError::WithdrawSlippageExceeded
+  );
+
+  // Reserves exceeding the vault balances means the pool is desynced; fail with a clear
+  // error instead of an opaque token program failure on transfer
+  if amount_x > accounts.vault_x.amount || amount_y > accounts.vault_y.amount {
+    msg!(
+      "Vaults hold {} X / {} Y, cannot release {} X / {} Y",
+      accounts.vault_x.amount,
+      accounts.vault_y.amount,
+      amount_x,
+      amount_y
+    );
+    return Err(AMMError::InsufficientLiquidity.into());
+  }
 
   // Burn LP tokens from user
   let burn_ctx = CpiContext::new(
-    ctx.accounts.token_program_lp.to_account_info(),
+    accounts.token_program_lp.to_account_info(),
     Burn {
-      mint: ctx.accounts.lp_mint.to_account_info(),
-      from: ctx.accounts.user_lp_token.to_account_info(),
-      authority: ctx.accounts.user.to_account_info(),
+      mint: accounts.lp_mint.to_account_info(),
+      from: accounts.user_lp_token.to_account_info(),
+      authority: accounts.user.to_account_info(),
     },
   );
   burn(burn_ctx, lp_amount)?;
 
-  // Transfer tokens from vault to user
+  // Transfer tokens from vault to user, and to the recipients when splitting
   let config_key = config.key();
-  let auth_seeds = &[b"auth", config_key.as_ref(), &[config.auth_bump]];
+  let (vault_seed, vault_bump) = config.vault_authority_seed();
+  let auth_seeds = &[vault_seed, config_key.as_ref(), &[vault_bump]];
   let signer = &[&auth_seeds[..]];
 
-  let transfer_x_ctx = CpiContext::new_with_signer(
-    ctx.accounts.token_program_x.to_account_info(),
-    TransferChecked {
-      from: ctx.accounts.vault_x.to_account_info(),
-      mint: ctx.accounts.mint_x.to_account_info(),
-      to: ctx.accounts.user_token_x.to_account_info(),
-      authority: ctx.accounts.pool_authority.to_account_info(),
-    },
-    signer,
-  );
-  transfer_checked(transfer_x_ctx, amount_x, ctx.accounts.mint_x.decimals)?;
-
-  let transfer_y_ctx = CpiContext::new_with_signer(
-    ctx.accounts.token_program_y.to_account_info(),
-    TransferChecked {
-      from: ctx.accounts.vault_y.to_account_info(),
-      mint: ctx.accounts.mint_y.to_account_info(),
-      to: ctx.accounts.user_token_y.to_account_info(),
-      authority: ctx.accounts.pool_authority.to_account_info(),
-    },
-    signer,
-  );
-  transfer_checked(transfer_y_ctx, amount_y, ctx.accounts.mint_y.decimals)?;
+  let (split_x_bps, split_y_bps, recipient_x, recipient_y) = match split {
+    Some(split) => (
+      split.split_x_bps,
+      split.split_y_bps,
+      Some(split.recipient_x),
+      Some(split.recipient_y),
+    ),
+    None => ([BPS_DENOMINATOR, 0], [BPS_DENOMINATOR, 0], None, None),
+  };
+  for (is_x, token_program, vault, mint, user_token, recipient, amount, split_bps) in [
+    (
+      true,
+      &accounts.token_program_x,
+      &accounts.vault_x,
+      &accounts.mint_x,
+      &accounts.user_token_x,
+      recipient_x,
+      amount_x,
+      split_x_bps,
+    ),
+    (
+      false,
+      &accounts.token_program_y,
+      &accounts.vault_y,
+      &accounts.mint_y,
+      &accounts.user_token_y,
+      recipient_y,
+      amount_y,
+      split_y_bps,
+    ),
+  ] {
+    let to_user = u64::try_from(amount as u128 * split_bps[0] as u128 / BPS_DENOMINATOR as u128)
+      .map_err(|_| AMMError::MathOverflow)?;
+    let to_recipient = amount - to_user;
+
+    for (to, amount) in [
+      (Some(user_token.as_ref()), to_user),
+      (recipient, to_recipient),
+    ] {
+      if amount == 0 {
+        continue;
+      }
+      let to = to.ok_or(AMMError::InvalidSplit)?;
+
+      let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+          from: vault.to_account_info(),
+          mint: mint.to_account_info(),
+          to: to.to_account_info(),
+          authority: accounts.vault_authority.to_account_info(),
+        },
+        signer,
+      );
+      transfer_checked(transfer_ctx, amount, mint.decimals)?;
+      pool_state.record_transfer_fee(
+        is_x,
+        calculate_transfer_fee_excluded_amount(mint, amount)?.transfer_fee,
+      )?;
+    }
+  }
 
   // Update pool state
   pool_state.reserve_x = pool_state
@@ -206,6 +324,44 @@ pub fn handler(
     .checked_sub(lp_amount)
     .ok_or(AMMError::InvalidAmount)?;
 
+  // Closing the wrapped SOL account hands its whole balance back to the user as SOL
+  if unwrap_sol {
+    let (user_token, token_program) = if is_native_mint(&accounts.mint_x.key()) {
+      (&accounts.user_token_x, &accounts.token_program_x)
+    } else if is_native_mint(&accounts.mint_y.key()) {
+      (&accounts.user_token_y, &accounts.token_program_y)
+    } else {
+      return Err(AMMError::InvalidMint.into());
+    };
+    let close_ctx = CpiContext::new(
+      token_program.to_account_info(),
+      CloseAccount {
+        account: user_token.to_account_info(),
+        destination: accounts.user.to_account_info(),
+        authority: accounts.user.to_account_info(),
+      },
+    );
+    close_account(close_ctx)?;
+    msg!("Unwrapped SOL to {}", accounts.user.key());
+  }
+
+  // Return the rent of the LP account once nothing is left in it
+  if close_lp_account {
+    accounts.user_lp_token.reload()?;
+    if accounts.user_lp_token.amount == 0 {
+      let close_ctx = CpiContext::new(
+        accounts.token_program_lp.to_account_info(),
+        CloseAccount {
+          account: accounts.user_lp_token.to_account_info(),
+          destination: accounts.user.to_account_info(),
+          authority: accounts.user.to_account_info(),
+        },
+      );
+      close_account(close_ctx)?;
+      msg!("Closed empty LP token account");
+    }
+  }
+
   msg!(
     "Withdrew {} token X (received: {}), {} token Y (received: {}), burned {} LP tokens",
     amount_x,
@@ -215,5 +371,14 @@ pub fn handler(
     lp_amount
   );
 
+  emit!(WithdrawEvent {
+    config: config_key,
+    user: accounts.user.key(),
+    lp_burned: lp_amount,
+    amount_x,
+    amount_y,
+    timestamp: Clock::get()?.unix_timestamp,
+  });
+
   Ok(())
 }
@@ -0,0 +1,139 @@
+use {
+  crate::{
+    common::{constant::MAX_FEE_BATCH_POOLS, error::AMMError, event::FeeWithdrawalExecutedEvent},
+    state::{Config, PoolState},
+    utils::token::ensure_pool_vault,
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::{
+    transfer_checked, Mint as MintInterface, TokenAccount, TransferChecked,
+  },
+};
+
+// Accounts each pool takes from `remaining_accounts`, in this order: config, pool state,
+// vault authority, mint X, mint Y, vault X, vault Y, the authority's X and Y token accounts
+// and the token programs of mint X and mint Y
+const POOL_ACCOUNTS: usize = 11;
+
+#[derive(Accounts)]
+pub struct WithdrawProtocolFeesBatch<'info> {
+  pub authority: Signer<'info>,
+}
+
+// Sweeps the protocol fees of several pools of the signer to its own token accounts. Only
+// what is not queued for withdrawal moves, and only from pools without a withdrawal delay:
+// a timelocked pool goes through `queue_fee_withdrawal` and `execute_fee_withdrawal`, which
+// this never bypasses. Any pool of another authority fails the whole batch
+pub fn handler<'info>(
+  ctx: Context<'_, '_, 'info, 'info, WithdrawProtocolFeesBatch<'info>>,
+) -> Result<()> {
+  let pool_count = ctx.remaining_accounts.len() / POOL_ACCOUNTS;
+  require!(
+    pool_count > 0
+      && pool_count <= MAX_FEE_BATCH_POOLS
+      && ctx.remaining_accounts.len() % POOL_ACCOUNTS == 0,
+    AMMError::InvalidFeeBatch
+  );
+
+  let authority = &ctx.accounts.authority;
+  let now = Clock::get()?.unix_timestamp;
+
+  for accounts in ctx.remaining_accounts.chunks(POOL_ACCOUNTS) {
+    let config = Account::<Config>::try_from(&accounts[0])?;
+    let mut pool_state = Account::<PoolState>::try_from(&accounts[1])?;
+    let vault_authority = &accounts[2];
+    let mint_x = InterfaceAccount::<MintInterface>::try_from(&accounts[3])?;
+    let mint_y = InterfaceAccount::<MintInterface>::try_from(&accounts[4])?;
+    let vault_x = InterfaceAccount::<TokenAccount>::try_from(&accounts[5])?;
+    let vault_y = InterfaceAccount::<TokenAccount>::try_from(&accounts[6])?;
+    let recipient_x = InterfaceAccount::<TokenAccount>::try_from(&accounts[7])?;
+    let recipient_y = InterfaceAccount::<TokenAccount>::try_from(&accounts[8])?;
+    let token_program_x = &accounts[9];
+    let token_program_y = &accounts[10];
+
+    require_keys_eq!(config.authority, authority.key(), AMMError::Unauthorized);
+    require!(
+      config.fee_withdrawal_delay_at(now) == 0,
+      AMMError::FeeWithdrawalLocked
+    );
+    require!(
+      pool_state.config == config.key()
+        && vault_authority.key() == config.vault_authority
+        && mint_x.key() == config.mint_x
+        && mint_y.key() == config.mint_y
+        && token_program_x.key() == *mint_x.to_account_info().owner
+        && token_program_y.key() == *mint_y.to_account_info().owner,
+      AMMError::InvalidFeeBatch
+    );
+    ensure_pool_vault(&config, &pool_state, &vault_x, &mint_x.key())?;
+    ensure_pool_vault(&config, &pool_state, &vault_y, &mint_y.key())?;
+    require!(
+      recipient_x.mint == mint_x.key()
+        && recipient_x.owner == authority.key()
+        && recipient_y.mint == mint_y.key()
+        && recipient_y.owner == authority.key(),
+      AMMError::InvalidFeeBatch
+    );
+
+    // Queued amounts stay behind for their own timelocked withdrawal
+    let amount_x = pool_state
+      .protocol_fees_x
+      .saturating_sub(config.queued_fee_x);
+    let amount_y = pool_state
+      .protocol_fees_y
+      .saturating_sub(config.queued_fee_y);
+    if amount_x == 0 && amount_y == 0 {
+      continue;
+    }
+
+    pool_state.protocol_fees_x -= amount_x;
+    pool_state.protocol_fees_y -= amount_y;
+
+    let config_key = config.key();
+    let (vault_seed, vault_bump) = config.vault_authority_seed();
+    let auth_seeds = &[vault_seed, config_key.as_ref(), &[vault_bump]];
+    let signer = &[&auth_seeds[..]];
+
+    for (token_program, vault, mint, recipient, amount) in [
+      (token_program_x, &vault_x, &mint_x, &recipient_x, amount_x),
+      (token_program_y, &vault_y, &mint_y, &recipient_y, amount_y),
+    ] {
+      if amount == 0 {
+        continue;
+      }
+
+      let transfer_ctx = CpiContext::new_with_signer(
+        token_program.clone(),
+        TransferChecked {
+          from: vault.to_account_info(),
+          mint: mint.to_account_info(),
+          to: recipient.to_account_info(),
+          authority: vault_authority.clone(),
+        },
+        signer,
+      );
+      transfer_checked(transfer_ctx, amount, mint.decimals)?;
+    }
+
+    // Accounts loaded by hand are not written back by Anchor
+    pool_state.exit(ctx.program_id)?;
+
+    msg!(
+      "Swept protocol fees of {} X, {} Y from {}",
+      amount_x,
+      amount_y,
+      config_key
+    );
+
+    emit!(FeeWithdrawalExecutedEvent {
+      config: config_key,
+      amount_x,
+      amount_y,
+      recipient_x: recipient_x.key(),
+      recipient_y: recipient_y.key(),
+      timestamp: now,
+    });
+  }
+
+  Ok(())
+}
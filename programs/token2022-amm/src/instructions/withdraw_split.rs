@@ -0,0 +1,65 @@
+use {
+  crate::{
+    common::{constant::BPS_DENOMINATOR, error::AMMError},
+    // The nested `Withdraw` accounts need the modules its derive generates in scope
+    instructions::withdraw::*,
+  },
+  anchor_lang::prelude::*,
+  anchor_spl::token_interface::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct WithdrawSplit<'info> {
+  pub withdraw: Withdraw<'info>,
+
+  /// Second destination for token X
+  #[account(
+        mut,
+        token::mint = withdraw.mint_x,
+        token::token_program = withdraw.token_program_x,
+    )]
+  pub recipient_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  /// Second destination for token Y
+  #[account(
+        mut,
+        token::mint = withdraw.mint_y,
+        token::token_program = withdraw.token_program_y,
+    )]
+  pub recipient_y: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Withdraws like `withdraw`, but splits each token's output between the user's token
+// account and `recipient_x` / `recipient_y`. The first entry of each split is the user's
+// share, the second the recipient's; both are in bps and must sum to 10000.
+pub fn handler(
+  ctx: Context<WithdrawSplit>,
+  lp_amount: u64,
+  min_amount_x: u64,
+  min_amount_y: u64,
+  split_x_bps: [u16; 2],
+  split_y_bps: [u16; 2],
+) -> Result<()> {
+  for split_bps in [split_x_bps, split_y_bps] {
+    require!(
+      split_bps[0] as u32 + split_bps[1] as u32 == BPS_DENOMINATOR as u32,
+      AMMError::InvalidSplit
+    );
+  }
+
+  let accounts = ctx.accounts;
+  process_withdraw(
+    &mut accounts.withdraw,
+    lp_amount,
+    min_amount_x,
+    min_amount_y,
+    false,
+    false,
+    Some(SplitShares {
+      split_x_bps,
+      split_y_bps,
+      recipient_x: &accounts.recipient_x,
+      recipient_y: &accounts.recipient_y,
+    }),
+  )
+}
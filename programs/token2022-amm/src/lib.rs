@@ -5,7 +5,7 @@ mod instructions;
 mod state;
 mod utils;
 
-use instructions::*;
+use {instructions::*, state::UpdateConfigParams};
 
 declare_id!("2AXqNb7CQRbS9z7U2NXZXVmzrJ3FxD2ztxiVASfgxUL2");
 
@@ -17,9 +17,20 @@ pub mod token2022_amm {
     ctx: Context<InitializePool>,
     seed: u64,
     fee: u16,
-    white_list_lp: Option<[Pubkey; 10]>,
+    white_list_lp: Option<Vec<Pubkey>>,
+    separate_vault_authority: bool,
+    max_fee_bearing_sides: u8,
+    max_transfer_fee_bps: u16,
   ) -> Result<()> {
-    init_pool::handler(ctx, seed, fee, white_list_lp)
+    init_pool::handler(
+      ctx,
+      seed,
+      fee,
+      white_list_lp,
+      separate_vault_authority,
+      max_fee_bearing_sides,
+      max_transfer_fee_bps,
+    )
   }
 
   pub fn deposit(
@@ -27,8 +38,10 @@ pub mod token2022_amm {
     amount_x: u64,
     amount_y: u64,
     min_lp_out: u64,
+    guards: DepositGuards,
+    wrap_sol: bool,
   ) -> Result<()> {
-    deposit::handler(ctx, amount_x, amount_y, min_lp_out)
+    deposit::handler(ctx, amount_x, amount_y, min_lp_out, guards, wrap_sol)
   }
 
   pub fn withdraw(
@@ -36,11 +49,263 @@ pub mod token2022_amm {
     lp_amount: u64,
     min_amount_x: u64,
     min_amount_y: u64,
+    close_lp_account: bool,
+    unwrap_sol: bool,
   ) -> Result<()> {
-    withdraw::handler(ctx, lp_amount, min_amount_x, min_amount_y)
+    withdraw::handler(
+      ctx,
+      lp_amount,
+      min_amount_x,
+      min_amount_y,
+      close_lp_account,
+      unwrap_sol,
+    )
   }
 
-  pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
-    swap::handler(ctx, amount_in, min_amount_out)
+  pub fn swap(
+    ctx: Context<Swap>,
+    amount_in: u64,
+    min_amount_out: u64,
+    guards: SwapGuards,
+  ) -> Result<()> {
+    swap::handler(ctx, amount_in, min_amount_out, guards)
+  }
+
+  pub fn check_mint_supported(ctx: Context<CheckMintSupported>) -> Result<MintSupport> {
+    check_mint_supported::handler(ctx)
+  }
+
+  pub fn update_config(ctx: Context<UpdateConfig>, params: UpdateConfigParams) -> Result<()> {
+    update_config::handler(ctx, params)
+  }
+
+  pub fn set_whitelist_enabled(ctx: Context<SetWhitelistEnabled>, enabled: bool) -> Result<()> {
+    set_whitelist_enabled::handler(ctx, enabled)
+  }
+
+  pub fn initialize_reward(
+    ctx: Context<InitializeReward>,
+    reward_rate_per_second: u64,
+  ) -> Result<()> {
+    initialize_reward::handler(ctx, reward_rate_per_second)
+  }
+
+  pub fn fund_reward(ctx: Context<FundReward>, amount: u64) -> Result<()> {
+    fund_reward::handler(ctx, amount)
+  }
+
+  pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+    stake_lp::handler(ctx, amount)
+  }
+
+  pub fn unstake_lp(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+    unstake_lp::handler(ctx, amount)
+  }
+
+  pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+    claim_reward::handler(ctx)
+  }
+
+  pub fn get_tvl(ctx: Context<GetTvl>) -> Result<PoolTvl> {
+    get_tvl::handler(ctx)
+  }
+
+  pub fn initialize_pool_with_pda_vaults(
+    ctx: Context<InitializePoolWithPdaVaults>,
+    seed: u64,
+    fee: u16,
+    white_list_lp: Option<Vec<Pubkey>>,
+    max_fee_bearing_sides: u8,
+    max_transfer_fee_bps: u16,
+  ) -> Result<()> {
+    init_pool_with_pda_vaults::handler(
+      ctx,
+      seed,
+      fee,
+      white_list_lp,
+      max_fee_bearing_sides,
+      max_transfer_fee_bps,
+    )
+  }
+
+  pub fn swap_with_price_bound(ctx: Context<Swap>, amount_in: u64, max_price: u128) -> Result<()> {
+    swap_with_price_bound::handler(ctx, amount_in, max_price)
+  }
+
+  pub fn migrate_vault(ctx: Context<MigrateVault>, which: u8) -> Result<()> {
+    migrate_vault::handler(ctx, which)
+  }
+
+  pub fn get_effective_fee(
+    ctx: Context<GetEffectiveFee>,
+    amount_in: u64,
+    user: Pubkey,
+  ) -> Result<u16> {
+    get_effective_fee::handler(ctx, amount_in, user)
+  }
+
+  pub fn check_pool_health(ctx: Context<CheckPoolHealth>) -> Result<PoolHealth> {
+    check_pool_health::handler(ctx)
+  }
+
+  pub fn deposit_and_balance(
+    ctx: Context<Deposit>,
+    amount_x: u64,
+    amount_y: u64,
+    min_lp_out: u64,
+  ) -> Result<()> {
+    deposit_and_balance::handler(ctx, amount_x, amount_y, min_lp_out)
+  }
+
+  pub fn resize_whitelist(ctx: Context<ResizeWhitelist>, new_capacity: u16) -> Result<()> {
+    resize_whitelist::handler(ctx, new_capacity)
+  }
+
+  pub fn add_to_whitelist(ctx: Context<UpdateWhitelist>, user: Pubkey) -> Result<()> {
+    add_to_whitelist::handler(ctx, user)
+  }
+
+  pub fn remove_from_whitelist(ctx: Context<UpdateWhitelist>, user: Pubkey) -> Result<()> {
+    remove_from_whitelist::handler(ctx, user)
+  }
+
+  pub fn force_drain(ctx: Context<ForceDrain>) -> Result<()> {
+    force_drain::handler(ctx)
+  }
+
+  pub fn withdraw_split(
+    ctx: Context<WithdrawSplit>,
+    lp_amount: u64,
+    min_amount_x: u64,
+    min_amount_y: u64,
+    split_x_bps: [u16; 2],
+    split_y_bps: [u16; 2],
+  ) -> Result<()> {
+    withdraw_split::handler(
+      ctx,
+      lp_amount,
+      min_amount_x,
+      min_amount_y,
+      split_x_bps,
+      split_y_bps,
+    )
+  }
+
+  pub fn pause_all(ctx: Context<SetGlobalPause>) -> Result<()> {
+    pause_all::handler(ctx)
+  }
+
+  pub fn unpause_all(ctx: Context<SetGlobalPause>) -> Result<()> {
+    unpause_all::handler(ctx)
+  }
+
+  pub fn get_fee_schedule(ctx: Context<GetFeeSchedule>) -> Result<FeeSchedule> {
+    get_fee_schedule::handler(ctx)
+  }
+
+  pub fn get_fee_stats(ctx: Context<GetFeeStats>) -> Result<FeeStats> {
+    get_fee_stats::handler(ctx)
+  }
+
+  pub fn get_price_impact(ctx: Context<GetPriceImpact>, amount_in: u64) -> Result<u16> {
+    get_price_impact::handler(ctx, amount_in)
+  }
+
+  pub fn get_pools_for_mint(ctx: Context<GetPoolsForMint>, mint: Pubkey) -> Result<Vec<Pubkey>> {
+    get_pools_for_mint::handler(ctx, mint)
+  }
+
+  pub fn quote_withdraw_exact(
+    ctx: Context<QuoteWithdrawExact>,
+    amount_x_out: u64,
+    amount_y_out: u64,
+  ) -> Result<u64> {
+    quote_withdraw_exact::handler(ctx, amount_x_out, amount_y_out)
+  }
+
+  pub fn get_pool_accounts(ctx: Context<GetPoolAccounts>) -> Result<PoolAddresses> {
+    get_pool_accounts::handler(ctx)
+  }
+
+  pub fn cheapest_direction(
+    ctx: Context<CheapestDirection>,
+    amount: u64,
+  ) -> Result<DirectionQuote> {
+    cheapest_direction::handler(ctx, amount)
+  }
+
+  pub fn queue_fee_withdrawal(
+    ctx: Context<QueueFeeWithdrawal>,
+    amount_x: u64,
+    amount_y: u64,
+  ) -> Result<()> {
+    queue_fee_withdrawal::handler(ctx, amount_x, amount_y)
+  }
+
+  pub fn execute_fee_withdrawal(ctx: Context<ExecuteFeeWithdrawal>) -> Result<()> {
+    execute_fee_withdrawal::handler(ctx)
+  }
+
+  pub fn withdraw_protocol_fees_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WithdrawProtocolFeesBatch<'info>>,
+  ) -> Result<()> {
+    withdraw_protocol_fees_batch::handler(ctx)
+  }
+
+  pub fn get_pending_rewards(ctx: Context<GetPendingRewards>, user: Pubkey) -> Result<u64> {
+    get_pending_rewards::handler(ctx, user)
+  }
+
+  pub fn unlock_pool(ctx: Context<UnlockPool>) -> Result<()> {
+    unlock_pool::handler(ctx)
+  }
+
+  pub fn reset_circuit_breaker(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+    reset_circuit_breaker::handler(ctx)
+  }
+
+  pub fn snapshot(ctx: Context<Snapshot>) -> Result<()> {
+    snapshot::handler(ctx)
+  }
+
+  pub fn compound_protocol_fees(ctx: Context<CompoundProtocolFees>) -> Result<()> {
+    compound_protocol_fees::handler(ctx)
+  }
+
+  pub fn is_solvent(ctx: Context<IsSolvent>) -> Result<PoolSolvency> {
+    is_solvent::handler(ctx)
+  }
+
+  pub fn rescue_tokens(ctx: Context<RescueTokens>, mint: Pubkey) -> Result<()> {
+    rescue_tokens::handler(ctx, mint)
+  }
+
+  pub fn get_config_compact(ctx: Context<GetConfigCompact>) -> Result<ConfigCompact> {
+    get_config_compact::handler(ctx)
+  }
+
+  pub fn swap_route<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    path: Vec<RouteHop>,
+    amount_in: u64,
+    min_amount_out: u64,
+  ) -> Result<()> {
+    swap_route::handler(ctx, path, amount_in, min_amount_out)
+  }
+
+  pub fn get_apr(ctx: Context<GetApr>) -> Result<PoolApr> {
+    get_apr::handler(ctx)
+  }
+
+  pub fn migrate_pool_state<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigratePoolState<'info>>,
+  ) -> Result<()> {
+    migrate_pool_state::handler(ctx)
+  }
+
+  pub fn migrate_config<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigrateConfig<'info>>,
+  ) -> Result<()> {
+    migrate_config::handler(ctx)
   }
 }
@@ -0,0 +1,26 @@
+use {crate::common::error::AMMError, anchor_lang::prelude::*};
+
+// One per pool authority; lets the operator halt every pool it administers at once.
+// Pools opt into the check by passing the registry to the mutating instructions.
+#[account]
+#[derive(InitSpace)]
+pub struct AuthorityRegistry {
+  pub authority: Pubkey,
+  pub global_paused: bool,
+  pub bump: u8,
+}
+
+impl AuthorityRegistry {
+  pub fn set_global_paused(&mut self, authority: Pubkey, paused: bool, bump: u8) {
+    self.authority = authority;
+    self.global_paused = paused;
+    self.bump = bump;
+
+    msg!("Pools of {} paused: {}", authority, paused);
+  }
+
+  pub fn ensure_active(&self) -> Result<()> {
+    require!(!self.global_paused, AMMError::GloballyPaused);
+    Ok(())
+  }
+}
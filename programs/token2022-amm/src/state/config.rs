@@ -1,22 +1,123 @@
-use anchor_lang::prelude::*;
+use {
+  crate::{
+    common::{
+      constant::{seed_prefix, BPS_DENOMINATOR, DISCRIMINATOR, MAX_FEE_BPS},
+      error::AMMError,
+    },
+    utils::migration::{deserialize_zero_extended, Migrate},
+  },
+  anchor_lang::prelude::*,
+};
 
+/// Whitelist entries a new config has room for
 pub const MAX_WHITE_LIST_LP: usize = 10;
+/// Upper bound `resize_whitelist` can grow the whitelist to
+pub const MAX_WHITELIST_CAPACITY: u16 = 200;
+/// Default and upper bound of the swap vault coverage grace, in raw units of the output mint
+pub const DEFAULT_VAULT_COVERAGE_GRACE: u64 = 1;
+pub const MAX_VAULT_COVERAGE_GRACE: u64 = 10;
+/// Layout version of a config; bump it with every field appended to `Config` and give the
+/// new fields their defaults in its `Migrate::migrate`
+pub const CONFIG_VERSION: u8 = 2;
+/// Layout version of a pool state; bump it with every field appended to `PoolState` and
+/// give the new fields their defaults in its `Migrate::migrate`
+pub const POOL_STATE_VERSION: u8 = 1;
+
+/// How fractional LP amounts are rounded when minting on deposit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum LpRounding {
+  RoundDown,
+  RoundToNearest,
+}
 
 #[account]
-#[derive(InitSpace, Copy)]
+#[derive(InitSpace)]
 pub struct Config {
   pub seed: u64,
   pub authority: Pubkey,
   pub mint_x: Pubkey,
   pub mint_y: Pubkey,
+  /// Swap fee while no launch decay is configured. With one, `target_fee` is the fee that
+  /// applies after the decay, and updates go there instead
   pub fee: u16,
   pub locked: bool,
 
-  pub white_list_lp: Option<[Pubkey; MAX_WHITE_LIST_LP]>,
+  /// Space is reserved for `whitelist_capacity` entries
+  #[max_len(MAX_WHITE_LIST_LP)]
+  pub white_list_lp: Vec<Pubkey>,
+  /// Restricts deposits to `white_list_lp` unless the list is empty
+  pub whitelist_enabled: bool,
 
   pub auth_bump: u8,
   pub config_bump: u8,
   pub lp_bump: u8,
+
+  /// Floor both reserves must stay above after a partial withdrawal (0 disables)
+  pub min_reserve: u64,
+  /// Largest swap input as a share of the input reserve (>= 10000 disables)
+  pub max_swap_ratio_bps: u16,
+  /// Fee charged on the unbalanced excess of a deposit, kept by existing LPs (10000 mints nothing for it)
+  pub imbalance_fee_bps: u16,
+  /// Rounding applied to minted LP (withdrawals always round down)
+  pub lp_rounding: LpRounding,
+  /// LP balance a swapper must hold to get the swap fee discount
+  pub lp_discount_threshold: u64,
+  /// Discount on the swap fee for LP holders above the threshold (0 disables)
+  pub lp_discount_bps: u16,
+  /// Share of the swap fee (in bps of the fee) set aside for the protocol
+  pub protocol_fee_bps: u16,
+  /// Smallest swap input the protocol fee applies to; below it the whole fee goes to LPs
+  pub protocol_fee_min_amount: u64,
+  /// Whitelist entries the account is currently sized for
+  pub whitelist_capacity: u16,
+  /// Set by `force_drain` when a vault is frozen and the program cannot thaw it
+  pub stuck: bool,
+  /// Unix timestamp the pool was created at, never changed afterwards
+  pub created_at: i64,
+  /// Swap fee at pool creation while the launch decay is active
+  pub launch_fee: u16,
+  /// Swap fee the launch fee decays to, and applies once the decay is over
+  pub target_fee: u16,
+  /// Seconds after creation over which the launch fee decays linearly (0 disables, so
+  /// `fee` applies)
+  pub decay_duration: i64,
+  /// Owner of the vaults; the pool authority unless the pool was created with a separate
+  /// vault authority PDA
+  pub vault_authority: Pubkey,
+  pub vault_auth_bump: u8,
+  pub separate_vault_authority: bool,
+  /// Market maker charged `mm_fee_bps` instead of the regular swap fee (default key: none)
+  pub privileged_mm: Pubkey,
+  pub mm_fee_bps: u16,
+  /// Whether each mint is owned by the legacy Token program, cached at init so hot paths
+  /// can skip re-verifying mints that cannot gain extensions
+  pub mint_x_legacy: bool,
+  pub mint_y_legacy: bool,
+  /// Shortfall of the output vault against a swap's output tolerated as transfer fee
+  /// rounding; the swap then pays out the vault balance instead (0 makes the check strict)
+  pub vault_coverage_grace: u64,
+  /// Largest LP balance a single user may reach through deposits (0 disables)
+  pub max_lp_per_user: u64,
+  /// Floor the resolved swap fee cannot go below, whatever discount or override applies
+  pub min_effective_fee_bps: u16,
+  /// Seconds a queued protocol fee withdrawal waits before it can be executed. New pools
+  /// start at 0, which is harmless while `protocol_fee_bps` is 0 too; once set, lowering it
+  /// only takes effect after the delay in force, see `pending_fee_withdrawal_delay`
+  pub fee_withdrawal_delay: i64,
+  /// Protocol fees queued for withdrawal (both 0: nothing queued) and when they unlock
+  pub queued_fee_x: u64,
+  pub queued_fee_y: u64,
+  pub fee_withdrawal_unlock_at: i64,
+  /// Spot price move a single swap may cause before it is refused and the pool locked
+  /// (0 disables)
+  pub circuit_breaker_bps: u16,
+  /// Layout the account was created or last migrated under, see `CONFIG_VERSION`. Configs
+  /// predating it read as 0
+  pub version: u8,
+  /// Lowered `fee_withdrawal_delay` waiting to replace the current one at
+  /// `fee_withdrawal_delay_effective_at` (0: nothing pending)
+  pub pending_fee_withdrawal_delay: i64,
+  pub fee_withdrawal_delay_effective_at: i64,
 }
 
 pub struct InitConfigParams {
@@ -25,14 +126,48 @@ pub struct InitConfigParams {
   pub mint_x: Pubkey,
   pub mint_y: Pubkey,
   pub fee: u16,
-  pub white_list_lp: Option<[Pubkey; MAX_WHITE_LIST_LP]>,
+  pub white_list_lp: Option<Vec<Pubkey>>,
   pub auth_bump: u8,
   pub config_bump: u8,
   pub lp_bump: u8,
+  pub created_at: i64,
+  pub vault_authority: Pubkey,
+  pub vault_auth_bump: u8,
+  pub separate_vault_authority: bool,
+  pub mint_x_legacy: bool,
+  pub mint_y_legacy: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct UpdateConfigParams {
+  pub fee: Option<u16>,
+  pub min_reserve: Option<u64>,
+  pub max_swap_ratio_bps: Option<u16>,
+  pub imbalance_fee_bps: Option<u16>,
+  pub lp_rounding: Option<LpRounding>,
+  pub lp_discount_threshold: Option<u64>,
+  pub lp_discount_bps: Option<u16>,
+  pub protocol_fee_bps: Option<u16>,
+  pub protocol_fee_min_amount: Option<u64>,
+  pub launch_fee: Option<u16>,
+  pub target_fee: Option<u16>,
+  pub decay_duration: Option<i64>,
+  pub privileged_mm: Option<Pubkey>,
+  pub mm_fee_bps: Option<u16>,
+  pub vault_coverage_grace: Option<u64>,
+  pub max_lp_per_user: Option<u64>,
+  pub min_effective_fee_bps: Option<u16>,
+  pub fee_withdrawal_delay: Option<i64>,
+  pub circuit_breaker_bps: Option<u16>,
 }
 
 impl Config {
-  pub fn init(&mut self, params: InitConfigParams) {
+  /// Account size with room for `capacity` whitelist entries
+  pub fn space_for_whitelist(capacity: u16) -> usize {
+    DISCRIMINATOR + Config::INIT_SPACE - MAX_WHITE_LIST_LP * 32 + capacity as usize * 32
+  }
+
+  pub fn init(&mut self, params: InitConfigParams) -> Result<()> {
     let InitConfigParams {
       seed,
       authority,
@@ -43,6 +178,12 @@ impl Config {
       auth_bump,
       config_bump,
       lp_bump,
+      created_at,
+      vault_authority,
+      vault_auth_bump,
+      separate_vault_authority,
+      mint_x_legacy,
+      mint_y_legacy,
     } = params;
 
     self.seed = seed;
@@ -51,13 +192,503 @@ impl Config {
     self.mint_y = mint_y;
     self.fee = fee;
     self.locked = false;
-    self.white_list_lp = white_list_lp;
+    let white_list_lp = white_list_lp.map(|mut list| {
+      // Unused slots may be padded with the default key
+      list.retain(|key| *key != Pubkey::default());
+      list
+    });
+    if let Some(list) = &white_list_lp {
+      require!(list.len() <= MAX_WHITE_LIST_LP, AMMError::WhitelistFull);
+    }
+
+    self.whitelist_enabled = white_list_lp.is_some();
+    self.white_list_lp = white_list_lp.unwrap_or_default();
     self.auth_bump = auth_bump;
     self.config_bump = config_bump;
     self.lp_bump = lp_bump;
+    self.min_reserve = 0;
+    self.max_swap_ratio_bps = BPS_DENOMINATOR;
+    self.imbalance_fee_bps = BPS_DENOMINATOR;
+    self.lp_rounding = LpRounding::RoundDown;
+    self.lp_discount_threshold = 0;
+    self.lp_discount_bps = 0;
+    self.protocol_fee_bps = 0;
+    self.protocol_fee_min_amount = 0;
+    self.whitelist_capacity = MAX_WHITE_LIST_LP as u16;
+    self.stuck = false;
+    self.created_at = created_at;
+    self.launch_fee = 0;
+    self.target_fee = 0;
+    self.decay_duration = 0;
+    self.vault_authority = vault_authority;
+    self.vault_auth_bump = vault_auth_bump;
+    self.separate_vault_authority = separate_vault_authority;
+    self.privileged_mm = Pubkey::default();
+    self.mm_fee_bps = 0;
+    self.mint_x_legacy = mint_x_legacy;
+    self.mint_y_legacy = mint_y_legacy;
+    self.vault_coverage_grace = DEFAULT_VAULT_COVERAGE_GRACE;
+    self.max_lp_per_user = 0;
+    self.min_effective_fee_bps = 0;
+    self.fee_withdrawal_delay = 0;
+    self.queued_fee_x = 0;
+    self.queued_fee_y = 0;
+    self.fee_withdrawal_unlock_at = 0;
+    self.circuit_breaker_bps = 0;
+    self.version = CONFIG_VERSION;
+    self.pending_fee_withdrawal_delay = 0;
+    self.fee_withdrawal_delay_effective_at = 0;
 
     msg!("Pool initialized with seed: {}, fee: {}", seed, fee);
     msg!("Mint X: {}, Mint Y: {}", mint_x, mint_y);
+
+    Ok(())
+  }
+
+  /// Seed prefix and bump the vault owner signs transfers out of the vaults with
+  pub fn vault_authority_seed(&self) -> (&'static [u8], u8) {
+    if self.separate_vault_authority {
+      (seed_prefix::VAULT_AUTH, self.vault_auth_bump)
+    } else {
+      (seed_prefix::AUTH, self.auth_bump)
+    }
+  }
+
+  /// Pool authority PDA derived with the stored bump. A bad bump fails here with a clear
+  /// error rather than later inside a CPI the PDA is meant to sign
+  pub fn pool_authority_key(&self, config: &Pubkey) -> Result<Pubkey> {
+    Pubkey::create_program_address(
+      &[seed_prefix::AUTH, config.as_ref(), &[self.auth_bump]],
+      &crate::ID,
+    )
+    .map_err(|_| AMMError::InvalidAuthorityBump.into())
+  }
+
+  pub fn set_whitelist_enabled(&mut self, enabled: bool) {
+    self.whitelist_enabled = enabled;
+
+    msg!("Whitelist enforcement enabled: {}", enabled);
+  }
+
+  pub fn mark_stuck(&mut self) {
+    self.stuck = true;
+    self.locked = true;
+
+    msg!("Pool marked stuck and locked");
+  }
+
+  /// Whether a swap moving the spot price by `price_move_bps` trips the circuit breaker
+  pub fn breaks_circuit(&self, price_move_bps: u16) -> bool {
+    self.circuit_breaker_bps > 0 && price_move_bps > self.circuit_breaker_bps
+  }
+
+  pub fn trip_circuit_breaker(&mut self, price_move_bps: u16) {
+    self.locked = true;
+
+    msg!(
+      "Circuit breaker tripped by a {} bps price move, pool locked",
+      price_move_bps
+    );
+  }
+
+  // A stuck pool stays locked: its frozen vaults cannot move funds either way, and it
+  // reopens through `unlock` once they are thawed
+  pub fn reset_circuit_breaker(&mut self) -> Result<()> {
+    require!(!self.stuck, AMMError::PoolLocked);
+    self.locked = false;
+
+    msg!("Circuit breaker reset, pool unlocked");
+    Ok(())
+  }
+
+  // Only once the mint's freeze authority has thawed the vaults, checked by the caller
+  pub fn unlock(&mut self) -> Result<()> {
+    require!(self.stuck, AMMError::PoolNotStuck);
+    self.stuck = false;
+    self.locked = false;
+
+    msg!("Pool no longer stuck, unlocked");
+    Ok(())
+  }
+
+  pub fn is_legacy_mint(&self, mint: &Pubkey) -> bool {
+    (*mint == self.mint_x && self.mint_x_legacy) || (*mint == self.mint_y && self.mint_y_legacy)
+  }
+
+  pub fn is_whitelisted(&self, user: &Pubkey) -> bool {
+    self.white_list_lp.contains(user)
+  }
+
+  /// Whether `user` may deposit. An enabled whitelist with no entries admits everyone, as a
+  /// config without a whitelist did before enforcement could be toggled
+  pub fn admits_depositor(&self, user: &Pubkey) -> bool {
+    !self.whitelist_enabled || self.white_list_lp.is_empty() || self.is_whitelisted(user)
+  }
+
+  pub fn add_to_whitelist(&mut self, user: Pubkey) -> Result<()> {
+    require!(!self.is_whitelisted(&user), AMMError::AlreadyWhitelisted);
+    require!(
+      self.white_list_lp.len() < self.whitelist_capacity as usize,
+      AMMError::WhitelistFull
+    );
+
+    self.white_list_lp.push(user);
+
+    msg!("Added {} to the whitelist", user);
+    Ok(())
+  }
+
+  pub fn remove_from_whitelist(&mut self, user: Pubkey) -> Result<()> {
+    let index = self
+      .white_list_lp
+      .iter()
+      .position(|key| *key == user)
+      .ok_or(AMMError::NotWhitelisted)?;
+
+    self.white_list_lp.swap_remove(index);
+
+    msg!("Removed {} from the whitelist", user);
+    Ok(())
+  }
+
+  pub fn resize_whitelist(&mut self, new_capacity: u16) -> Result<()> {
+    require!(
+      new_capacity <= MAX_WHITELIST_CAPACITY && new_capacity as usize >= self.white_list_lp.len(),
+      AMMError::InvalidWhitelistCapacity
+    );
+
+    self.whitelist_capacity = new_capacity;
+
+    msg!("Whitelist capacity set to: {}", new_capacity);
+    Ok(())
+  }
+
+  pub fn update(&mut self, params: UpdateConfigParams, now: i64) -> Result<()> {
+    let UpdateConfigParams {
+      fee,
+      min_reserve,
+      max_swap_ratio_bps,
+      imbalance_fee_bps,
+      lp_rounding,
+      lp_discount_threshold,
+      lp_discount_bps,
+      protocol_fee_bps,
+      protocol_fee_min_amount,
+      launch_fee,
+      target_fee,
+      decay_duration,
+      privileged_mm,
+      mm_fee_bps,
+      vault_coverage_grace,
+      max_lp_per_user,
+      min_effective_fee_bps,
+      fee_withdrawal_delay,
+      circuit_breaker_bps,
+    } = params;
+
+    if let Some(fee) = fee {
+      // `fee` is not read while a decay is configured, so an update would be silently lost
+      require!(
+        decay_duration.unwrap_or(self.decay_duration) == 0,
+        AMMError::FeeDecayConfigured
+      );
+      // Same bound as at pool init (max 10% = 1000 basis points)
+      require!(fee <= MAX_FEE_BPS, AMMError::InvalidAmount);
+      self.fee = fee;
+      msg!("Fee updated to: {}", fee);
+    }
+
+    if let Some(min_reserve) = min_reserve {
+      self.min_reserve = min_reserve;
+      msg!("Min reserve updated to: {}", min_reserve);
+    }
+
+    if let Some(max_swap_ratio_bps) = max_swap_ratio_bps {
+      self.max_swap_ratio_bps = max_swap_ratio_bps;
+      msg!("Max swap ratio updated to: {} bps", max_swap_ratio_bps);
+    }
+
+    if let Some(imbalance_fee_bps) = imbalance_fee_bps {
+      require!(
+        imbalance_fee_bps <= BPS_DENOMINATOR,
+        AMMError::InvalidAmount
+      );
+      self.imbalance_fee_bps = imbalance_fee_bps;
+      msg!("Imbalance fee updated to: {} bps", imbalance_fee_bps);
+    }
+
+    if let Some(lp_rounding) = lp_rounding {
+      self.lp_rounding = lp_rounding;
+      msg!("LP rounding updated to: {:?}", lp_rounding);
+    }
+
+    if let Some(lp_discount_threshold) = lp_discount_threshold {
+      self.lp_discount_threshold = lp_discount_threshold;
+      msg!(
+        "LP discount threshold updated to: {}",
+        lp_discount_threshold
+      );
+    }
+
+    if let Some(lp_discount_bps) = lp_discount_bps {
+      require!(lp_discount_bps <= BPS_DENOMINATOR, AMMError::InvalidAmount);
+      self.lp_discount_bps = lp_discount_bps;
+      msg!("LP discount updated to: {} bps", lp_discount_bps);
+    }
+
+    if let Some(protocol_fee_bps) = protocol_fee_bps {
+      require!(protocol_fee_bps <= BPS_DENOMINATOR, AMMError::InvalidAmount);
+      self.protocol_fee_bps = protocol_fee_bps;
+      msg!(
+        "Protocol fee updated to: {} bps of the swap fee",
+        protocol_fee_bps
+      );
+    }
+
+    if let Some(protocol_fee_min_amount) = protocol_fee_min_amount {
+      self.protocol_fee_min_amount = protocol_fee_min_amount;
+      msg!(
+        "Protocol fee min amount updated to: {}",
+        protocol_fee_min_amount
+      );
+    }
+
+    if let Some(launch_fee) = launch_fee {
+      require!(launch_fee <= MAX_FEE_BPS, AMMError::InvalidAmount);
+      self.launch_fee = launch_fee;
+      msg!("Launch fee updated to: {}", launch_fee);
+    }
+
+    if let Some(target_fee) = target_fee {
+      require!(target_fee <= MAX_FEE_BPS, AMMError::InvalidAmount);
+      self.target_fee = target_fee;
+      msg!("Target fee updated to: {}", target_fee);
+    }
+
+    if let Some(decay_duration) = decay_duration {
+      require!(decay_duration >= 0, AMMError::InvalidAmount);
+      self.decay_duration = decay_duration;
+      msg!("Launch fee decay duration updated to: {}s", decay_duration);
+    }
+
+    if let Some(privileged_mm) = privileged_mm {
+      self.privileged_mm = privileged_mm;
+      msg!("Privileged market maker updated to: {}", privileged_mm);
+    }
+
+    if let Some(mm_fee_bps) = mm_fee_bps {
+      require!(mm_fee_bps <= MAX_FEE_BPS, AMMError::InvalidAmount);
+      self.mm_fee_bps = mm_fee_bps;
+      msg!("Market maker fee updated to: {}", mm_fee_bps);
+    }
+
+    if let Some(vault_coverage_grace) = vault_coverage_grace {
+      require!(
+        vault_coverage_grace <= MAX_VAULT_COVERAGE_GRACE,
+        AMMError::InvalidAmount
+      );
+      self.vault_coverage_grace = vault_coverage_grace;
+      msg!("Vault coverage grace updated to: {}", vault_coverage_grace);
+    }
+
+    if let Some(max_lp_per_user) = max_lp_per_user {
+      self.max_lp_per_user = max_lp_per_user;
+      msg!("Max LP per user updated to: {}", max_lp_per_user);
+    }
+
+    if let Some(min_effective_fee_bps) = min_effective_fee_bps {
+      require!(
+        min_effective_fee_bps <= MAX_FEE_BPS,
+        AMMError::InvalidAmount
+      );
+      self.min_effective_fee_bps = min_effective_fee_bps;
+      msg!("Min effective fee updated to: {}", min_effective_fee_bps);
+    }
+
+    if let Some(fee_withdrawal_delay) = fee_withdrawal_delay {
+      self.set_fee_withdrawal_delay(fee_withdrawal_delay, now)?;
+    }
+
+    if let Some(circuit_breaker_bps) = circuit_breaker_bps {
+      require!(
+        circuit_breaker_bps <= BPS_DENOMINATOR,
+        AMMError::InvalidAmount
+      );
+      self.circuit_breaker_bps = circuit_breaker_bps;
+      msg!("Circuit breaker updated to: {} bps", circuit_breaker_bps);
+    }
+
+    Ok(())
+  }
+
+  /// Swap fee before any per-user discount. While the launch decay is active it moves
+  /// linearly from `launch_fee` at creation to `target_fee` after `decay_duration` seconds
+  pub fn base_fee(&self, now: i64) -> u16 {
+    if self.decay_duration <= 0 {
+      return self.fee;
+    }
+
+    let elapsed = now.saturating_sub(self.created_at).max(0);
+    if elapsed >= self.decay_duration {
+      return self.target_fee;
+    }
+
+    let (launch_fee, target_fee) = (self.launch_fee as i128, self.target_fee as i128);
+    let fee =
+      launch_fee + (target_fee - launch_fee) * elapsed as i128 / self.decay_duration as i128;
+
+    // Always between launch_fee and target_fee, so it fits
+    fee as u16
+  }
+
+  // Swap fee in bps for `swapper` holding `lp_balance` LP tokens. The privileged market
+  // maker pays its own fee, replacing the base fee and any LP discount. Whatever applies is
+  // raised to `min_effective_fee_bps` so LPs always earn the floor
+  pub fn swap_fee_for(&self, swapper: &Pubkey, lp_balance: u64, now: i64) -> Result<u16> {
+    Ok(
+      self
+        .resolve_swap_fee(swapper, lp_balance, now)?
+        .max(self.min_effective_fee_bps),
+    )
+  }
+
+  fn resolve_swap_fee(&self, swapper: &Pubkey, lp_balance: u64, now: i64) -> Result<u16> {
+    if self.privileged_mm != Pubkey::default() && *swapper == self.privileged_mm {
+      return Ok(self.mm_fee_bps);
+    }
+
+    let base_fee = self.base_fee(now);
+    if self.lp_discount_bps == 0 || lp_balance < self.lp_discount_threshold || lp_balance == 0 {
+      return Ok(base_fee);
+    }
+
+    let discount_bps = self.lp_discount_bps.min(BPS_DENOMINATOR);
+    let fee = (base_fee as u32)
+      .checked_mul((BPS_DENOMINATOR - discount_bps) as u32)
+      .ok_or(AMMError::InvalidAmount)?
+      / BPS_DENOMINATOR as u32;
+
+    Ok(u16::try_from(fee).map_err(|_| AMMError::MathOverflow)?)
+  }
+
+  // Rejects a deposit minting `lp_to_mint` to a user already holding `lp_balance` in the
+  // account being minted to, when it would take the user past the per-user cap
+  pub fn ensure_within_position_limit(&self, lp_balance: u64, lp_to_mint: u64) -> Result<()> {
+    if self.max_lp_per_user == 0 {
+      return Ok(());
+    }
+
+    let lp_after = lp_balance
+      .checked_add(lp_to_mint)
+      .ok_or(AMMError::InvalidAmount)?;
+    require!(
+      lp_after <= self.max_lp_per_user,
+      AMMError::PositionLimitExceeded
+    );
+
+    Ok(())
+  }
+
+  // Raising the delay applies at once. Lowering it waits out the delay in force, so a
+  // withdrawal can never unlock sooner than the delay announced before it was queued
+  fn set_fee_withdrawal_delay(&mut self, fee_withdrawal_delay: i64, now: i64) -> Result<()> {
+    require!(fee_withdrawal_delay >= 0, AMMError::InvalidAmount);
+    self.apply_pending_fee_withdrawal_delay(now);
+
+    if fee_withdrawal_delay >= self.fee_withdrawal_delay {
+      self.fee_withdrawal_delay = fee_withdrawal_delay;
+      self.pending_fee_withdrawal_delay = 0;
+      self.fee_withdrawal_delay_effective_at = 0;
+      msg!("Fee withdrawal delay updated to: {}s", fee_withdrawal_delay);
+    } else {
+      let effective_at = now
+        .checked_add(self.fee_withdrawal_delay)
+        .ok_or(AMMError::MathOverflow)?;
+      self.pending_fee_withdrawal_delay = fee_withdrawal_delay;
+      self.fee_withdrawal_delay_effective_at = effective_at;
+      msg!(
+        "Fee withdrawal delay lowering to: {}s at {}",
+        fee_withdrawal_delay,
+        effective_at
+      );
+    }
+
+    Ok(())
+  }
+
+  // Delay in force at `now`, counting a pending lowered delay that has taken effect but was
+  // not written back yet
+  pub fn fee_withdrawal_delay_at(&self, now: i64) -> i64 {
+    if self.fee_withdrawal_delay_effective_at != 0 && now >= self.fee_withdrawal_delay_effective_at
+    {
+      self.pending_fee_withdrawal_delay
+    } else {
+      self.fee_withdrawal_delay
+    }
+  }
+
+  fn apply_pending_fee_withdrawal_delay(&mut self, now: i64) {
+    if self.fee_withdrawal_delay_effective_at != 0 && now >= self.fee_withdrawal_delay_effective_at
+    {
+      self.fee_withdrawal_delay = self.pending_fee_withdrawal_delay;
+      self.pending_fee_withdrawal_delay = 0;
+      self.fee_withdrawal_delay_effective_at = 0;
+    }
+  }
+
+  // Queues `amount_x`/`amount_y` of protocol fees, unlocking `fee_withdrawal_delay` seconds
+  // from `now`. Replaces any withdrawal already queued, restarting the delay
+  pub fn queue_fee_withdrawal(&mut self, amount_x: u64, amount_y: u64, now: i64) -> Result<i64> {
+    require!(amount_x > 0 || amount_y > 0, AMMError::InvalidAmount);
+    self.apply_pending_fee_withdrawal_delay(now);
+
+    let unlock_at = now
+      .checked_add(self.fee_withdrawal_delay)
+      .ok_or(AMMError::MathOverflow)?;
+    self.queued_fee_x = amount_x;
+    self.queued_fee_y = amount_y;
+    self.fee_withdrawal_unlock_at = unlock_at;
+
+    msg!(
+      "Queued protocol fee withdrawal of {} X, {} Y, unlocking at {}",
+      amount_x,
+      amount_y,
+      unlock_at
+    );
+    Ok(unlock_at)
+  }
+
+  // Clears the queued withdrawal once it has unlocked, returning the amounts to move
+  pub fn take_queued_fee_withdrawal(&mut self, now: i64) -> Result<(u64, u64)> {
+    require!(
+      self.queued_fee_x > 0 || self.queued_fee_y > 0,
+      AMMError::NoFeeWithdrawalQueued
+    );
+    require!(
+      now >= self.fee_withdrawal_unlock_at,
+      AMMError::FeeWithdrawalLocked
+    );
+
+    let amounts = (self.queued_fee_x, self.queued_fee_y);
+    self.queued_fee_x = 0;
+    self.queued_fee_y = 0;
+    self.fee_withdrawal_unlock_at = 0;
+    Ok(amounts)
+  }
+
+  // Protocol portion of a swap's `fee_amount`, zero for swaps below the volume threshold.
+  // `amount_in` only gates the threshold: the cut is taken from the fee, never principal
+  pub fn protocol_fee_for(&self, amount_in: u64, fee_amount: u64) -> Result<u64> {
+    if self.protocol_fee_bps == 0 || amount_in < self.protocol_fee_min_amount {
+      return Ok(0);
+    }
+
+    let protocol_fee = (fee_amount as u128)
+      .checked_mul(self.protocol_fee_bps as u128)
+      .ok_or(AMMError::InvalidAmount)?
+      / BPS_DENOMINATOR as u128;
+
+    Ok(u64::try_from(protocol_fee).map_err(|_| AMMError::MathOverflow)?)
   }
 }
 
@@ -71,6 +702,19 @@ pub struct PoolState {
   pub reserve_x: u64,
   pub reserve_y: u64,
   pub lp_supply: u64,
+  /// Protocol fees accrued in the vaults, not counted in the reserves
+  pub protocol_fees_x: u64,
+  pub protocol_fees_y: u64,
+  /// Lifetime swap fees left to LPs, for yield analytics only. The tokens themselves are
+  /// part of the reserves
+  pub cumulative_fee_x: u128,
+  pub cumulative_fee_y: u128,
+  /// Lifetime Token-2022 transfer fees withheld on transfers into and out of the vaults
+  pub cumulative_transfer_fee_x: u128,
+  pub cumulative_transfer_fee_y: u128,
+  /// Layout the account was created or last migrated under, see `POOL_STATE_VERSION`. Pools
+  /// predating it read as 0
+  pub version: u8,
 }
 
 pub struct InitPoolStateParams {
@@ -96,5 +740,162 @@ impl PoolState {
     self.reserve_x = 0;
     self.reserve_y = 0;
     self.lp_supply = 0;
+    self.protocol_fees_x = 0;
+    self.protocol_fees_y = 0;
+    self.cumulative_fee_x = 0;
+    self.cumulative_fee_y = 0;
+    self.cumulative_transfer_fee_x = 0;
+    self.cumulative_transfer_fee_y = 0;
+    self.version = POOL_STATE_VERSION;
+  }
+
+  /// Records the LP share of a swap fee, paid in the swap's input token
+  pub fn accrue_lp_fee(&mut self, input_is_x: bool, lp_fee: u64) -> Result<()> {
+    let cumulative_fee = if input_is_x {
+      &mut self.cumulative_fee_x
+    } else {
+      &mut self.cumulative_fee_y
+    };
+    *cumulative_fee = cumulative_fee
+      .checked_add(lp_fee as u128)
+      .ok_or(AMMError::MathOverflow)?;
+    Ok(())
+  }
+
+  /// Records the transfer fee withheld on a transfer of token X or Y into or out of a vault
+  pub fn record_transfer_fee(&mut self, is_x: bool, transfer_fee: u64) -> Result<()> {
+    let cumulative_transfer_fee = if is_x {
+      &mut self.cumulative_transfer_fee_x
+    } else {
+      &mut self.cumulative_transfer_fee_y
+    };
+    *cumulative_transfer_fee = cumulative_transfer_fee
+      .checked_add(transfer_fee as u128)
+      .ok_or(AMMError::MathOverflow)?;
+    Ok(())
+  }
+}
+
+/// Config as first released, with an optional fixed-size whitelist in place of
+/// `white_list_lp`, `whitelist_enabled` and `whitelist_capacity`. Predates `version`, so it
+/// is recognized by its size
+#[derive(AnchorDeserialize)]
+struct ConfigV0 {
+  seed: u64,
+  authority: Pubkey,
+  mint_x: Pubkey,
+  mint_y: Pubkey,
+  fee: u16,
+  locked: bool,
+  white_list_lp: Option<[Pubkey; MAX_WHITE_LIST_LP]>,
+  auth_bump: u8,
+  config_bump: u8,
+  lp_bump: u8,
+}
+
+impl ConfigV0 {
+  // Space for the whitelist was allocated whether or not one was set
+  const LEN: usize = DISCRIMINATOR + 8 + 32 * 3 + 2 + 1 + (1 + 32 * MAX_WHITE_LIST_LP) + 3;
+
+  /// Current layout of the config, at version 0. No whitelist keeps deposits open with
+  /// enforcement off; a whitelist keeps enforcing its non-default entries
+  fn into_config(self) -> Result<Config> {
+    let mut config: Config = deserialize_zero_extended(Config::DISCRIMINATOR)?;
+    config.seed = self.seed;
+    config.authority = self.authority;
+    config.mint_x = self.mint_x;
+    config.mint_y = self.mint_y;
+    config.fee = self.fee;
+    config.locked = self.locked;
+    config.whitelist_enabled = self.white_list_lp.is_some();
+    config.white_list_lp = self
+      .white_list_lp
+      .map(|list| {
+        list
+          .into_iter()
+          .filter(|key| *key != Pubkey::default())
+          .collect()
+      })
+      .unwrap_or_default();
+    config.whitelist_capacity = MAX_WHITE_LIST_LP as u16;
+    config.auth_bump = self.auth_bump;
+    config.config_bump = self.config_bump;
+    config.lp_bump = self.lp_bump;
+    // Fields added since, where the default `init` gives is not zero. The mints are not
+    // cached as legacy, so they keep being re-verified
+    config.max_swap_ratio_bps = BPS_DENOMINATOR;
+    config.imbalance_fee_bps = BPS_DENOMINATOR;
+    config.vault_coverage_grace = DEFAULT_VAULT_COVERAGE_GRACE;
+
+    // The vaults were owned by the pool authority
+    let config_key = Pubkey::create_program_address(
+      &[
+        seed_prefix::CONFIG,
+        self.seed.to_le_bytes().as_ref(),
+        &[self.config_bump],
+      ],
+      &crate::ID,
+    )
+    .map_err(|_| ErrorCode::ConstraintSeeds)?;
+    config.vault_authority = config.pool_authority_key(&config_key)?;
+    config.vault_auth_bump = self.auth_bump;
+
+    Ok(config)
+  }
+}
+
+impl Migrate for Config {
+  const VERSION: u8 = CONFIG_VERSION;
+
+  fn version(&self) -> u8 {
+    self.version
+  }
+
+  fn migrate(&mut self) {
+    // Version 0 also covers the original layout, which `deserialize_versioned` has already
+    // brought into this one. Version 1 only added `version` itself. Version 2 added the
+    // pending fee withdrawal delay, with nothing pending
+    if self.version < 2 {
+      self.pending_fee_withdrawal_delay = 0;
+      self.fee_withdrawal_delay_effective_at = 0;
+    }
+    self.version = CONFIG_VERSION;
+  }
+
+  fn migrated_len(&self) -> usize {
+    Config::space_for_whitelist(self.whitelist_capacity)
+  }
+
+  fn deserialize_versioned(data: &[u8]) -> Result<Self> {
+    // No later layout has this size, whatever the whitelist capacity
+    if data.len() != ConfigV0::LEN {
+      return deserialize_zero_extended(data);
+    }
+    require!(
+      data.starts_with(Config::DISCRIMINATOR),
+      ErrorCode::AccountDiscriminatorMismatch
+    );
+    ConfigV0::deserialize(&mut &data[DISCRIMINATOR..])?.into_config()
+  }
+}
+
+impl Migrate for PoolState {
+  const VERSION: u8 = POOL_STATE_VERSION;
+
+  fn version(&self) -> u8 {
+    self.version
+  }
+
+  fn migrate(&mut self) {
+    // Version 1 added the transfer fee counters, which start from zero
+    if self.version < 1 {
+      self.cumulative_transfer_fee_x = 0;
+      self.cumulative_transfer_fee_y = 0;
+    }
+    self.version = POOL_STATE_VERSION;
+  }
+
+  fn migrated_len(&self) -> usize {
+    DISCRIMINATOR + PoolState::INIT_SPACE
   }
 }
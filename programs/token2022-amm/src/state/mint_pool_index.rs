@@ -0,0 +1,35 @@
+use {crate::common::error::AMMError, anchor_lang::prelude::*};
+
+/// Pools a single index account can list
+pub const MAX_POOLS_PER_MINT: usize = 32;
+
+// One per mint; lists the configs of pools trading the mint so they can be discovered
+// on-chain. Only pools created with the index supplied are listed.
+#[account]
+#[derive(InitSpace)]
+pub struct MintPoolIndex {
+  pub mint: Pubkey,
+  #[max_len(MAX_POOLS_PER_MINT)]
+  pub pools: Vec<Pubkey>,
+  pub bump: u8,
+}
+
+impl MintPoolIndex {
+  pub fn add_pool(&mut self, mint: Pubkey, config: Pubkey, bump: u8) -> Result<()> {
+    self.mint = mint;
+    self.bump = bump;
+
+    if self.pools.contains(&config) {
+      return Ok(());
+    }
+    require!(
+      self.pools.len() < MAX_POOLS_PER_MINT,
+      AMMError::MintPoolIndexFull
+    );
+    self.pools.push(config);
+
+    msg!("Indexed pool {} under mint {}", config, mint);
+
+    Ok(())
+  }
+}
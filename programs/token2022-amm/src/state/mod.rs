@@ -1,2 +1,6 @@
+pub mod authority_registry;
 pub mod config;
-pub use config::*;
+pub mod mint_pool_index;
+pub mod pool_snapshot;
+pub mod reward;
+pub use {authority_registry::*, config::*, mint_pool_index::*, pool_snapshot::*, reward::*};
@@ -0,0 +1,33 @@
+use {crate::state::PoolState, anchor_lang::prelude::*};
+
+// Point-in-time record of a pool's reserves and LP supply, written once by `snapshot` into
+// a fresh account so any number of snapshots can coexist
+#[account]
+#[derive(InitSpace)]
+pub struct PoolSnapshot {
+  pub config: Pubkey,
+  pub reserve_x: u64,
+  pub reserve_y: u64,
+  pub lp_supply: u64,
+  pub slot: u64,
+  pub timestamp: i64,
+}
+
+impl PoolSnapshot {
+  pub fn record(&mut self, config: Pubkey, pool_state: &PoolState, clock: &Clock) {
+    self.config = config;
+    self.reserve_x = pool_state.reserve_x;
+    self.reserve_y = pool_state.reserve_y;
+    self.lp_supply = pool_state.lp_supply;
+    self.slot = clock.slot;
+    self.timestamp = clock.unix_timestamp;
+
+    msg!(
+      "Snapshot at slot {}: {} X / {} Y, {} LP",
+      clock.slot,
+      pool_state.reserve_x,
+      pool_state.reserve_y,
+      pool_state.lp_supply
+    );
+  }
+}
@@ -0,0 +1,184 @@
+use {
+  crate::common::{constant::REWARD_PRECISION, error::AMMError},
+  anchor_lang::prelude::*,
+};
+
+// Per-LP-token accumulator (SNX staking rewards style): `reward_per_lp_stored` grows by
+// `rate * elapsed / total_staked` and each staker is paid the growth since their last
+// checkpoint. Only LP locked in the stake vault earns, so the balance a user is paid on can
+// not change between checkpoints.
+#[account]
+#[derive(InitSpace)]
+pub struct RewardConfig {
+  pub config: Pubkey,
+  pub reward_mint: Pubkey,
+  pub reward_vault: Pubkey,
+  pub reward_rate_per_second: u64,
+  pub last_update_ts: i64,
+  pub reward_per_lp_stored: u128,
+  pub bump: u8,
+  pub vault_bump: u8,
+  pub stake_vault: Pubkey,
+  pub total_staked: u64,
+  pub stake_vault_bump: u8,
+}
+
+pub struct InitRewardConfigParams {
+  pub config: Pubkey,
+  pub reward_mint: Pubkey,
+  pub reward_vault: Pubkey,
+  pub stake_vault: Pubkey,
+  pub reward_rate_per_second: u64,
+  pub now: i64,
+  pub bump: u8,
+  pub vault_bump: u8,
+  pub stake_vault_bump: u8,
+}
+
+impl RewardConfig {
+  pub fn init(&mut self, params: InitRewardConfigParams) {
+    let InitRewardConfigParams {
+      config,
+      reward_mint,
+      reward_vault,
+      stake_vault,
+      reward_rate_per_second,
+      now,
+      bump,
+      vault_bump,
+      stake_vault_bump,
+    } = params;
+
+    self.config = config;
+    self.reward_mint = reward_mint;
+    self.reward_vault = reward_vault;
+    self.reward_rate_per_second = reward_rate_per_second;
+    self.last_update_ts = now;
+    self.reward_per_lp_stored = 0;
+    self.bump = bump;
+    self.vault_bump = vault_bump;
+    self.stake_vault = stake_vault;
+    self.total_staked = 0;
+    self.stake_vault_bump = stake_vault_bump;
+
+    msg!(
+      "Reward initialized with mint: {}, rate: {}/s",
+      reward_mint,
+      reward_rate_per_second
+    );
+  }
+
+  // Emission while nothing is staked is not owed to anyone and stays in the vault
+  pub fn reward_per_lp(&self, now: i64) -> Result<u128> {
+    if self.total_staked == 0 || now <= self.last_update_ts {
+      return Ok(self.reward_per_lp_stored);
+    }
+
+    let elapsed = (now - self.last_update_ts) as u128;
+    let accrued = elapsed
+      .checked_mul(self.reward_rate_per_second as u128)
+      .ok_or(AMMError::InvalidAmount)?
+      .checked_mul(REWARD_PRECISION)
+      .ok_or(AMMError::InvalidAmount)?
+      .checked_div(self.total_staked as u128)
+      .ok_or(AMMError::DivisionByZero)?;
+
+    Ok(
+      self
+        .reward_per_lp_stored
+        .checked_add(accrued)
+        .ok_or(AMMError::InvalidAmount)?,
+    )
+  }
+
+  pub fn update(&mut self, now: i64) -> Result<()> {
+    self.reward_per_lp_stored = self.reward_per_lp(now)?;
+    self.last_update_ts = now;
+
+    Ok(())
+  }
+
+  pub fn stake(&mut self, amount: u64) -> Result<()> {
+    self.total_staked = self
+      .total_staked
+      .checked_add(amount)
+      .ok_or(AMMError::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn unstake(&mut self, amount: u64) -> Result<()> {
+    self.total_staked = self
+      .total_staked
+      .checked_sub(amount)
+      .ok_or(AMMError::InsufficientStakedLp)?;
+
+    Ok(())
+  }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserReward {
+  pub reward_config: Pubkey,
+  pub user: Pubkey,
+  pub reward_per_lp_paid: u128,
+  pub pending_reward: u64,
+  pub staked: u64,
+  pub bump: u8,
+}
+
+impl UserReward {
+  pub fn is_initialized(&self) -> bool {
+    self.reward_config != Pubkey::default()
+  }
+
+  pub fn init(&mut self, reward_config: Pubkey, user: Pubkey, reward_per_lp: u128, bump: u8) {
+    self.reward_config = reward_config;
+    self.user = user;
+    self.reward_per_lp_paid = reward_per_lp;
+    self.pending_reward = 0;
+    self.staked = 0;
+    self.bump = bump;
+  }
+
+  // Credits the accumulator growth since the last checkpoint on the LP staked over it.
+  // Must run before `staked` changes
+  pub fn settle(&mut self, reward_per_lp: u128) -> Result<()> {
+    let earned = (self.staked as u128)
+      .checked_mul(
+        reward_per_lp
+          .checked_sub(self.reward_per_lp_paid)
+          .ok_or(AMMError::InvalidAmount)?,
+      )
+      .ok_or(AMMError::InvalidAmount)?
+      .checked_div(REWARD_PRECISION)
+      .ok_or(AMMError::DivisionByZero)?;
+
+    self.pending_reward = self
+      .pending_reward
+      .checked_add(u64::try_from(earned).map_err(|_| AMMError::MathOverflow)?)
+      .ok_or(AMMError::InvalidAmount)?;
+    self.reward_per_lp_paid = reward_per_lp;
+
+    Ok(())
+  }
+
+  pub fn stake(&mut self, amount: u64) -> Result<()> {
+    self.staked = self
+      .staked
+      .checked_add(amount)
+      .ok_or(AMMError::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn unstake(&mut self, amount: u64) -> Result<()> {
+    self.staked = self
+      .staked
+      .checked_sub(amount)
+      .ok_or(AMMError::InsufficientStakedLp)?;
+
+    Ok(())
+  }
+}
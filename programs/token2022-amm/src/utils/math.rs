@@ -0,0 +1,572 @@
+use {
+  crate::{
+    common::{
+      constant::{BPS_DENOMINATOR, SECONDS_PER_YEAR},
+      error::AMMError,
+    },
+    state::LpRounding,
+  },
+  anchor_lang::prelude::*,
+};
+
+// Quotient rounded down: the rounding for anything the pool pays out
+pub fn div_floor(numerator: u128, denominator: u128) -> Result<u128> {
+  require!(denominator > 0, AMMError::DivisionByZero);
+  Ok(numerator / denominator)
+}
+
+// Quotient rounded up: the rounding for anything the pool requires in, so a fractional
+// unit is never given away
+pub fn div_ceil(numerator: u128, denominator: u128) -> Result<u128> {
+  require!(denominator > 0, AMMError::DivisionByZero);
+  Ok(numerator.div_ceil(denominator))
+}
+
+// Share of `reserve` released by burning `lp_amount` of `lp_supply`, rounded down
+pub fn reserve_share(lp_amount: u64, reserve: u64, lp_supply: u64) -> Result<u64> {
+  // u64 * u64 always fits in u128
+  let share = div_floor(lp_amount as u128 * reserve as u128, lp_supply as u128)?;
+  Ok(u64::try_from(share).map_err(|_| AMMError::MathOverflow)?)
+}
+
+// Divides for an LP mint amount. Minting only ever rounds in the user's favor when the
+// pool is configured for it; withdrawals keep rounding down so the pool is never drained.
+pub fn div_lp(numerator: u128, denominator: u128, rounding: LpRounding) -> Result<u128> {
+  require!(denominator > 0, AMMError::DivisionByZero);
+
+  let quotient = numerator / denominator;
+  let remainder = numerator % denominator;
+
+  match rounding {
+    LpRounding::RoundDown => Ok(quotient),
+    LpRounding::RoundToNearest => {
+      if remainder >= denominator - remainder {
+        quotient
+          .checked_add(1)
+          .ok_or(AMMError::InvalidAmount.into())
+      } else {
+        Ok(quotient)
+      }
+    }
+  }
+}
+
+// LP each side of a deposit is worth at the reserve ratio, amount * lp_supply / reserve,
+// rounded per the pool's LP rounding mode. Outstanding LP backed by an empty reserve means
+// the pool is desynced, so that fails clearly before dividing by it
+pub fn proportional_lp(
+  amounts: (u64, u64),
+  reserves: (u64, u64),
+  lp_supply: u64,
+  rounding: LpRounding,
+) -> Result<(u64, u64)> {
+  require!(reserves.0 > 0 && reserves.1 > 0, AMMError::EmptyReserves);
+
+  let supply = lp_supply as u128;
+  // u64 * u64 always fits in u128
+  let lp_from_x = div_lp(amounts.0 as u128 * supply, reserves.0 as u128, rounding)?;
+  let lp_from_y = div_lp(amounts.1 as u128 * supply, reserves.1 as u128, rounding)?;
+
+  Ok((
+    u64::try_from(lp_from_x).map_err(|_| AMMError::MathOverflow)?,
+    u64::try_from(lp_from_y).map_err(|_| AMMError::MathOverflow)?,
+  ))
+}
+
+// LP minted for the unbalanced excess of a deposit. The excess is valued as a single-sided
+// add along the constant product curve, lp * (sqrt((reserve + excess) / reserve) - 1), then
+// charged the imbalance fee; the unminted share stays in the reserves for existing LPs.
+// Every step rounds down since the result is minted out. Since the excess is later
+// withdrawable as both tokens, the fee should be at least half the swap fee or the deposit
+// becomes a cheaper swap.
+pub fn imbalance_lp(
+  excess_amount: u64,
+  reserve: u64,
+  lp_supply: u64,
+  imbalance_fee_bps: u16,
+) -> Result<u64> {
+  const SQRT_SCALE: u128 = 1_000_000_000;
+
+  if excess_amount == 0 || imbalance_fee_bps >= BPS_DENOMINATOR {
+    return Ok(0);
+  }
+
+  let growth = (reserve as u128)
+    .checked_add(excess_amount as u128)
+    .ok_or(AMMError::InvalidAmount)?
+    .checked_mul(SQRT_SCALE * SQRT_SCALE)
+    .ok_or(AMMError::InvalidAmount)?;
+  let growth_scaled = div_floor(growth, reserve as u128)?
+    .integer_sqrt()
+    .saturating_sub(SQRT_SCALE);
+
+  let gross_lp = div_floor(
+    (lp_supply as u128)
+      .checked_mul(growth_scaled)
+      .ok_or(AMMError::InvalidAmount)?,
+    SQRT_SCALE,
+  )?;
+  let excess_lp = div_floor(
+    gross_lp
+      .checked_mul((BPS_DENOMINATOR - imbalance_fee_bps) as u128)
+      .ok_or(AMMError::InvalidAmount)?,
+    BPS_DENOMINATOR as u128,
+  )?;
+
+  Ok(u64::try_from(excess_lp).map_err(|_| AMMError::MathOverflow)?)
+}
+
+// Square root for the initial LP mint, rounded per the pool's LP rounding mode
+pub fn sqrt_lp(value: u128, rounding: LpRounding) -> u128 {
+  let root = value.integer_sqrt();
+
+  match rounding {
+    LpRounding::RoundDown => root,
+    // sqrt(value) >= root + 0.5 exactly when value > root^2 + root
+    LpRounding::RoundToNearest if value > root * root + root => root + 1,
+    LpRounding::RoundToNearest => root,
+  }
+}
+
+// Helper trait for integer square root
+pub trait IntegerSquareRoot {
+  fn integer_sqrt(self) -> Self;
+}
+
+impl IntegerSquareRoot for u128 {
+  fn integer_sqrt(self) -> Self {
+    if self < 2 {
+      return self;
+    }
+
+    let mut x = self;
+    let mut y = (self + 1) / 2;
+
+    while y < x {
+      x = y;
+      y = (x + self / x) / 2;
+    }
+
+    x
+  }
+}
+
+// Amount of `amount_in` to swap into a reserve of `reserve_in` so the remainder and the
+// swap output deposit at the post-swap pool ratio with nothing left over. Only the pool fee
+// (in bps) is accounted for; transfer and protocol fees are left to the caller.
+//
+// With F = BPS_DENOMINATOR and g = F - fee, balancing (a - s) / (R + s) against the swap output
+// reduces to g*s^2 + R*(F + g)*s - a*R*F = 0, whose positive root is
+// s = (sqrt(R * (R*(F + g)^2 + 4*a*g*F)) - R*(F + g)) / (2*g), rounded down.
+pub fn compute_zap_swap_amount(reserve_in: u64, amount_in: u64, fee: u16) -> Result<u64> {
+  require!(reserve_in > 0, AMMError::EmptyReserves);
+  require!(fee < BPS_DENOMINATOR, AMMError::InvalidAmount);
+
+  let reserve = reserve_in as u128;
+  let f = BPS_DENOMINATOR as u128;
+  let g = f - fee as u128;
+
+  let b = reserve.checked_mul(f + g).ok_or(AMMError::MathOverflow)?;
+  let inner = b
+    .checked_mul(f + g)
+    .and_then(|v| v.checked_add(4 * (amount_in as u128) * g * f))
+    .ok_or(AMMError::MathOverflow)?;
+
+  // R * inner can exceed u128, so its root is taken over the 256-bit product
+  let root = isqrt_product(reserve, inner);
+  let swap_amount = root.saturating_sub(b) / (2 * g);
+
+  // Never more than the input, even with rounding at the extremes
+  Ok(
+    u64::try_from(swap_amount)
+      .map_err(|_| AMMError::MathOverflow)?
+      .min(amount_in),
+  )
+}
+
+// Fails unless the reserve product x * y did not decrease between `before` and `after`, e.g.
+// from the reserves the pool credits to the ones its vaults actually back. A decrease means
+// the accounting is off
+pub fn ensure_product_non_decreasing(before: (u64, u64), after: (u64, u64)) -> Result<()> {
+  // u64 * u64 always fits in u128
+  let product_before = before.0 as u128 * before.1 as u128;
+  let product_after = after.0 as u128 * after.1 as u128;
+  require!(
+    product_after >= product_before,
+    AMMError::InvariantViolation
+  );
+  Ok(())
+}
+
+// LP minted for adding up to `amounts` at the reserve ratio, and the amounts that LP
+// actually takes. The LP is rounded down and the amounts taken up, both in the pool's favor;
+// whatever exceeds the ratio on one side is left out
+pub fn lp_for_proportional_deposit(
+  amounts: (u64, u64),
+  reserves: (u64, u64),
+  lp_supply: u64,
+) -> Result<(u64, (u64, u64))> {
+  require!(lp_supply > 0, AMMError::InsufficientLiquidity);
+  require!(reserves.0 > 0 && reserves.1 > 0, AMMError::EmptyReserves);
+
+  let supply = lp_supply as u128;
+  // u64 * u64 always fits in u128
+  let lp = div_floor(amounts.0 as u128 * supply, reserves.0 as u128)?
+    .min(div_floor(amounts.1 as u128 * supply, reserves.1 as u128)?);
+  let lp = u64::try_from(lp).map_err(|_| AMMError::MathOverflow)?;
+
+  // lp * reserve / supply <= amount, so rounding it up stays within `amounts`
+  let used = (
+    u64::try_from(div_ceil(lp as u128 * reserves.0 as u128, supply)?)
+      .map_err(|_| AMMError::MathOverflow)?,
+    u64::try_from(div_ceil(lp as u128 * reserves.1 as u128, supply)?)
+      .map_err(|_| AMMError::MathOverflow)?,
+  );
+
+  Ok((lp, used))
+}
+
+// Smallest LP burn whose proportional withdrawal releases exactly `amounts`, with the same
+// round-down share math as `withdraw`. Fails when no burn does, i.e. the amounts are not in
+// the reserve ratio
+pub fn lp_for_exact_withdrawal(
+  amounts: (u64, u64),
+  reserves: (u64, u64),
+  lp_supply: u64,
+) -> Result<u64> {
+  require!(lp_supply > 0, AMMError::InsufficientLiquidity);
+  require!(reserves.0 > 0 && reserves.1 > 0, AMMError::EmptyReserves);
+
+  let supply = lp_supply as u128;
+  // u64 * u64 always fits in u128
+  let lp = div_ceil(amounts.0 as u128 * supply, reserves.0 as u128)?
+    .max(div_ceil(amounts.1 as u128 * supply, reserves.1 as u128)?);
+  require!(lp > 0 && lp <= supply, AMMError::InvalidAmount);
+  let lp = u64::try_from(lp).map_err(|_| AMMError::MathOverflow)?;
+
+  let released = (
+    reserve_share(lp, reserves.0, lp_supply)?,
+    reserve_share(lp, reserves.1, lp_supply)?,
+  );
+  require!(released == amounts, AMMError::NonProportionalAmounts);
+
+  Ok(lp)
+}
+
+// How far a vault balance falls short of everything the pool owes out of it: the reserve
+// plus any fees accrued but not yet withdrawn. 0 when the vault covers it all
+pub fn vault_shortfall(vault_amount: u64, reserve: u64, accrued_fees: u64) -> u64 {
+  // Summed in u128 so the claims can exceed u64 without wrapping
+  let owed = reserve as u128 + accrued_fees as u128;
+  owed
+    .saturating_sub(vault_amount as u128)
+    .min(u64::MAX as u128) as u64
+}
+
+// Fees earned over `elapsed` seconds extrapolated to a year, in basis points of the reserve
+// they were earned on. 0 for an empty pool or when no time has passed yet
+pub fn annualized_fee_bps(cumulative_fee: u128, reserve: u64, elapsed: i64) -> u64 {
+  if reserve == 0 || elapsed <= 0 {
+    return 0;
+  }
+  // Saturating: a pool a few seconds old can extrapolate to absurd yields, which need not
+  // be exact
+  let annual_fee = cumulative_fee
+    .saturating_mul(BPS_DENOMINATOR as u128)
+    .saturating_mul(SECONDS_PER_YEAR);
+  (annual_fee / (reserve as u128 * elapsed as u128)).min(u64::MAX as u128) as u64
+}
+
+// Full 256-bit product of two u128 values as (high, low)
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+  const MASK: u128 = u64::MAX as u128;
+  let (a_hi, a_lo) = (a >> 64, a & MASK);
+  let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+  let lo_lo = a_lo * b_lo;
+  let hi_lo = a_hi * b_lo;
+  let lo_hi = a_lo * b_hi;
+  let hi_hi = a_hi * b_hi;
+
+  let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+  let low = (cross << 64) | (lo_lo & MASK);
+  let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+
+  (high, low)
+}
+
+// floor(sqrt(a * b)) without materializing the 256-bit product
+fn isqrt_product(a: u128, b: u128) -> u128 {
+  let target = mul_wide(a, b);
+  let mut low = 0u128;
+  let mut high = (a.integer_sqrt() + 1).saturating_mul(b.integer_sqrt() + 1);
+
+  while low < high {
+    let mid = low + (high - low).div_ceil(2);
+    if mul_wide(mid, mid) <= target {
+      low = mid;
+    } else {
+      high = mid - 1;
+    }
+  }
+
+  low
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Bisects the real-valued balance condition for the swap size that leaves no remainder
+  fn solve_zap_numerically(reserve_in: u64, amount_in: u64, fee: u16) -> f64 {
+    let (r, a) = (reserve_in as f64, amount_in as f64);
+    let gamma = (10000.0 - fee as f64) / 10000.0;
+    // The balanced swap size does not depend on the output reserve
+    let r_out = 1.0e12;
+    let imbalance = |s: f64| {
+      let out = s * gamma * r_out / (r + s * gamma);
+      (a - s) * (r_out - out) - out * (r + s)
+    };
+
+    let (mut low, mut high) = (0.0, a);
+    for _ in 0..200 {
+      let mid = (low + high) / 2.0;
+      if imbalance(mid) > 0.0 {
+        low = mid;
+      } else {
+        high = mid;
+      }
+    }
+    low
+  }
+
+  #[test]
+  fn zap_swap_amount_matches_numeric_solution() {
+    let reserves = [
+      1_000u64,
+      123_456,
+      10_000_000,
+      1_000_000_000,
+      987_654_321_000,
+    ];
+    let amounts = [1u64, 999, 50_000, 10_000_000, 5_000_000_000];
+    let fees = [0u16, 1, 30, 300, 1000, 5000];
+
+    for &reserve in &reserves {
+      for &amount in &amounts {
+        for &fee in &fees {
+          let closed = compute_zap_swap_amount(reserve, amount, fee).unwrap();
+          let numeric = solve_zap_numerically(reserve, amount, fee);
+          let tolerance = 1.0 + numeric * 1e-12;
+          assert!(
+            (closed as f64 - numeric).abs() <= tolerance,
+            "reserve {} amount {} fee {}: closed {} numeric {}",
+            reserve,
+            amount,
+            fee,
+            closed,
+            numeric
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn zap_swap_amount_is_about_half_without_fee_for_small_inputs() {
+    // For a << R and no fee the root tends to a / 2
+    assert_eq!(
+      compute_zap_swap_amount(1_000_000_000_000, 1_000, 0).unwrap(),
+      499
+    );
+  }
+
+  #[test]
+  fn zap_swap_amount_handles_full_range_reserves() {
+    let swap = compute_zap_swap_amount(u64::MAX, u64::MAX, 30).unwrap();
+    assert!(swap > 0 && swap < u64::MAX / 2);
+    assert_eq!(compute_zap_swap_amount(u64::MAX, 0, 30).unwrap(), 0);
+  }
+
+  #[test]
+  fn product_check_accepts_growing_or_equal_reserves() {
+    assert!(ensure_product_non_decreasing((100, 200), (100, 200)).is_ok());
+    assert!(ensure_product_non_decreasing((100, 200), (150, 300)).is_ok());
+    // One side shrinking is fine while the product grows
+    assert!(ensure_product_non_decreasing((100, 200), (99, 300)).is_ok());
+    assert!(ensure_product_non_decreasing((0, 0), (1, 1)).is_ok());
+    assert!(ensure_product_non_decreasing((u64::MAX, u64::MAX), (u64::MAX, u64::MAX)).is_ok());
+  }
+
+  #[test]
+  fn product_check_rejects_shrinking_reserves() {
+    assert!(ensure_product_non_decreasing((100, 200), (100, 199)).is_err());
+    assert!(ensure_product_non_decreasing((100, 200), (101, 197)).is_err());
+    assert!(ensure_product_non_decreasing((u64::MAX, u64::MAX), (u64::MAX, u64::MAX - 1)).is_err());
+  }
+
+  #[test]
+  fn isqrt_product_is_exact_past_u128() {
+    let a = u64::MAX as u128 * 3;
+    let root = isqrt_product(a, a);
+    assert_eq!(root, a);
+    assert_eq!(isqrt_product(a, a + 1), a);
+  }
+
+  #[test]
+  fn exact_withdrawal_lp_releases_the_requested_amounts() {
+    // 1 LP per 2 X and 3 Y
+    assert_eq!(
+      lp_for_exact_withdrawal((200, 300), (2_000, 3_000), 1_000).unwrap(),
+      100
+    );
+    // Rounding down on release leaves slack: 333 LP of 1000 release 333 X and 666 Y
+    assert_eq!(
+      lp_for_exact_withdrawal((333, 666), (1_000, 2_000), 1_000).unwrap(),
+      333
+    );
+    assert_eq!(
+      lp_for_exact_withdrawal((1_000, 2_000), (1_000, 2_000), 1_000).unwrap(),
+      1_000
+    );
+  }
+
+  #[test]
+  fn exact_withdrawal_rejects_off_ratio_or_unreachable_amounts() {
+    assert_eq!(
+      lp_for_exact_withdrawal((200, 301), (2_000, 3_000), 1_000).unwrap_err(),
+      AMMError::NonProportionalAmounts.into()
+    );
+    assert_eq!(
+      lp_for_exact_withdrawal((0, 0), (2_000, 3_000), 1_000).unwrap_err(),
+      AMMError::InvalidAmount.into()
+    );
+    assert_eq!(
+      lp_for_exact_withdrawal((2_001, 3_000), (2_000, 3_000), 1_000).unwrap_err(),
+      AMMError::InvalidAmount.into()
+    );
+    assert!(lp_for_exact_withdrawal((1, 1), (0, 3_000), 1_000).is_err());
+  }
+
+  #[test]
+  fn div_floor_and_div_ceil_round_in_opposite_directions() {
+    assert_eq!(div_floor(10, 5).unwrap(), 2);
+    assert_eq!(div_ceil(10, 5).unwrap(), 2);
+    assert_eq!(div_floor(11, 5).unwrap(), 2);
+    assert_eq!(div_ceil(11, 5).unwrap(), 3);
+    assert_eq!(div_floor(0, 5).unwrap(), 0);
+    assert_eq!(div_ceil(0, 5).unwrap(), 0);
+    assert_eq!(div_ceil(u128::MAX, 2).unwrap(), u128::MAX / 2 + 1);
+
+    for result in [div_floor(1, 0), div_ceil(1, 0)] {
+      assert_eq!(result.unwrap_err(), AMMError::DivisionByZero.into());
+    }
+  }
+
+  #[test]
+  fn reserve_share_rounds_withdrawals_down() {
+    // 1 of 3 LP over a reserve of 10 is 3.33 -> 3
+    assert_eq!(reserve_share(1, 10, 3).unwrap(), 3);
+    assert_eq!(reserve_share(2, 10, 3).unwrap(), 6);
+    assert_eq!(reserve_share(3, 10, 3).unwrap(), 10);
+    assert_eq!(reserve_share(1, u64::MAX, 1).unwrap(), u64::MAX);
+    assert!(reserve_share(1, 10, 0).is_err());
+  }
+
+  #[test]
+  fn proportional_deposit_takes_only_the_ratio_matched_amounts() {
+    // 2:3 pool with 1000 LP: 200 X / 300 Y is exactly 100 LP
+    assert_eq!(
+      lp_for_proportional_deposit((200, 300), (2_000, 3_000), 1_000).unwrap(),
+      (100, (200, 300))
+    );
+    // Excess Y is left out
+    assert_eq!(
+      lp_for_proportional_deposit((200, 900), (2_000, 3_000), 1_000).unwrap(),
+      (100, (200, 300))
+    );
+    // 1 LP of a 3-LP supply over 10/10 reserves takes 3.33 -> 4 of each
+    assert_eq!(
+      lp_for_proportional_deposit((5, 5), (10, 10), 3).unwrap(),
+      (1, (4, 4))
+    );
+    // Too little on one side for a whole LP unit
+    assert_eq!(
+      lp_for_proportional_deposit((0, 1_000), (2_000, 3_000), 1_000).unwrap(),
+      (0, (0, 0))
+    );
+    assert!(lp_for_proportional_deposit((1, 1), (0, 10), 10).is_err());
+    assert!(lp_for_proportional_deposit((1, 1), (10, 10), 0).is_err());
+  }
+
+  #[test]
+  fn proportional_lp_values_each_side_at_the_reserve_ratio() {
+    // 2:3 pool with 1000 LP: 200 X and 450 Y are worth 100 and 150 LP
+    assert_eq!(
+      proportional_lp((200, 450), (2_000, 3_000), 1_000, LpRounding::RoundDown).unwrap(),
+      (100, 150)
+    );
+    // 5 * 3 / 10 = 1.5 LP per side
+    assert_eq!(
+      proportional_lp((5, 5), (10, 10), 3, LpRounding::RoundDown).unwrap(),
+      (1, 1)
+    );
+    assert_eq!(
+      proportional_lp((5, 5), (10, 10), 3, LpRounding::RoundToNearest).unwrap(),
+      (2, 2)
+    );
+  }
+
+  #[test]
+  fn proportional_lp_rejects_empty_reserves_with_outstanding_lp() {
+    for reserves in [(0, 3_000), (2_000, 0), (0, 0)] {
+      assert_eq!(
+        proportional_lp((200, 300), reserves, 1_000, LpRounding::RoundDown).unwrap_err(),
+        AMMError::EmptyReserves.into()
+      );
+    }
+  }
+
+  #[test]
+  fn vault_shortfall_counts_reserve_and_accrued_fees() {
+    assert_eq!(vault_shortfall(1_000, 900, 100), 0);
+    assert_eq!(vault_shortfall(1_001, 900, 100), 0);
+    // Covering the reserve alone is not enough once fees are owed
+    assert_eq!(vault_shortfall(950, 900, 100), 50);
+    assert_eq!(vault_shortfall(0, u64::MAX, u64::MAX), u64::MAX);
+  }
+
+  #[test]
+  fn annualized_fee_bps_extrapolates_to_a_year() {
+    let year = SECONDS_PER_YEAR as i64;
+    // 5% of the reserve earned over a full year
+    assert_eq!(annualized_fee_bps(50, 1_000, year), 500);
+    // The same fees over half a year double the yield
+    assert_eq!(annualized_fee_bps(50, 1_000, year / 2), 1_000);
+    assert_eq!(annualized_fee_bps(50, 0, year), 0);
+    assert_eq!(annualized_fee_bps(50, 1_000, 0), 0);
+    assert_eq!(annualized_fee_bps(50, 1_000, -1), 0);
+    assert_eq!(annualized_fee_bps(u128::MAX, 1, 1), u64::MAX);
+  }
+
+  #[test]
+  fn imbalance_lp_rounds_down() {
+    // 1000 LP over a reserve growing 1000 -> 1001: 1000 * (sqrt(1.001) - 1) = 0.49988 -> 0
+    assert_eq!(imbalance_lp(1, 1000, 1000, 0).unwrap(), 0);
+    // 3000 -> 4000: 3000 * (sqrt(4/3) - 1) = 464.10 -> 464, then 464 * 0.999 = 463.54 -> 463
+    assert_eq!(imbalance_lp(1000, 3000, 3000, 0).unwrap(), 464);
+    assert_eq!(imbalance_lp(1000, 3000, 3000, 10).unwrap(), 463);
+    // Exact growth is not shaved: 1000 -> 4000 doubles sqrt, minting the supply again
+    assert_eq!(imbalance_lp(3000, 1000, 1000, 0).unwrap(), 1000);
+  }
+
+  #[test]
+  fn imbalance_lp_mints_nothing_without_excess_or_with_a_full_fee() {
+    assert_eq!(imbalance_lp(0, 1000, 1000, 0).unwrap(), 0);
+    assert_eq!(imbalance_lp(1000, 1000, 1000, BPS_DENOMINATOR).unwrap(), 0);
+    assert_eq!(
+      imbalance_lp(1000, 0, 1000, 0).unwrap_err(),
+      AMMError::DivisionByZero.into()
+    );
+  }
+}
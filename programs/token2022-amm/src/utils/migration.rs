@@ -0,0 +1,257 @@
+use {
+  crate::common::error::AMMError,
+  anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+  },
+};
+
+/// An account whose layout grows by fields appended at its end, tracked by a version byte
+pub trait Migrate: AccountSerialize + AccountDeserialize + Space {
+  /// Version new accounts are created at
+  const VERSION: u8;
+
+  fn version(&self) -> u8;
+
+  /// Gives every field added after the account's version its default and stamps `VERSION`
+  fn migrate(&mut self);
+
+  /// Account size the current layout needs
+  fn migrated_len(&self) -> usize;
+
+  /// Reads account data written under any version of `T`. Layouts that only ever grew by
+  /// appended fields read zero-extended; override this for one that changed otherwise
+  fn deserialize_versioned(data: &[u8]) -> Result<Self> {
+    deserialize_zero_extended(data)
+  }
+}
+
+/// Deserializes account data that may predate fields appended to `T` since: the bytes an
+/// old, shorter layout lacks read as zero
+pub fn deserialize_zero_extended<T: Migrate>(data: &[u8]) -> Result<T> {
+  let mut extended = data.to_vec();
+  extended.resize(data.len() + T::INIT_SPACE, 0);
+  T::try_deserialize(&mut extended.as_slice())
+}
+
+/// Current layout of account data written under any earlier version of `T`, padded with
+/// zeros to `migrated_len`. `None` when the data is already current and large enough
+pub fn migrate_account_data<T: Migrate>(data: &[u8]) -> Result<Option<Vec<u8>>> {
+  let mut account = T::deserialize_versioned(data)?;
+  require!(
+    account.version() <= T::VERSION,
+    AMMError::UnsupportedAccountVersion
+  );
+  if account.version() == T::VERSION && data.len() >= account.migrated_len() {
+    return Ok(None);
+  }
+
+  account.migrate();
+  let mut migrated = Vec::with_capacity(account.migrated_len());
+  account.try_serialize(&mut migrated)?;
+  require!(
+    migrated.len() <= account.migrated_len(),
+    AMMError::MathOverflow
+  );
+  migrated.resize(account.migrated_len(), 0);
+  Ok(Some(migrated))
+}
+
+/// Rewrites `account` in the current layout of `T`, growing it first (rent paid by `payer`)
+/// when the layout needs more room. Returns whether anything changed
+pub fn migrate_account<'info, T: Migrate>(
+  account: &AccountInfo<'info>,
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+  let migrated = match migrate_account_data::<T>(&account.try_borrow_data()?)? {
+    Some(migrated) => migrated,
+    None => return Ok(false),
+  };
+
+  if account.data_len() < migrated.len() {
+    let required_lamports = Rent::get()?.minimum_balance(migrated.len());
+    let lamports = account.lamports();
+    if lamports < required_lamports {
+      transfer(
+        CpiContext::new(
+          system_program.clone(),
+          Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+          },
+        ),
+        required_lamports - lamports,
+      )?;
+    }
+    account.resize(migrated.len())?;
+  }
+  account.try_borrow_mut_data()?[..migrated.len()].copy_from_slice(&migrated);
+
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      common::constant::{seed_prefix, BPS_DENOMINATOR},
+      state::{
+        Config, PoolState, CONFIG_VERSION, MAX_WHITELIST_CAPACITY, MAX_WHITE_LIST_LP,
+        POOL_STATE_VERSION,
+      },
+    },
+  };
+
+  fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+  }
+
+  fn pool_state() -> PoolState {
+    let mut pool_state: PoolState = deserialize_zero_extended(PoolState::DISCRIMINATOR).unwrap();
+    pool_state.reserve_x = 1_000;
+    pool_state.reserve_y = 2_000;
+    pool_state.lp_supply = 1_414;
+    pool_state.cumulative_fee_x = 7;
+    pool_state.version = POOL_STATE_VERSION;
+    pool_state
+  }
+
+  #[test]
+  fn migrates_a_pool_state_from_before_the_transfer_fee_counters() {
+    // Version 0 ends at `cumulative_fee_y`: drop both counters and the version byte
+    let current = serialize(&pool_state());
+    let old = &current[..current.len() - (16 + 16 + 1)];
+
+    let migrated = migrate_account_data::<PoolState>(old).unwrap().unwrap();
+    assert_eq!(migrated.len(), 8 + PoolState::INIT_SPACE);
+
+    let pool_state = PoolState::try_deserialize(&mut migrated.as_slice()).unwrap();
+    assert_eq!(pool_state.reserve_x, 1_000);
+    assert_eq!(pool_state.reserve_y, 2_000);
+    assert_eq!(pool_state.lp_supply, 1_414);
+    assert_eq!(pool_state.cumulative_fee_x, 7);
+    assert_eq!(pool_state.cumulative_transfer_fee_x, 0);
+    assert_eq!(pool_state.cumulative_transfer_fee_y, 0);
+    assert_eq!(pool_state.version, POOL_STATE_VERSION);
+  }
+
+  #[test]
+  fn leaves_a_current_pool_state_alone() {
+    let mut current = serialize(&pool_state());
+    current.resize(8 + PoolState::INIT_SPACE, 0);
+
+    assert!(migrate_account_data::<PoolState>(&current)
+      .unwrap()
+      .is_none());
+  }
+
+  #[test]
+  fn migrates_a_config_with_a_full_whitelist() {
+    let mut config: Config = deserialize_zero_extended(Config::DISCRIMINATOR).unwrap();
+    config.fee = 30;
+    config.whitelist_capacity = MAX_WHITE_LIST_LP as u16;
+    config.white_list_lp = vec![Pubkey::new_unique(); MAX_WHITE_LIST_LP];
+    config.circuit_breaker_bps = 500;
+
+    // A full whitelist leaves no slack behind the serialized fields, so version 0 is
+    // exactly the version byte and the pending fee withdrawal delay short of the current one
+    let current = serialize(&config);
+    let old = &current[..current.len() - (1 + 8 + 8)];
+    assert!(Config::try_deserialize(&mut &old[..]).is_err());
+
+    let migrated = migrate_account_data::<Config>(old).unwrap().unwrap();
+    assert_eq!(
+      migrated.len(),
+      Config::space_for_whitelist(MAX_WHITE_LIST_LP as u16)
+    );
+
+    let migrated = Config::try_deserialize(&mut migrated.as_slice()).unwrap();
+    assert_eq!(migrated.fee, 30);
+    assert_eq!(migrated.white_list_lp, config.white_list_lp);
+    assert_eq!(migrated.circuit_breaker_bps, 500);
+    assert_eq!(migrated.version, CONFIG_VERSION);
+  }
+
+  /// Config in the original layout, before the whitelist could be toggled or resized
+  fn original_config(white_list_lp: Option<[Pubkey; MAX_WHITE_LIST_LP]>) -> (Vec<u8>, Pubkey) {
+    let seed = 7u64;
+    let (config_key, config_bump) = Pubkey::find_program_address(
+      &[seed_prefix::CONFIG, seed.to_le_bytes().as_ref()],
+      &crate::ID,
+    );
+    let (pool_authority, auth_bump) =
+      Pubkey::find_program_address(&[seed_prefix::AUTH, config_key.as_ref()], &crate::ID);
+
+    let mut data = Config::DISCRIMINATOR.to_vec();
+    (
+      seed,
+      Pubkey::new_unique(),
+      Pubkey::new_unique(),
+      Pubkey::new_unique(),
+      30u16,
+      true,
+      white_list_lp,
+      auth_bump,
+      config_bump,
+      1u8,
+    )
+      .serialize(&mut data)
+      .unwrap();
+    data.resize(8 + 8 + 32 * 3 + 2 + 1 + (1 + 32 * MAX_WHITE_LIST_LP) + 3, 0);
+    (data, pool_authority)
+  }
+
+  #[test]
+  fn migrates_an_original_config_without_a_whitelist() {
+    let (old, pool_authority) = original_config(None);
+    // Whatever the whitelist capacity, a current config never has the original size
+    assert!((0..=MAX_WHITELIST_CAPACITY)
+      .all(|capacity| Config::space_for_whitelist(capacity) != old.len()));
+
+    let migrated = migrate_account_data::<Config>(&old).unwrap().unwrap();
+    assert_eq!(
+      migrated.len(),
+      Config::space_for_whitelist(MAX_WHITE_LIST_LP as u16)
+    );
+
+    let config = Config::try_deserialize(&mut migrated.as_slice()).unwrap();
+    assert_eq!(config.seed, 7);
+    assert_eq!(config.fee, 30);
+    assert!(config.locked);
+    assert!(!config.whitelist_enabled);
+    assert!(config.admits_depositor(&Pubkey::new_unique()));
+    assert_eq!(config.whitelist_capacity, MAX_WHITE_LIST_LP as u16);
+    assert_eq!(config.vault_authority, pool_authority);
+    assert_eq!(config.max_swap_ratio_bps, BPS_DENOMINATOR);
+    assert_eq!(config.imbalance_fee_bps, BPS_DENOMINATOR);
+    assert_eq!(config.lp_bump, 1);
+    assert_eq!(config.version, CONFIG_VERSION);
+  }
+
+  #[test]
+  fn migrates_an_original_config_with_a_whitelist() {
+    let lp = Pubkey::new_unique();
+    let mut white_list_lp = [Pubkey::default(); MAX_WHITE_LIST_LP];
+    white_list_lp[0] = lp;
+    let (old, _) = original_config(Some(white_list_lp));
+
+    let migrated = migrate_account_data::<Config>(&old).unwrap().unwrap();
+    let config = Config::try_deserialize(&mut migrated.as_slice()).unwrap();
+    assert!(config.whitelist_enabled);
+    assert_eq!(config.white_list_lp, vec![lp]);
+    assert!(config.admits_depositor(&lp));
+    assert!(!config.admits_depositor(&Pubkey::new_unique()));
+  }
+
+  #[test]
+  fn rejects_data_from_a_newer_version() {
+    let mut pool_state = pool_state();
+    pool_state.version = POOL_STATE_VERSION + 1;
+
+    assert!(migrate_account_data::<PoolState>(&serialize(&pool_state)).is_err());
+  }
+}
@@ -1 +1,4 @@
+pub mod math;
+pub mod migration;
+pub mod swap;
 pub mod token;
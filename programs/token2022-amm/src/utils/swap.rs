@@ -0,0 +1,376 @@
+use {
+  crate::{
+    common::{constant::BPS_DENOMINATOR, error::AMMError},
+    state::{Config, PoolState},
+    utils::math::{div_ceil, div_floor},
+  },
+  anchor_lang::prelude::*,
+};
+
+/// Which way a swap moves through the pool, resolved from the mints being swapped
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwapDirection {
+  XToY,
+  YToX,
+}
+
+impl SwapDirection {
+  /// Resolves the direction of a `mint_in` -> `mint_out` swap against the pool's mints,
+  /// rejecting any pair that is not exactly (mint_x, mint_y) in either order
+  pub fn resolve(config: &Config, mint_in: &Pubkey, mint_out: &Pubkey) -> Result<Self> {
+    Self::from_mints(&config.mint_x, &config.mint_y, mint_in, mint_out)
+  }
+
+  fn from_mints(
+    mint_x: &Pubkey,
+    mint_y: &Pubkey,
+    mint_in: &Pubkey,
+    mint_out: &Pubkey,
+  ) -> Result<Self> {
+    if mint_in == mint_x && mint_out == mint_y {
+      Ok(Self::XToY)
+    } else if mint_in == mint_y && mint_out == mint_x {
+      Ok(Self::YToX)
+    } else {
+      Err(AMMError::InvalidMint.into())
+    }
+  }
+
+  pub fn is_x_to_y(self) -> bool {
+    self == Self::XToY
+  }
+
+  /// (reserve_in, reserve_out)
+  pub fn reserves(self, pool_state: &PoolState) -> (u64, u64) {
+    match self {
+      Self::XToY => (pool_state.reserve_x, pool_state.reserve_y),
+      Self::YToX => (pool_state.reserve_y, pool_state.reserve_x),
+    }
+  }
+}
+
+/// Price impact in bps of swapping `amount_in` against the reserves, before any fee:
+/// (mid_price - execution_price) / mid_price. On the constant product curve the output is
+/// amount_in * reserve_out / (reserve_in + amount_in), so this reduces to
+/// amount_in / (reserve_in + amount_in), rounded down. Near 0 for dust and approaching
+/// BPS_DENOMINATOR as the input dwarfs the reserve
+pub fn price_impact_bps(reserve_in: u64, reserve_out: u64, amount_in: u64) -> Result<u16> {
+  require!(reserve_in > 0 && reserve_out > 0, AMMError::EmptyReserves);
+
+  // amount_in < reserve_in + amount_in, so the result is below BPS_DENOMINATOR and fits
+  let impact =
+    (amount_in as u128) * BPS_DENOMINATOR as u128 / (reserve_in as u128 + amount_in as u128);
+  Ok(impact as u16)
+}
+
+/// Constant product output of `amount_in` after the pool fee (in bps):
+/// amount_in * (F - fee) * reserve_out / (reserve_in * F + amount_in * (F - fee)) with
+/// F = BPS_DENOMINATOR,
+/// rounded down so the pool never pays out a fraction of a unit it does not have
+pub fn constant_product_out(
+  amount_in: u64,
+  reserve_in: u64,
+  reserve_out: u64,
+  fee: u16,
+) -> Result<u64> {
+  let fee_adjusted_amount_in = (amount_in as u128)
+    .checked_mul(
+      (BPS_DENOMINATOR as u128)
+        .checked_sub(fee as u128)
+        .ok_or(AMMError::InvalidAmount)?,
+    )
+    .ok_or(AMMError::InvalidAmount)?;
+  let numerator = fee_adjusted_amount_in
+    .checked_mul(reserve_out as u128)
+    .ok_or(AMMError::InvalidAmount)?;
+  let denominator = (reserve_in as u128)
+    .checked_mul(BPS_DENOMINATOR as u128)
+    .ok_or(AMMError::InvalidAmount)?
+    .checked_add(fee_adjusted_amount_in)
+    .ok_or(AMMError::InvalidAmount)?;
+
+  Ok(u64::try_from(div_floor(numerator, denominator)?).map_err(|_| AMMError::MathOverflow)?)
+}
+
+/// Relative drop, in bps, of the spot price of the input token (reserve_out / reserve_in)
+/// between the (reserve_in, reserve_out) pairs before and after a swap. Swaps only ever
+/// push it down; a rise reads as 0
+pub fn spot_price_move_bps(before: (u64, u64), after: (u64, u64)) -> Result<u16> {
+  require!(before.1 > 0 && after.0 > 0, AMMError::EmptyReserves);
+
+  // (p_before - p_after) / p_before = 1 - (out_after * in_before) / (out_before * in_after),
+  // both products fit in u128
+  let denominator = before.1 as u128 * after.0 as u128;
+  let numerator = denominator.saturating_sub(after.1 as u128 * before.0 as u128);
+
+  // Drop low bits so numerator * BPS_DENOMINATOR cannot overflow; the ratio is unaffected beyond
+  // the last few of its ~114 remaining bits
+  let shift = (u128::BITS - denominator.leading_zeros()).saturating_sub(114);
+  let move_bps = div_floor(
+    (numerator >> shift) * BPS_DENOMINATOR as u128,
+    denominator >> shift,
+  )?;
+
+  // numerator <= denominator, so this is at most BPS_DENOMINATOR
+  Ok(move_bps as u16)
+}
+
+/// Output a vault holding `vault_balance` can pay for a computed `amount_out`. Transfer fee
+/// rounding can leave the vault a unit or so short of what the reserve accounting promises;
+/// a shortfall within `grace` is absorbed by paying out the whole vault balance, anything
+/// larger means the pool is desynced and fails
+pub fn fit_to_vault(amount_out: u64, vault_balance: u64, grace: u64) -> Result<u64> {
+  if vault_balance >= amount_out {
+    return Ok(amount_out);
+  }
+  require!(
+    amount_out - vault_balance <= grace,
+    AMMError::InsufficientLiquidity
+  );
+  Ok(vault_balance)
+}
+
+/// Smallest raw `amount_in` a swap accepts: enough to buy one raw unit of the output at
+/// the spot price of the reserves, `ceil(reserve_in / reserve_out)`. Anything less has no
+/// output proportional to it and only buys rounding. Deriving it from the reserves scales it
+/// with both mints' decimals, in whichever direction: a 9-decimal input against a pricier
+/// 0-decimal output needs thousands of raw units, while a single unit of a 0-decimal input
+/// worth many output units is enough
+pub fn min_swap_amount_in(reserve_in: u64, reserve_out: u64) -> Result<u64> {
+  require!(reserve_out > 0, AMMError::InsufficientLiquidity);
+  let min_amount_in = div_ceil(reserve_in as u128, reserve_out as u128)?.max(1);
+  Ok(u64::try_from(min_amount_in).map_err(|_| AMMError::MathOverflow)?)
+}
+
+/// Largest raw `amount_in` a single swap accepts: `max_swap_ratio_bps` of the input
+/// reserve, rounded down so a fractional unit never lets a trade past the cap
+pub fn max_swap_amount_in(reserve_in: u64, max_swap_ratio_bps: u16) -> Result<u64> {
+  // u64 * u16 always fits in u128, and the ratio keeps the quotient within u64
+  let cap = div_floor(
+    reserve_in as u128 * max_swap_ratio_bps as u128,
+    BPS_DENOMINATOR as u128,
+  )?;
+  Ok(u64::try_from(cap).map_err(|_| AMMError::MathOverflow)?)
+}
+
+/// Pool fee charged on `actual_amount_in`, rounded down. The curve already keeps the whole
+/// fee through `constant_product_out`; this amount only sizes the protocol's cut carved out
+/// of the reserves, which is paid out of the pool and so must not round up
+pub fn swap_fee_amount(actual_amount_in: u64, fee: u16) -> Result<u64> {
+  let fee_amount = div_floor(
+    actual_amount_in as u128 * fee as u128,
+    BPS_DENOMINATOR as u128,
+  )?;
+  Ok(u64::try_from(fee_amount).map_err(|_| AMMError::MathOverflow)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::common::constant::MAX_FEE_BPS};
+
+  #[test]
+  fn resolves_both_directions_of_the_pool_pair() {
+    let (mint_x, mint_y) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_eq!(
+      SwapDirection::from_mints(&mint_x, &mint_y, &mint_x, &mint_y).unwrap(),
+      SwapDirection::XToY
+    );
+    assert_eq!(
+      SwapDirection::from_mints(&mint_x, &mint_y, &mint_y, &mint_x).unwrap(),
+      SwapDirection::YToX
+    );
+  }
+
+  #[test]
+  fn rejects_same_mint_on_both_sides() {
+    let (mint_x, mint_y) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    for mint in [mint_x, mint_y] {
+      assert_eq!(
+        SwapDirection::from_mints(&mint_x, &mint_y, &mint, &mint).unwrap_err(),
+        AMMError::InvalidMint.into()
+      );
+    }
+  }
+
+  #[test]
+  fn rejects_mints_outside_the_pool() {
+    let (mint_x, mint_y) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let other = Pubkey::new_unique();
+
+    for (mint_in, mint_out) in [
+      (other, mint_y),
+      (mint_x, other),
+      (other, mint_x),
+      (mint_y, other),
+    ] {
+      assert_eq!(
+        SwapDirection::from_mints(&mint_x, &mint_y, &mint_in, &mint_out).unwrap_err(),
+        AMMError::InvalidMint.into()
+      );
+    }
+  }
+
+  #[test]
+  fn price_impact_is_zero_for_dust_and_bounded_for_huge_inputs() {
+    assert_eq!(price_impact_bps(1_000_000, 1_000_000, 0).unwrap(), 0);
+    assert_eq!(
+      price_impact_bps(1_000_000_000, 1_000_000_000, 1).unwrap(),
+      0
+    );
+    assert_eq!(price_impact_bps(1, 1, u64::MAX).unwrap(), 9999);
+    assert_eq!(
+      price_impact_bps(u64::MAX, u64::MAX, u64::MAX).unwrap(),
+      5000
+    );
+  }
+
+  #[test]
+  fn price_impact_matches_execution_against_mid_price() {
+    // 1% of the input reserve moves the price by about 1%
+    assert_eq!(price_impact_bps(1_000_000, 2_000_000, 10_000).unwrap(), 99);
+    assert_eq!(
+      price_impact_bps(1_000_000, 2_000_000, 1_000_000).unwrap(),
+      5000
+    );
+    assert!(price_impact_bps(0, 1, 1).is_err());
+  }
+
+  #[test]
+  fn constant_product_out_rounds_down() {
+    // 10 * 10 / (10 + 10) = 5 exactly, 10 * 10 / (11 + 10) = 4.76 -> 4
+    assert_eq!(constant_product_out(10, 10, 10, 0).unwrap(), 5);
+    assert_eq!(constant_product_out(10, 11, 10, 0).unwrap(), 4);
+    // With a 3% fee: 9700 * 1000 * 7 / (1000 * 10000 + 9700 * 1000) = 3.44 -> 3
+    assert_eq!(constant_product_out(1000, 1000, 7, 300).unwrap(), 3);
+    // Dust rounds to nothing rather than to one unit
+    assert_eq!(constant_product_out(1, 1_000_000, 1_000_000, 0).unwrap(), 0);
+  }
+
+  #[test]
+  fn constant_product_out_charges_fees_over_the_bps_denominator() {
+    let (amount_in, reserve_in, reserve_out) = (1_000_000u64, 50_000_000u64, 80_000_000u64);
+
+    for fee in [0, 30, 300, MAX_FEE_BPS] {
+      let net_in = amount_in as u128 * (BPS_DENOMINATOR - fee) as u128;
+      let expected =
+        net_in * reserve_out as u128 / (reserve_in as u128 * BPS_DENOMINATOR as u128 + net_in);
+      assert_eq!(
+        constant_product_out(amount_in, reserve_in, reserve_out, fee).unwrap() as u128,
+        expected
+      );
+    }
+    // A fee of the whole denominator leaves nothing to swap
+    assert_eq!(
+      constant_product_out(amount_in, reserve_in, reserve_out, BPS_DENOMINATOR).unwrap(),
+      0
+    );
+  }
+
+  #[test]
+  fn constant_product_out_rejects_empty_pools_and_full_fees() {
+    assert_eq!(
+      constant_product_out(0, 0, 10, 0).unwrap_err(),
+      AMMError::DivisionByZero.into()
+    );
+    assert_eq!(
+      constant_product_out(10, 10, 10, 10001).unwrap_err(),
+      AMMError::InvalidAmount.into()
+    );
+  }
+
+  #[test]
+  fn spot_price_move_matches_the_reserve_ratios() {
+    assert_eq!(spot_price_move_bps((1000, 1000), (1000, 1000)).unwrap(), 0);
+    // 1:1 -> 2000 in / 500 out, price 1 -> 0.25
+    assert_eq!(
+      spot_price_move_bps((1000, 1000), (2000, 500)).unwrap(),
+      7500
+    );
+    // 1% more input against the same output
+    assert_eq!(
+      spot_price_move_bps((10_000, 10_000), (10_100, 10_000)).unwrap(),
+      99
+    );
+    // A rise is not a move in the swap's direction
+    assert_eq!(spot_price_move_bps((1000, 1000), (500, 2000)).unwrap(), 0);
+    // Emptying the output side is a full move
+    assert_eq!(spot_price_move_bps((1000, 1000), (2000, 0)).unwrap(), 10000);
+  }
+
+  #[test]
+  fn spot_price_move_handles_full_range_reserves() {
+    assert_eq!(
+      spot_price_move_bps((u64::MAX, u64::MAX), (u64::MAX, u64::MAX / 2)).unwrap(),
+      5000
+    );
+    assert_eq!(
+      spot_price_move_bps((u64::MAX / 2, u64::MAX), (u64::MAX, u64::MAX)).unwrap(),
+      5000
+    );
+    assert!(spot_price_move_bps((1, 0), (1, 0)).is_err());
+  }
+
+  #[test]
+  fn min_swap_amount_in_buys_a_unit_at_spot() {
+    // 1 000 whole 0-decimal tokens against 1 9-decimal token: 10^6 raw units per whole token
+    assert_eq!(min_swap_amount_in(1_000_000_000, 1_000).unwrap(), 1_000_000);
+    assert_eq!(min_swap_amount_in(1_000, 1_000_000_000).unwrap(), 1);
+    assert_eq!(min_swap_amount_in(1_001, 1_000).unwrap(), 2);
+    assert_eq!(min_swap_amount_in(1_000, 1_000).unwrap(), 1);
+    // One unit under the minimum buys nothing, even before fees
+    let min = min_swap_amount_in(1_000_000_000, 1_000).unwrap();
+    assert_eq!(
+      constant_product_out(min - 1, 1_000_000_000, 1_000, 0).unwrap(),
+      0
+    );
+    assert!(min_swap_amount_in(1, 0).is_err());
+  }
+
+  #[test]
+  fn fit_to_vault_keeps_covered_amounts() {
+    assert_eq!(fit_to_vault(100, 100, 0).unwrap(), 100);
+    assert_eq!(fit_to_vault(100, 500, 1).unwrap(), 100);
+  }
+
+  #[test]
+  fn fit_to_vault_absorbs_a_shortfall_within_grace() {
+    // One unit short, as left behind by transfer fee rounding
+    assert_eq!(fit_to_vault(100, 99, 1).unwrap(), 99);
+    assert_eq!(fit_to_vault(100, 95, 5).unwrap(), 95);
+  }
+
+  #[test]
+  fn fit_to_vault_rejects_a_shortfall_beyond_grace() {
+    for (amount_out, vault_balance, grace) in [(100, 99, 0), (100, 98, 1), (100, 0, 99)] {
+      assert_eq!(
+        fit_to_vault(amount_out, vault_balance, grace).unwrap_err(),
+        AMMError::InsufficientLiquidity.into()
+      );
+    }
+  }
+
+  #[test]
+  fn max_swap_amount_in_rounds_the_cap_down() {
+    // 999 * 5000 / 10000 = 499.5 -> 499
+    assert_eq!(max_swap_amount_in(999, 5000).unwrap(), 499);
+    assert_eq!(max_swap_amount_in(1000, 5000).unwrap(), 500);
+    // 1 * 9999 / 10000 rounds to no allowance at all
+    assert_eq!(max_swap_amount_in(1, 9999).unwrap(), 0);
+    assert_eq!(
+      max_swap_amount_in(u64::MAX, BPS_DENOMINATOR).unwrap(),
+      u64::MAX
+    );
+  }
+
+  #[test]
+  fn swap_fee_amount_rounds_down() {
+    // 999 * 30 / 10000 = 2.997 -> 2
+    assert_eq!(swap_fee_amount(999, 30).unwrap(), 2);
+    assert_eq!(swap_fee_amount(1000, 30).unwrap(), 3);
+    // A fee too small to reach one unit is not charged as one
+    assert_eq!(swap_fee_amount(333, 30).unwrap(), 0);
+    assert_eq!(swap_fee_amount(u64::MAX, 0).unwrap(), 0);
+  }
+}
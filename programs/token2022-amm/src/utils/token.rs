@@ -1,8 +1,14 @@
 use {
-  crate::common::error::AMMError,
-  anchor_lang::prelude::*,
+  crate::{
+    common::error::AMMError,
+    state::{Config, PoolState},
+  },
+  anchor_lang::{
+    prelude::*,
+    system_program::{self, Transfer},
+  },
   anchor_spl::{
-    token::Token,
+    token::{spl_token, Token},
     token_2022::spl_token_2022::{
       self,
       extension::{
@@ -11,7 +17,7 @@ use {
         BaseStateWithExtensions, ExtensionType, StateWithExtensions,
       },
     },
-    token_interface::Mint,
+    token_interface::{sync_native, Mint, SyncNative, TokenAccount},
   },
 };
 
@@ -35,6 +41,108 @@ pub fn verify_supported_token_mint(token_mint: &InterfaceAccount<'_, Mint>) -> R
     return Ok(false);
   }
 
+  // Name the extension so the rejection can be told apart in the logs; the raw value is
+  // also available through `check_mint_supported`
+  if let Some(extension) = get_unsupported_token_extension(token_mint)? {
+    msg!(
+      "Mint {} rejected due to {:?} (extension type {})",
+      token_mint.key(),
+      extension,
+      u16::from(extension)
+    );
+    return Err(AMMError::NotAllowedTokenExtension.into());
+  }
+
+  Ok(true)
+}
+
+pub fn is_legacy_token_mint(token_mint: &InterfaceAccount<'_, Mint>) -> bool {
+  *token_mint.to_account_info().owner == Token::id()
+}
+
+/// Fails unless `vault` is exactly the vault `pool_state` stores for `mint` and holds that
+/// mint. Which vault is expected follows from the mint alone, so a vault of the right mint
+/// from another pool, or the pool's other vault, never passes
+pub fn ensure_pool_vault(
+  config: &Config,
+  pool_state: &PoolState,
+  vault: &InterfaceAccount<'_, TokenAccount>,
+  mint: &Pubkey,
+) -> Result<()> {
+  let expected_vault = if *mint == config.mint_x {
+    pool_state.vault_x
+  } else if *mint == config.mint_y {
+    pool_state.vault_y
+  } else {
+    return Err(AMMError::InvalidMint.into());
+  };
+
+  require!(
+    vault.key() == expected_vault && vault.mint == *mint,
+    AMMError::InvalidVault
+  );
+  Ok(())
+}
+
+/// Whether `mint` is the wrapped SOL mint of the legacy Token program, the only native mint
+/// a pool can hold
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+  spl_token::native_mint::check_id(mint)
+}
+
+/// Moves `lamports` from `user` into its wrapped SOL account and syncs the token balance
+pub fn wrap_lamports<'info>(
+  system_program: AccountInfo<'info>,
+  token_program: AccountInfo<'info>,
+  user: AccountInfo<'info>,
+  user_token: AccountInfo<'info>,
+  lamports: u64,
+) -> Result<()> {
+  system_program::transfer(
+    CpiContext::new(
+      system_program,
+      Transfer {
+        from: user,
+        to: user_token.clone(),
+      },
+    ),
+    lamports,
+  )?;
+  sync_native(CpiContext::new(
+    token_program,
+    SyncNative {
+      account: user_token,
+    },
+  ))
+}
+
+/// Re-checks the pool mints on hot paths. Each mint comes with the legacy verdict cached on
+/// the config at init: a legacy Token mint cannot gain extensions, so it is skipped without
+/// touching its data and only Token-2022 mints are parsed again.
+pub fn reverify_pool_mints(mints: [(&InterfaceAccount<'_, Mint>, bool); 2]) -> Result<()> {
+  for (token_mint, cached_legacy) in mints {
+    if cached_legacy {
+      continue;
+    }
+    require!(
+      verify_supported_token_mint(token_mint)?,
+      AMMError::NotAllowedTokenExtension
+    );
+  }
+
+  Ok(())
+}
+
+/// Returns the first extension on a Token-2022 mint that the program does not support,
+/// or `None` if every extension is allowed (or the mint is owned by the Token program).
+pub fn get_unsupported_token_extension(
+  token_mint: &InterfaceAccount<'_, Mint>,
+) -> Result<Option<ExtensionType>> {
+  let token_mint_info = token_mint.to_account_info();
+  if *token_mint_info.owner == Token::id() {
+    return Ok(None);
+  }
+
   let token_mint_data = token_mint_info.try_borrow_data()?;
   let token_mint_unpacked =
     StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint_data)?;
@@ -42,40 +150,27 @@ pub fn verify_supported_token_mint(token_mint: &InterfaceAccount<'_, Mint>) -> R
   let tlv_data = token_mint_unpacked.get_tlv_data();
   let extensions = get_token_extension_types(tlv_data)?;
 
-  // Check if any extension is in the NOT_ALLOW_TOKEN_EXTS list
-  for extension in extensions {
-    match extension {
-      // supported extensions
-      ExtensionType::TransferFeeConfig => {}
-      ExtensionType::InterestBearingConfig => {}
-      ExtensionType::TokenMetadata => {}
-      ExtensionType::MetadataPointer => {}
-      // partially supported
-      ExtensionType::ConfidentialTransferMint => {
-        // Supported, but non-confidential transfer only
-      }
-      ExtensionType::ConfidentialTransferFeeConfig => {
-        // Supported, but non-confidential transfer only
-      }
-      // explicitly not allowed (handled above by NOT_ALLOW_TOKEN_EXTS check)
-      ExtensionType::PermanentDelegate
-      | ExtensionType::TransferHook
-      | ExtensionType::NonTransferable => {
-        // This should not be reached due to the check above, but kept for safety
-        return Err(AMMError::NotAllowedTokenExtension.into());
-      }
-      // other unsupported extensions
-      ExtensionType::MintCloseAuthority | ExtensionType::DefaultAccountState => {
-        return Err(AMMError::NotAllowedTokenExtension.into());
-      }
-      // Catch-all for unknown/future extensions - be conservative
-      _ => {
-        return Err(AMMError::NotAllowedTokenExtension.into());
-      }
-    }
-  }
+  Ok(
+    extensions
+      .into_iter()
+      .find(|extension| !is_supported_token_extension(*extension)),
+  )
+}
 
-  Ok(true)
+fn is_supported_token_extension(extension: ExtensionType) -> bool {
+  // PermanentDelegate, TransferHook, NonTransferable, MintCloseAuthority, DefaultAccountState
+  // and any unknown/future extension are rejected - be conservative
+  matches!(
+    extension,
+    // supported extensions
+    ExtensionType::TransferFeeConfig
+      | ExtensionType::InterestBearingConfig
+      | ExtensionType::TokenMetadata
+      | ExtensionType::MetadataPointer
+      // partially supported: non-confidential transfer only
+      | ExtensionType::ConfidentialTransferMint
+      | ExtensionType::ConfidentialTransferFeeConfig
+  )
 }
 
 // reference implementation: get_tlv_data_info
@@ -154,10 +249,10 @@ pub fn calculate_transfer_fee_excluded_amount(
   if let Some(epoch_transfer_fee) = get_epoch_transfer_fee(token_mint)? {
     let transfer_fee = epoch_transfer_fee
       .calculate_fee(transfer_fee_included_amount)
-      .unwrap();
+      .ok_or(AMMError::TransferFeeCalculationError)?;
     let transfer_fee_excluded_amount = transfer_fee_included_amount
       .checked_sub(transfer_fee)
-      .unwrap();
+      .ok_or(AMMError::TransferFeeCalculationError)?;
     return Ok(TransferFeeExcludedAmount {
       amount: transfer_fee_excluded_amount,
       transfer_fee,
@@ -205,7 +300,7 @@ pub fn calculate_transfer_fee_included_amount(
     // verify transfer fee calculation for safety
     let transfer_fee_verification = epoch_transfer_fee
       .calculate_fee(transfer_fee_included_amount)
-      .unwrap();
+      .ok_or(AMMError::TransferFeeCalculationError)?;
     if transfer_fee != transfer_fee_verification {
       // We believe this should never happen
       return Err(AMMError::TransferFeeCalculationError.into());
@@ -223,6 +318,12 @@ pub fn calculate_transfer_fee_included_amount(
   })
 }
 
+/// Transfer fee of the current epoch, or `None` if the mint charges none.
+///
+/// The fee applies to every transfer of the mint, whichever account is the source:
+/// Token-2022's `TransferFeeConfig` has no per-account exemption, so the vaults can never
+/// be fee-exempt and output transfers always pay it. The only ways around the fee are
+/// mint-level (a 0 bps schedule, or no extension), and both show up here.
 pub fn get_epoch_transfer_fee(
   token_mint: &InterfaceAccount<'_, Mint>,
 ) -> Result<Option<TransferFee>> {
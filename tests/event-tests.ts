@@ -0,0 +1,130 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { Keypair } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { UserTokenAccounts } from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  getEvents,
+  setupPool,
+  setWhitelistEnabled,
+  swapTokens,
+  updateConfig,
+  withdrawLiquidity,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Event Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  let pool: PoolAccounts;
+  let userAccounts: Map<string, UserTokenAccounts>;
+
+  // Asserts the transaction emitted `name` for this pool, with a timestamp
+  async function expectEvent(signature: string, name: string) {
+    const events = await getEvents(program, signature);
+    const event = events.find(
+      (e) => e.name.toLowerCase() === name.toLowerCase()
+    );
+
+    assert.isDefined(event, `Expected ${name} to be emitted`);
+    assert.isTrue(event!.data.config.equals(pool.config));
+
+    const now = Math.floor(Date.now() / 1000);
+    assert.approximately(event!.data.timestamp.toNumber(), now, 300);
+
+    return event!.data;
+  }
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+
+    ({ pool, userAccounts } = await setupPool(
+      provider.connection,
+      program,
+      authority,
+      [user.publicKey],
+      'legacy',
+      'legacy',
+      fee
+    ));
+  });
+
+  it('Should emit DepositEvent on deposit', async () => {
+    const signature = await depositLiquidity(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      100 * 10 ** 6,
+      100 * 10 ** 6
+    );
+
+    const data = await expectEvent(signature, 'DepositEvent');
+    assert.isTrue(data.user.equals(user.publicKey));
+    assert.equal(data.lpMinted.toString(), (100 * 10 ** 6).toString());
+  });
+
+  it('Should emit SwapEvent on swap', async () => {
+    const signature = await swapTokens(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      true,
+      10 * 10 ** 6
+    );
+
+    const data = await expectEvent(signature, 'SwapEvent');
+    assert.isTrue(data.mintIn.equals(pool.tokenX.mint));
+    assert.equal(data.amountIn.toString(), (10 * 10 ** 6).toString());
+  });
+
+  it('Should emit WithdrawEvent on withdraw', async () => {
+    const signature = await withdrawLiquidity(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      10 * 10 ** 6
+    );
+
+    const data = await expectEvent(signature, 'WithdrawEvent');
+    assert.equal(data.lpBurned.toString(), (10 * 10 ** 6).toString());
+  });
+
+  it('Should emit ConfigUpdatedEvent on update_config', async () => {
+    const signature = await updateConfig(program, authority, pool, {
+      fee: 100,
+    });
+
+    const data = await expectEvent(signature, 'ConfigUpdatedEvent');
+    assert.equal(data.fee, 100);
+  });
+
+  it('Should emit WhitelistUpdatedEvent on set_whitelist_enabled', async () => {
+    const signature = await setWhitelistEnabled(
+      program,
+      authority,
+      pool,
+      false
+    );
+
+    const data = await expectEvent(signature, 'WhitelistUpdatedEvent');
+    assert.isFalse(data.enabled);
+  });
+});
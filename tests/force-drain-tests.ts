@@ -0,0 +1,196 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { Keypair } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { freezeAccount, thawAccount } from '@solana/spl-token';
+import { createLegacyToken, UserTokenAccounts } from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  expectError,
+  getEvents,
+  getLpTokenAddress,
+  setupPoolWithTokens,
+  swapTokens,
+  withdrawLiquidity,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Force Drain Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+  // Freeze authority of both mints, outside of the program's control
+  let freezer: Keypair;
+
+  let pool: PoolAccounts;
+  let userAccounts: Map<string, UserTokenAccounts>;
+
+  function forceDrain(signer: Keypair) {
+    return program.methods
+      .forceDrain()
+      .accountsPartial({
+        authority: signer.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        poolAuthority: pool.poolAuthority,
+        mintX: pool.tokenX.mint,
+        mintY: pool.tokenY.mint,
+        vaultX: pool.vaultX,
+        vaultY: pool.vaultY,
+        tokenProgramX: pool.tokenX.tokenProgram,
+        tokenProgramY: pool.tokenY.tokenProgram,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  function unlockPool() {
+    return program.methods
+      .unlockPool()
+      .accountsPartial({
+        config: pool.config,
+        poolState: pool.poolState,
+        vaultX: pool.vaultX,
+        vaultY: pool.vaultY,
+      })
+      .rpc();
+  }
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+    freezer = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+
+    const tokenX = await createLegacyToken(
+      provider.connection,
+      authority,
+      authority.publicKey,
+      6,
+      freezer.publicKey
+    );
+    const tokenY = await createLegacyToken(
+      provider.connection,
+      authority,
+      authority.publicKey,
+      6,
+      freezer.publicKey
+    );
+
+    ({ pool, userAccounts } = await setupPoolWithTokens(
+      provider.connection,
+      program,
+      authority,
+      [user.publicKey],
+      tokenX,
+      tokenY,
+      fee
+    ));
+
+    await depositLiquidity(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      100 * 10 ** 6,
+      100 * 10 ** 6
+    );
+  });
+
+  it('Should reject force drain while the vaults are not frozen', async () => {
+    await expectError(forceDrain(authority), 'VaultsNotFrozen');
+  });
+
+  it('Should mark the pool stuck once both vaults are frozen', async () => {
+    for (const [vault, mint] of [
+      [pool.vaultX, pool.tokenX.mint],
+      [pool.vaultY, pool.tokenY.mint],
+    ]) {
+      await freezeAccount(provider.connection, authority, vault, mint, freezer);
+    }
+
+    await expectError(forceDrain(user), 'Unauthorized');
+
+    await forceDrain(authority);
+
+    const configData = await program.account.config.fetch(pool.config);
+    assert.isTrue(configData.stuck);
+    assert.isTrue(configData.locked);
+
+    // Accounting is left untouched, and the pool refuses further activity
+    const poolData = await program.account.poolState.fetch(pool.poolState);
+    assert.equal(poolData.reserveX.toString(), (100 * 10 ** 6).toString());
+    await expectError(
+      swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6
+      ),
+      'PoolLocked'
+    );
+  });
+
+  it('Should keep the pool stuck while a vault is still frozen', async () => {
+    await thawAccount(
+      provider.connection,
+      authority,
+      pool.vaultX,
+      pool.tokenX.mint,
+      freezer
+    );
+
+    await expectError(unlockPool(), 'VaultFrozen');
+
+    const configData = await program.account.config.fetch(pool.config);
+    assert.isTrue(configData.stuck);
+  });
+
+  it('Should let LPs exit once the freeze authority thaws the vaults', async () => {
+    await thawAccount(
+      provider.connection,
+      authority,
+      pool.vaultY,
+      pool.tokenY.mint,
+      freezer
+    );
+
+    // Permissionless: the provider wallet is neither the authority nor an LP
+    const signature = await unlockPool();
+
+    const configData = await program.account.config.fetch(pool.config);
+    assert.isFalse(configData.stuck);
+    assert.isFalse(configData.locked);
+
+    const event = (await getEvents(program, signature)).find(
+      (e) => e.name.toLowerCase() === 'poolunlockedevent'
+    );
+    assert.isDefined(event);
+    assert.isTrue(event!.data.config.equals(pool.config));
+
+    const lpBalance = await provider.connection.getTokenAccountBalance(
+      getLpTokenAddress(pool, user.publicKey)
+    );
+    await withdrawLiquidity(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      new anchor.BN(lpBalance.value.amount)
+    );
+
+    await expectError(unlockPool(), 'PoolNotStuck');
+  });
+});
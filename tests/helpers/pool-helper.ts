@@ -0,0 +1,951 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../../target/types/token2022_amm';
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from '@solana/spl-token';
+import {
+  Connection,
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  LAMPORTS_PER_SOL,
+} from '@solana/web3.js';
+import { assert } from 'chai';
+
+import {
+  setupTestTokens,
+  createUserTokenAccounts,
+  fundUsers,
+  getTokenAddress,
+  TokenInfo,
+  UserTokenAccounts,
+} from './token-helper';
+
+export const MAX_WHITE_LIST_LP = 10;
+
+export interface PoolAccounts {
+  seed: anchor.BN;
+  config: PublicKey;
+  poolState: PublicKey;
+  poolAuthority: PublicKey;
+  vaultAuthority: PublicKey;
+  lpMint: PublicKey;
+  vaultX: PublicKey;
+  vaultY: PublicKey;
+  tokenX: TokenInfo;
+  tokenY: TokenInfo;
+}
+
+export interface DepositOptions {
+  minLpOut?: anchor.BN;
+  maxPriceDeviationBps?: number | null;
+  expectedLp?: anchor.BN;
+  expectedReserveRatio?: {
+    reserveX: anchor.BN;
+    reserveY: anchor.BN;
+    maxReserveRatioDeviationBps: number;
+  };
+  registry?: PublicKey;
+  wrapSol?: boolean;
+}
+
+export interface SwapOptions {
+  minAmountOut?: anchor.BN;
+  expectedFee?: number;
+  expectedReserveX?: anchor.BN;
+  expectedReserveY?: anchor.BN;
+  reserveToleranceBps?: number;
+  expectedEpoch?: anchor.BN;
+  userLpToken?: PublicKey;
+  registry?: PublicKey;
+}
+
+// Swap guards that check nothing, for swaps built by hand
+export const NO_SWAP_GUARDS = {
+  expectedFee: null,
+  expectedReserveX: null,
+  expectedReserveY: null,
+  reserveToleranceBps: 0,
+  expectedEpoch: null,
+};
+
+// Deposit guards that check nothing, for deposits built by hand
+export const NO_DEPOSIT_GUARDS = {
+  maxPriceDeviationBps: null,
+  expectedLp: null,
+  expectedReserveRatio: null,
+};
+
+export interface UpdateConfigOptions {
+  fee?: number;
+  minReserve?: anchor.BN;
+  maxSwapRatioBps?: number;
+  imbalanceFeeBps?: number;
+  lpRounding?: { roundDown: {} } | { roundToNearest: {} };
+  lpDiscountThreshold?: anchor.BN;
+  lpDiscountBps?: number;
+  protocolFeeBps?: number;
+  protocolFeeMinAmount?: anchor.BN;
+  launchFee?: number;
+  targetFee?: number;
+  decayDuration?: anchor.BN;
+  privilegedMm?: PublicKey;
+  mmFeeBps?: number;
+  vaultCoverageGrace?: anchor.BN;
+  maxLpPerUser?: anchor.BN;
+  minEffectiveFeeBps?: number;
+  feeWithdrawalDelay?: anchor.BN;
+  circuitBreakerBps?: number;
+}
+
+export interface WithdrawOptions {
+  minAmountX?: anchor.BN;
+  minAmountY?: anchor.BN;
+  closeLpAccount?: boolean;
+  registry?: PublicKey;
+  unwrapSol?: boolean;
+}
+
+/**
+ * Airdrops SOL to the given accounts and waits for confirmation
+ */
+export async function airdrop(
+  connection: Connection,
+  accounts: PublicKey[],
+  amount: number = 10 * LAMPORTS_PER_SOL
+): Promise<void> {
+  await Promise.all(
+    accounts.map((account) => connection.requestAirdrop(account, amount))
+  );
+
+  // Wait for airdrops to confirm
+  await new Promise((resolve) => setTimeout(resolve, 2000));
+}
+
+/**
+ * Derives every PDA and vault address of a pool from its seed
+ */
+export function derivePoolAccounts(
+  programId: PublicKey,
+  seed: anchor.BN,
+  tokenX: TokenInfo,
+  tokenY: TokenInfo,
+  separateVaultAuthority = false
+): PoolAccounts {
+  const [config] = PublicKey.findProgramAddressSync(
+    [Buffer.from('config'), seed.toArrayLike(Buffer, 'le', 8)],
+    programId
+  );
+  const [poolState] = PublicKey.findProgramAddressSync(
+    [Buffer.from('pool'), config.toBuffer()],
+    programId
+  );
+  const [poolAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from('auth'), config.toBuffer()],
+    programId
+  );
+  const [lpMint] = PublicKey.findProgramAddressSync(
+    [Buffer.from('lp_mint'), config.toBuffer()],
+    programId
+  );
+  const vaultAuthority = separateVaultAuthority
+    ? PublicKey.findProgramAddressSync(
+        [Buffer.from('vault_auth'), config.toBuffer()],
+        programId
+      )[0]
+    : poolAuthority;
+
+  return {
+    seed,
+    config,
+    poolState,
+    poolAuthority,
+    vaultAuthority,
+    lpMint,
+    vaultX: getTokenAddress(
+      tokenX.mint,
+      vaultAuthority,
+      tokenX.tokenProgram,
+      true
+    ),
+    vaultY: getTokenAddress(
+      tokenY.mint,
+      vaultAuthority,
+      tokenY.tokenProgram,
+      true
+    ),
+    tokenX,
+    tokenY,
+  };
+}
+
+/**
+ * Returns the user's LP token account for a pool
+ */
+export function getLpTokenAddress(
+  pool: PoolAccounts,
+  user: PublicKey
+): PublicKey {
+  return getTokenAddress(pool.lpMint, user, TOKEN_PROGRAM_ID);
+}
+
+/**
+ * Initializes a pool for the given token pair with a random seed, optionally with its
+ * vaults owned by a vault authority PDA separate from the pool authority. By default
+ * both mints may carry a transfer fee of any size. With `indexMints` the pool is listed
+ * in the discovery index of both mints
+ */
+export async function initializePool(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  tokenX: TokenInfo,
+  tokenY: TokenInfo,
+  fee: number,
+  whiteListLp: PublicKey[] | null = null,
+  separateVaultAuthority = false,
+  maxFeeBearingSides = 2,
+  indexMints = false,
+  maxTransferFeeBps = 10000
+): Promise<PoolAccounts> {
+  const seed = new anchor.BN(Math.floor(Math.random() * 1000000000));
+  const pool = derivePoolAccounts(
+    program.programId,
+    seed,
+    tokenX,
+    tokenY,
+    separateVaultAuthority
+  );
+
+  await program.methods
+    .initializePool(
+      seed,
+      fee,
+      whiteListLp,
+      separateVaultAuthority,
+      maxFeeBearingSides,
+      maxTransferFeeBps
+    )
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      mintX: tokenX.mint,
+      mintY: tokenY.mint,
+      lpMint: pool.lpMint,
+      poolAuthority: pool.poolAuthority,
+      vaultAuthority: pool.vaultAuthority,
+      vaultX: pool.vaultX,
+      vaultY: pool.vaultY,
+      mintXPoolIndex: indexMints
+        ? deriveMintPoolIndex(program.programId, tokenX.mint)
+        : null,
+      mintYPoolIndex: indexMints
+        ? deriveMintPoolIndex(program.programId, tokenY.mint)
+        : null,
+      tokenProgramX: tokenX.tokenProgram,
+      tokenProgramY: tokenY.tokenProgram,
+      tokenProgramLp: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([authority])
+    .rpc();
+
+  return pool;
+}
+
+/**
+ * Derives the index listing the pools created for a mint with indexing requested
+ */
+export function deriveMintPoolIndex(
+  programId: PublicKey,
+  mint: PublicKey
+): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('mint_pool_index'), mint.toBuffer()],
+    programId
+  )[0];
+}
+
+/**
+ * Initializes a pool whose vaults are token accounts at PDAs seeded by config + mint
+ * rather than associated token accounts of the pool authority. Transfer fee caps and
+ * mint indexing work as in `initializePool`
+ */
+export async function initializePoolWithPdaVaults(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  tokenX: TokenInfo,
+  tokenY: TokenInfo,
+  fee: number,
+  maxFeeBearingSides = 2,
+  indexMints = false,
+  maxTransferFeeBps = 10000
+): Promise<PoolAccounts> {
+  const seed = new anchor.BN(Math.floor(Math.random() * 1000000000));
+  const pool = derivePoolAccounts(program.programId, seed, tokenX, tokenY);
+  [pool.vaultX] = PublicKey.findProgramAddressSync(
+    [Buffer.from('vault'), pool.config.toBuffer(), tokenX.mint.toBuffer()],
+    program.programId
+  );
+  [pool.vaultY] = PublicKey.findProgramAddressSync(
+    [Buffer.from('vault'), pool.config.toBuffer(), tokenY.mint.toBuffer()],
+    program.programId
+  );
+
+  await program.methods
+    .initializePoolWithPdaVaults(
+      seed,
+      fee,
+      null,
+      maxFeeBearingSides,
+      maxTransferFeeBps
+    )
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      mintX: tokenX.mint,
+      mintY: tokenY.mint,
+      lpMint: pool.lpMint,
+      poolAuthority: pool.poolAuthority,
+      vaultX: pool.vaultX,
+      vaultY: pool.vaultY,
+      mintXPoolIndex: indexMints
+        ? deriveMintPoolIndex(program.programId, tokenX.mint)
+        : null,
+      mintYPoolIndex: indexMints
+        ? deriveMintPoolIndex(program.programId, tokenY.mint)
+        : null,
+      tokenProgramX: tokenX.tokenProgram,
+      tokenProgramY: tokenY.tokenProgram,
+      tokenProgramLp: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([authority])
+    .rpc();
+
+  return pool;
+}
+
+/**
+ * Creates a token pair, funds the users and initializes a pool for it
+ */
+export async function setupPool(
+  connection: Connection,
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  users: PublicKey[],
+  tokenXType: 'legacy' | 'token2022',
+  tokenYType: 'legacy' | 'token2022',
+  fee: number,
+  whiteListLp: PublicKey[] | null = null
+): Promise<{
+  pool: PoolAccounts;
+  userAccounts: Map<string, UserTokenAccounts>;
+}> {
+  const { tokenX, tokenY, userAccounts } = await setupTestTokens(
+    connection,
+    authority,
+    users,
+    tokenXType,
+    tokenYType
+  );
+
+  await fundUsers(connection, authority, tokenX, tokenY, userAccounts);
+
+  const pool = await initializePool(
+    program,
+    authority,
+    tokenX,
+    tokenY,
+    fee,
+    whiteListLp
+  );
+
+  return { pool, userAccounts };
+}
+
+/**
+ * Creates user accounts for already existing tokens, funds them and initializes a pool
+ */
+export async function setupPoolWithTokens(
+  connection: Connection,
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  users: PublicKey[],
+  tokenX: TokenInfo,
+  tokenY: TokenInfo,
+  fee: number
+): Promise<{
+  pool: PoolAccounts;
+  userAccounts: Map<string, UserTokenAccounts>;
+}> {
+  const userAccounts = new Map<string, UserTokenAccounts>();
+  for (const user of users) {
+    const accounts = await createUserTokenAccounts(
+      connection,
+      authority,
+      user,
+      tokenX,
+      tokenY
+    );
+    userAccounts.set(user.toString(), accounts);
+  }
+
+  await fundUsers(connection, authority, tokenX, tokenY, userAccounts);
+
+  const pool = await initializePool(program, authority, tokenX, tokenY, fee);
+
+  return { pool, userAccounts };
+}
+
+/**
+ * Deposits liquidity into a pool
+ */
+export async function depositLiquidity(
+  program: Program<Token2022Amm>,
+  user: Keypair,
+  pool: PoolAccounts,
+  userAccounts: UserTokenAccounts,
+  amountX: number | anchor.BN,
+  amountY: number | anchor.BN,
+  options: DepositOptions = {}
+): Promise<string> {
+  return program.methods
+    .deposit(
+      new anchor.BN(amountX),
+      new anchor.BN(amountY),
+      options.minLpOut ?? new anchor.BN(1),
+      {
+        maxPriceDeviationBps: options.maxPriceDeviationBps ?? null,
+        expectedLp: options.expectedLp ?? null,
+        expectedReserveRatio: options.expectedReserveRatio ?? null,
+      },
+      options.wrapSol ?? false
+    )
+    .accountsPartial({
+      user: user.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      poolAuthority: pool.poolAuthority,
+      vaultAuthority: pool.vaultAuthority,
+      mintX: pool.tokenX.mint,
+      mintY: pool.tokenY.mint,
+      vaultX: pool.vaultX,
+      vaultY: pool.vaultY,
+      userTokenX: userAccounts.tokenX,
+      userTokenY: userAccounts.tokenY,
+      lpMint: pool.lpMint,
+      userLpToken: getLpTokenAddress(pool, user.publicKey),
+      registry: options.registry ?? null,
+      tokenProgramX: pool.tokenX.tokenProgram,
+      tokenProgramY: pool.tokenY.tokenProgram,
+      tokenProgramLp: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([user])
+    .rpc();
+}
+
+/**
+ * Deposits both tokens at any ratio, swapping the excess side against the pool first
+ */
+export async function depositAndBalance(
+  program: Program<Token2022Amm>,
+  user: Keypair,
+  pool: PoolAccounts,
+  userAccounts: UserTokenAccounts,
+  amountX: number | anchor.BN,
+  amountY: number | anchor.BN,
+  minLpOut: anchor.BN = new anchor.BN(1)
+): Promise<string> {
+  return program.methods
+    .depositAndBalance(
+      new anchor.BN(amountX),
+      new anchor.BN(amountY),
+      minLpOut
+    )
+    .accountsPartial({
+      user: user.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      poolAuthority: pool.poolAuthority,
+      vaultAuthority: pool.vaultAuthority,
+      mintX: pool.tokenX.mint,
+      mintY: pool.tokenY.mint,
+      vaultX: pool.vaultX,
+      vaultY: pool.vaultY,
+      userTokenX: userAccounts.tokenX,
+      userTokenY: userAccounts.tokenY,
+      lpMint: pool.lpMint,
+      userLpToken: getLpTokenAddress(pool, user.publicKey),
+      registry: null,
+      tokenProgramX: pool.tokenX.tokenProgram,
+      tokenProgramY: pool.tokenY.tokenProgram,
+      tokenProgramLp: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([user])
+    .rpc();
+}
+
+/**
+ * Swaps through a pool, X -> Y when `xToY` is set, Y -> X otherwise
+ */
+export async function swapTokens(
+  program: Program<Token2022Amm>,
+  user: Keypair,
+  pool: PoolAccounts,
+  userAccounts: UserTokenAccounts,
+  xToY: boolean,
+  amountIn: number | anchor.BN,
+  options: SwapOptions = {}
+): Promise<string> {
+  const [tokenIn, tokenOut] = xToY
+    ? [pool.tokenX, pool.tokenY]
+    : [pool.tokenY, pool.tokenX];
+
+  return program.methods
+    .swap(
+      new anchor.BN(amountIn),
+      options.minAmountOut ?? new anchor.BN(1),
+      {
+        expectedFee: options.expectedFee ?? null,
+        expectedReserveX: options.expectedReserveX ?? null,
+        expectedReserveY: options.expectedReserveY ?? null,
+        reserveToleranceBps: options.reserveToleranceBps ?? 0,
+        expectedEpoch: options.expectedEpoch ?? null,
+      }
+    )
+    .accountsPartial({
+      user: user.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      poolAuthority: pool.poolAuthority,
+      vaultAuthority: pool.vaultAuthority,
+      mintIn: tokenIn.mint,
+      mintOut: tokenOut.mint,
+      vaultIn: xToY ? pool.vaultX : pool.vaultY,
+      vaultOut: xToY ? pool.vaultY : pool.vaultX,
+      userTokenIn: xToY ? userAccounts.tokenX : userAccounts.tokenY,
+      userTokenOut: xToY ? userAccounts.tokenY : userAccounts.tokenX,
+      userLpToken: options.userLpToken ?? null,
+      registry: options.registry ?? null,
+      tokenProgramX: pool.tokenX.tokenProgram,
+      tokenProgramY: pool.tokenY.tokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([user])
+    .rpc();
+}
+
+/**
+ * Withdraws liquidity from a pool by burning LP tokens
+ */
+export async function withdrawLiquidity(
+  program: Program<Token2022Amm>,
+  user: Keypair,
+  pool: PoolAccounts,
+  userAccounts: UserTokenAccounts,
+  lpAmount: number | anchor.BN,
+  options: WithdrawOptions = {}
+): Promise<string> {
+  return program.methods
+    .withdraw(
+      new anchor.BN(lpAmount),
+      options.minAmountX ?? new anchor.BN(0),
+      options.minAmountY ?? new anchor.BN(0),
+      options.closeLpAccount ?? false,
+      options.unwrapSol ?? false
+    )
+    .accountsPartial({
+      user: user.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      poolAuthority: pool.poolAuthority,
+      vaultAuthority: pool.vaultAuthority,
+      mintX: pool.tokenX.mint,
+      mintY: pool.tokenY.mint,
+      vaultX: pool.vaultX,
+      vaultY: pool.vaultY,
+      userTokenX: userAccounts.tokenX,
+      userTokenY: userAccounts.tokenY,
+      lpMint: pool.lpMint,
+      userLpToken: getLpTokenAddress(pool, user.publicKey),
+      registry: options.registry ?? null,
+      tokenProgramX: pool.tokenX.tokenProgram,
+      tokenProgramY: pool.tokenY.tokenProgram,
+      tokenProgramLp: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([user])
+    .rpc();
+}
+
+/**
+ * Withdraws liquidity, splitting each token between the user and a recipient.
+ * Splits are [user, recipient] shares in bps and must sum to 10000
+ */
+export async function withdrawSplit(
+  program: Program<Token2022Amm>,
+  user: Keypair,
+  pool: PoolAccounts,
+  userAccounts: UserTokenAccounts,
+  lpAmount: number | anchor.BN,
+  recipientX: PublicKey,
+  recipientY: PublicKey,
+  splitXBps: [number, number],
+  splitYBps: [number, number]
+): Promise<string> {
+  return program.methods
+    .withdrawSplit(
+      new anchor.BN(lpAmount),
+      new anchor.BN(0),
+      new anchor.BN(0),
+      splitXBps,
+      splitYBps
+    )
+    .accountsPartial({
+      withdraw: {
+        user: user.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        poolAuthority: pool.poolAuthority,
+        vaultAuthority: pool.vaultAuthority,
+        mintX: pool.tokenX.mint,
+        mintY: pool.tokenY.mint,
+        vaultX: pool.vaultX,
+        vaultY: pool.vaultY,
+        userTokenX: userAccounts.tokenX,
+        userTokenY: userAccounts.tokenY,
+        lpMint: pool.lpMint,
+        userLpToken: getLpTokenAddress(pool, user.publicKey),
+        registry: null,
+        tokenProgramX: pool.tokenX.tokenProgram,
+        tokenProgramY: pool.tokenY.tokenProgram,
+        tokenProgramLp: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
+      recipientX,
+      recipientY,
+    })
+    .signers([user])
+    .rpc();
+}
+
+/**
+ * Updates pool configuration as the pool authority; unset options are left unchanged
+ */
+export async function updateConfig(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts,
+  options: UpdateConfigOptions
+): Promise<string> {
+  return program.methods
+    .updateConfig({
+      fee: options.fee ?? null,
+      minReserve: options.minReserve ?? null,
+      maxSwapRatioBps: options.maxSwapRatioBps ?? null,
+      imbalanceFeeBps: options.imbalanceFeeBps ?? null,
+      lpRounding: options.lpRounding ?? null,
+      lpDiscountThreshold: options.lpDiscountThreshold ?? null,
+      lpDiscountBps: options.lpDiscountBps ?? null,
+      protocolFeeBps: options.protocolFeeBps ?? null,
+      protocolFeeMinAmount: options.protocolFeeMinAmount ?? null,
+      launchFee: options.launchFee ?? null,
+      targetFee: options.targetFee ?? null,
+      decayDuration: options.decayDuration ?? null,
+      privilegedMm: options.privilegedMm ?? null,
+      mmFeeBps: options.mmFeeBps ?? null,
+      vaultCoverageGrace: options.vaultCoverageGrace ?? null,
+      maxLpPerUser: options.maxLpPerUser ?? null,
+      minEffectiveFeeBps: options.minEffectiveFeeBps ?? null,
+      feeWithdrawalDelay: options.feeWithdrawalDelay ?? null,
+      circuitBreakerBps: options.circuitBreakerBps ?? null,
+    })
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Queues a withdrawal of accrued protocol fees, executable after the pool's delay
+ */
+export async function queueFeeWithdrawal(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts,
+  amountX: number | anchor.BN,
+  amountY: number | anchor.BN
+): Promise<string> {
+  return program.methods
+    .queueFeeWithdrawal(new anchor.BN(amountX), new anchor.BN(amountY))
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Moves the queued protocol fees out of the vaults to the recipient's associated token
+ * accounts, which the program creates if they do not exist yet
+ */
+export async function executeFeeWithdrawal(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts,
+  recipient: PublicKey
+): Promise<string> {
+  return program.methods
+    .executeFeeWithdrawal()
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      vaultAuthority: pool.vaultAuthority,
+      mintX: pool.tokenX.mint,
+      mintY: pool.tokenY.mint,
+      vaultX: pool.vaultX,
+      vaultY: pool.vaultY,
+      recipient,
+      recipientX: getTokenAddress(
+        pool.tokenX.mint,
+        recipient,
+        pool.tokenX.tokenProgram
+      ),
+      recipientY: getTokenAddress(
+        pool.tokenY.mint,
+        recipient,
+        pool.tokenY.tokenProgram
+      ),
+      tokenProgramX: pool.tokenX.tokenProgram,
+      tokenProgramY: pool.tokenY.tokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Sweeps the unqueued protocol fees of several pools to the authority's token
+ * accounts in one transaction
+ */
+export async function withdrawProtocolFeesBatch(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pools: PoolAccounts[]
+): Promise<string> {
+  const meta = (pubkey: PublicKey, isWritable = false) => ({
+    pubkey,
+    isSigner: false,
+    isWritable,
+  });
+
+  return program.methods
+    .withdrawProtocolFeesBatch()
+    .accountsPartial({ authority: authority.publicKey })
+    .remainingAccounts(
+      pools.flatMap((pool) => [
+        meta(pool.config),
+        meta(pool.poolState, true),
+        meta(pool.vaultAuthority),
+        meta(pool.tokenX.mint),
+        meta(pool.tokenY.mint),
+        meta(pool.vaultX, true),
+        meta(pool.vaultY, true),
+        meta(
+          getTokenAddress(
+            pool.tokenX.mint,
+            authority.publicKey,
+            pool.tokenX.tokenProgram
+          ),
+          true
+        ),
+        meta(
+          getTokenAddress(
+            pool.tokenY.mint,
+            authority.publicKey,
+            pool.tokenY.tokenProgram
+          ),
+          true
+        ),
+        meta(pool.tokenX.tokenProgram),
+        meta(pool.tokenY.tokenProgram),
+      ])
+    )
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Reinvests the ratio-matched part of the accrued protocol fees as LP owned by the authority
+ */
+export async function compoundProtocolFees(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts
+): Promise<string> {
+  return program.methods
+    .compoundProtocolFees()
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+      poolState: pool.poolState,
+      poolAuthority: pool.poolAuthority,
+      lpMint: pool.lpMint,
+      authorityLpToken: getLpTokenAddress(pool, authority.publicKey),
+      tokenProgramLp: TOKEN_PROGRAM_ID,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Toggles whitelist enforcement on a pool without touching the stored entries
+ */
+export async function setWhitelistEnabled(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts,
+  enabled: boolean
+): Promise<string> {
+  return program.methods
+    .setWhitelistEnabled(enabled)
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Reallocs the config so the whitelist has room for `newCapacity` entries
+ */
+export async function resizeWhitelist(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts,
+  newCapacity: number
+): Promise<string> {
+  return program.methods
+    .resizeWhitelist(newCapacity)
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Adds (or removes, when `add` is false) a user from the pool whitelist
+ */
+export async function updateWhitelist(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  pool: PoolAccounts,
+  user: PublicKey,
+  add: boolean = true
+): Promise<string> {
+  const method = add
+    ? program.methods.addToWhitelist(user)
+    : program.methods.removeFromWhitelist(user);
+
+  return method
+    .accountsPartial({
+      authority: authority.publicKey,
+      config: pool.config,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Derives the registry holding the pause switch shared by every pool of an authority
+ */
+export function deriveRegistry(
+  programId: PublicKey,
+  authority: PublicKey
+): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('registry'), authority.toBuffer()],
+    programId
+  )[0];
+}
+
+/**
+ * Pauses (or unpauses, when `paused` is false) every pool of the authority at once
+ */
+export async function setGlobalPause(
+  program: Program<Token2022Amm>,
+  authority: Keypair,
+  paused: boolean
+): Promise<string> {
+  const method = paused
+    ? program.methods.pauseAll()
+    : program.methods.unpauseAll();
+
+  return method
+    .accountsPartial({
+      authority: authority.publicKey,
+      registry: deriveRegistry(program.programId, authority.publicKey),
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([authority])
+    .rpc();
+}
+
+/**
+ * Asserts that the given transaction fails with the expected program error code
+ */
+export async function expectError(
+  promise: Promise<unknown>,
+  errorCode: string
+): Promise<void> {
+  let error: any = null;
+  try {
+    await promise;
+  } catch (e) {
+    error = e;
+  }
+
+  assert.isNotNull(error, `Expected transaction to fail with ${errorCode}`);
+  const message = `${error} ${(error.logs ?? []).join('\n')}`;
+  assert.include(message, errorCode);
+}
+
+/**
+ * Returns the events a confirmed transaction emitted, parsed from its logs
+ */
+export async function getEvents(
+  program: Program<Token2022Amm>,
+  signature: string
+): Promise<anchor.Event[]> {
+  const connection = program.provider.connection;
+  await connection.confirmTransaction(signature, 'confirmed');
+
+  const tx = await connection.getTransaction(signature, {
+    commitment: 'confirmed',
+    maxSupportedTransactionVersion: 0,
+  });
+  const parser = new anchor.EventParser(program.programId, program.coder);
+
+  return Array.from(parser.parseLogs(tx?.meta?.logMessages ?? []));
+}
@@ -17,6 +17,10 @@ import {
   ExtensionType,
   createInitializeMintInstruction,
   createInitializeTransferFeeConfigInstruction,
+  createInitializeTransferHookInstruction,
+  createInitializeMintCloseAuthorityInstruction,
+  createInitializeNonTransferableMintInstruction,
+  createInitializePermanentDelegateInstruction,
   getAssociatedTokenAddressSync,
 } from '@solana/spl-token';
 
@@ -42,13 +46,14 @@ export async function createLegacyToken(
   connection: Connection,
   payer: Keypair,
   mintAuthority: PublicKey,
-  decimals: number = 6
+  decimals: number = 6,
+  freezeAuthority: PublicKey | null = null
 ): Promise<TokenInfo> {
   const mint = await createMint(
     connection,
     payer,
     mintAuthority,
-    null,
+    freezeAuthority,
     decimals,
     undefined,
     undefined,
@@ -135,6 +140,130 @@ export async function createToken2022WithTransferFee(
   };
 }
 
+/**
+ * Creates a Token-2022 with TransferHook extension (not supported by the AMM)
+ */
+export async function createToken2022WithTransferHook(
+  connection: Connection,
+  payer: Keypair,
+  mintAuthority: PublicKey,
+  transferHookProgramId: PublicKey,
+  decimals: number = 6
+): Promise<TokenInfo> {
+  const mintKeypair = Keypair.generate();
+  const mint = mintKeypair.publicKey;
+
+  const extensions = [ExtensionType.TransferHook];
+  const mintLen = getMintLen(extensions);
+  const lamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+
+  const transaction = new Transaction().add(
+    SystemProgram.createAccount({
+      fromPubkey: payer.publicKey,
+      newAccountPubkey: mint,
+      space: mintLen,
+      lamports,
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    createInitializeTransferHookInstruction(
+      mint,
+      mintAuthority,
+      transferHookProgramId,
+      TOKEN_2022_PROGRAM_ID
+    ),
+    createInitializeMintInstruction(
+      mint,
+      decimals,
+      mintAuthority,
+      null,
+      TOKEN_2022_PROGRAM_ID
+    )
+  );
+
+  await sendAndConfirmTransaction(connection, transaction, [
+    payer,
+    mintKeypair,
+  ]);
+
+  return {
+    mint,
+    decimals,
+    tokenProgram: TOKEN_2022_PROGRAM_ID,
+    hasTransferFee: false,
+  };
+}
+
+/**
+ * Creates a Token-2022 mint carrying a single extension the program does not support,
+ * configured with `mintAuthority` where the extension takes an authority
+ */
+export async function createToken2022WithUnsupportedExtension(
+  connection: Connection,
+  payer: Keypair,
+  mintAuthority: PublicKey,
+  extension:
+    | ExtensionType.PermanentDelegate
+    | ExtensionType.NonTransferable
+    | ExtensionType.MintCloseAuthority,
+  decimals: number = 6
+): Promise<TokenInfo> {
+  const mintKeypair = Keypair.generate();
+  const mint = mintKeypair.publicKey;
+
+  const mintLen = getMintLen([extension]);
+  const lamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+
+  const initializeExtension = {
+    [ExtensionType.PermanentDelegate]: () =>
+      createInitializePermanentDelegateInstruction(
+        mint,
+        mintAuthority,
+        TOKEN_2022_PROGRAM_ID
+      ),
+    [ExtensionType.NonTransferable]: () =>
+      createInitializeNonTransferableMintInstruction(
+        mint,
+        TOKEN_2022_PROGRAM_ID
+      ),
+    [ExtensionType.MintCloseAuthority]: () =>
+      createInitializeMintCloseAuthorityInstruction(
+        mint,
+        mintAuthority,
+        TOKEN_2022_PROGRAM_ID
+      ),
+  }[extension];
+
+  const transaction = new Transaction().add(
+    SystemProgram.createAccount({
+      fromPubkey: payer.publicKey,
+      newAccountPubkey: mint,
+      space: mintLen,
+      lamports,
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    initializeExtension(),
+    createInitializeMintInstruction(
+      mint,
+      decimals,
+      mintAuthority,
+      null,
+      TOKEN_2022_PROGRAM_ID
+    )
+  );
+
+  await sendAndConfirmTransaction(connection, transaction, [
+    payer,
+    mintKeypair,
+  ]);
+
+  return {
+    mint,
+    decimals,
+    tokenProgram: TOKEN_2022_PROGRAM_ID,
+    hasTransferFee: false,
+  };
+}
+
 /**
  * Creates user token accounts for a given mint
  */
@@ -0,0 +1,628 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import {
+  getAccount,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+} from '@solana/spl-token';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { getTokenBalance } from './helpers/balance-helper';
+import {
+  createLegacyToken,
+  createToken2022WithTransferFee,
+  fundUsers,
+  setupTestTokens,
+  UserTokenAccounts,
+} from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  deriveMintPoolIndex,
+  derivePoolAccounts,
+  expectError,
+  initializePool,
+  initializePoolWithPdaVaults,
+  setupPool,
+  swapTokens,
+  updateConfig,
+  withdrawLiquidity,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Pool Initialization Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+  });
+
+  describe('PDA vaults', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      const tokens = await setupTestTokens(
+        provider.connection,
+        authority,
+        [user.publicKey],
+        'token2022',
+        'legacy'
+      );
+      userAccounts = tokens.userAccounts;
+      await fundUsers(
+        provider.connection,
+        authority,
+        tokens.tokenX,
+        tokens.tokenY,
+        userAccounts
+      );
+
+      pool = await initializePoolWithPdaVaults(
+        program,
+        authority,
+        tokens.tokenX,
+        tokens.tokenY,
+        fee
+      );
+    });
+
+    it('Should store the PDA vaults on the pool state', async () => {
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      assert.isTrue(poolData.vaultX.equals(pool.vaultX));
+      assert.isTrue(poolData.vaultY.equals(pool.vaultY));
+
+      const vaultX = await getAccount(
+        provider.connection,
+        pool.vaultX,
+        undefined,
+        pool.tokenX.tokenProgram
+      );
+      assert.isTrue(vaultX.owner.equals(pool.poolAuthority));
+      assert.isTrue(vaultX.mint.equals(pool.tokenX.mint));
+    });
+
+    it('Should deposit, swap and withdraw against PDA vaults', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+      await swapTokens(program, user, pool, accounts, true, 10 * 10 ** 6);
+
+      const vaultY = await getTokenBalance(
+        provider.connection,
+        pool.vaultY,
+        pool.tokenY.tokenProgram
+      );
+      assert.isTrue(vaultY.balance < BigInt(100 * 10 ** 6));
+
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      await withdrawLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        poolBefore.lpSupply.divn(2)
+      );
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.isTrue(poolAfter.lpSupply.lt(poolBefore.lpSupply));
+      assert.isTrue(poolAfter.reserveX.lt(poolBefore.reserveX));
+    });
+
+    it('Should apply the transfer fee caps like a regular pool init', async () => {
+      // Token X carries a 1% transfer fee
+      await expectError(
+        initializePoolWithPdaVaults(
+          program,
+          authority,
+          pool.tokenX,
+          pool.tokenY,
+          fee,
+          0
+        ),
+        'TooManyFeeBearingMints'
+      );
+      await expectError(
+        initializePoolWithPdaVaults(
+          program,
+          authority,
+          pool.tokenX,
+          pool.tokenY,
+          fee,
+          2,
+          false,
+          50
+        ),
+        'TransferFeeTooHigh'
+      );
+    });
+
+    it('Should list the pool in the mint indexes when asked to', async () => {
+      const indexed = await initializePoolWithPdaVaults(
+        program,
+        authority,
+        pool.tokenX,
+        pool.tokenY,
+        fee,
+        2,
+        true
+      );
+
+      const index = await program.account.mintPoolIndex.fetch(
+        deriveMintPoolIndex(program.programId, pool.tokenY.mint)
+      );
+      assert.deepEqual(
+        index.pools.map((listed) => listed.toString()),
+        [indexed.config.toString()]
+      );
+    });
+  });
+
+  describe('Separate vault authority', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      const tokens = await setupTestTokens(
+        provider.connection,
+        authority,
+        [user.publicKey],
+        'token2022',
+        'legacy'
+      );
+      userAccounts = tokens.userAccounts;
+      await fundUsers(
+        provider.connection,
+        authority,
+        tokens.tokenX,
+        tokens.tokenY,
+        userAccounts
+      );
+
+      pool = await initializePool(
+        program,
+        authority,
+        tokens.tokenX,
+        tokens.tokenY,
+        fee,
+        null,
+        true
+      );
+    });
+
+    it('Should own the vaults by the vault authority, not the pool authority', async () => {
+      assert.isFalse(pool.vaultAuthority.equals(pool.poolAuthority));
+
+      const configData = await program.account.config.fetch(pool.config);
+      assert.isTrue(configData.separateVaultAuthority);
+      assert.isTrue(configData.vaultAuthority.equals(pool.vaultAuthority));
+
+      const vaultX = await getAccount(
+        provider.connection,
+        pool.vaultX,
+        undefined,
+        pool.tokenX.tokenProgram
+      );
+      assert.isTrue(vaultX.owner.equals(pool.vaultAuthority));
+    });
+
+    it('Should deposit, swap and withdraw with a distinct vault authority', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+      await swapTokens(program, user, pool, accounts, true, 10 * 10 ** 6);
+
+      const vaultY = await getTokenBalance(
+        provider.connection,
+        pool.vaultY,
+        pool.tokenY.tokenProgram
+      );
+      assert.isTrue(vaultY.balance < BigInt(100 * 10 ** 6));
+
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      await withdrawLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        poolBefore.lpSupply.divn(2)
+      );
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.isTrue(poolAfter.lpSupply.lt(poolBefore.lpSupply));
+      assert.isTrue(poolAfter.reserveX.lt(poolBefore.reserveX));
+    });
+  });
+
+  describe('Fee-bearing side allowance', () => {
+    // Token-2022 test mints carry a transfer fee, legacy ones don't
+    const pairs = [
+      { feeBearingSides: 0, types: ['legacy', 'legacy'] },
+      { feeBearingSides: 1, types: ['token2022', 'legacy'] },
+      { feeBearingSides: 2, types: ['token2022', 'token2022'] },
+    ] as const;
+
+    for (const { feeBearingSides, types } of pairs) {
+      for (const allowance of [0, 1, 2]) {
+        const allowed = feeBearingSides <= allowance;
+
+        it(`Should ${allowed ? 'create' : 'reject'} a pool with ${feeBearingSides} fee-bearing mints when ${allowance} are allowed`, async () => {
+          const { tokenX, tokenY } = await setupTestTokens(
+            provider.connection,
+            authority,
+            [],
+            types[0],
+            types[1]
+          );
+          const init = initializePool(
+            program,
+            authority,
+            tokenX,
+            tokenY,
+            fee,
+            null,
+            false,
+            allowance
+          );
+
+          if (allowed) {
+            const pool = await init;
+            const configData = await program.account.config.fetch(pool.config);
+            assert.isTrue(configData.mintX.equals(tokenX.mint));
+          } else {
+            await expectError(init, 'TooManyFeeBearingMints');
+          }
+        });
+      }
+    }
+
+    it('Should reject an allowance above two sides', async () => {
+      const { tokenX, tokenY } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'legacy'
+      );
+
+      await expectError(
+        initializePool(program, authority, tokenX, tokenY, fee, null, false, 3),
+        'InvalidAmount'
+      );
+    });
+  });
+
+  describe('Transfer fee cap', () => {
+    // createToken2022WithTransferFee defaults to a 1% fee
+    const transferFeeBps = 100;
+
+    for (const cap of [transferFeeBps - 1, transferFeeBps]) {
+      const allowed = transferFeeBps <= cap;
+
+      it(`Should ${allowed ? 'create' : 'reject'} a pool with a ${transferFeeBps} bps transfer fee under a ${cap} bps cap`, async () => {
+        const tokenX = await createToken2022WithTransferFee(
+          provider.connection,
+          authority,
+          authority.publicKey,
+          authority.publicKey,
+          authority.publicKey,
+          transferFeeBps
+        );
+        const tokenY = await createLegacyToken(
+          provider.connection,
+          authority,
+          authority.publicKey
+        );
+        const init = initializePool(
+          program,
+          authority,
+          tokenX,
+          tokenY,
+          fee,
+          null,
+          false,
+          2,
+          false,
+          cap
+        );
+
+        if (allowed) {
+          const pool = await init;
+          const configData = await program.account.config.fetch(pool.config);
+          assert.isTrue(configData.mintX.equals(tokenX.mint));
+        } else {
+          await expectError(init, 'TransferFeeTooHigh');
+        }
+      });
+    }
+  });
+
+  describe('Creation timestamp', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+    let createdAt: anchor.BN;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+    });
+
+    it('Should record the creation time on the config', async () => {
+      createdAt = (await program.account.config.fetch(pool.config)).createdAt;
+
+      const now = Math.floor(Date.now() / 1000);
+      assert.approximately(createdAt.toNumber(), now, 300);
+    });
+
+    it('Should keep the creation time through later activity', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+      await swapTokens(program, user, pool, accounts, true, 10 * 10 ** 6);
+      await updateConfig(program, authority, pool, { fee: 100 });
+
+      const configData = await program.account.config.fetch(pool.config);
+      assert.equal(configData.createdAt.toString(), createdAt.toString());
+    });
+  });
+
+  describe('Mint pool index', () => {
+    it('Should list every indexed pool sharing a mint', async () => {
+      const { tokenX, tokenY } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'token2022'
+      );
+      const tokenZ = await createLegacyToken(
+        provider.connection,
+        authority,
+        authority.publicKey
+      );
+
+      const poolXY = await initializePool(
+        program,
+        authority,
+        tokenX,
+        tokenY,
+        fee,
+        null,
+        false,
+        2,
+        true
+      );
+      const poolXZ = await initializePool(
+        program,
+        authority,
+        tokenX,
+        tokenZ,
+        fee,
+        null,
+        false,
+        2,
+        true
+      );
+
+      const pools = await program.methods
+        .getPoolsForMint(tokenX.mint)
+        .accountsPartial({
+          mintPoolIndex: deriveMintPoolIndex(program.programId, tokenX.mint),
+        })
+        .view();
+      assert.deepEqual(
+        pools.map((pool: PublicKey) => pool.toString()),
+        [poolXY.config.toString(), poolXZ.config.toString()]
+      );
+
+      // Each of the other mints only trades in one of the pools
+      const index = await program.account.mintPoolIndex.fetch(
+        deriveMintPoolIndex(program.programId, tokenZ.mint)
+      );
+      assert.isTrue(index.mint.equals(tokenZ.mint));
+      assert.deepEqual(
+        index.pools.map((pool) => pool.toString()),
+        [poolXZ.config.toString()]
+      );
+    });
+
+    it('Should leave pools created without the index unlisted', async () => {
+      const { tokenX, tokenY } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'legacy'
+      );
+      await initializePool(program, authority, tokenX, tokenY, fee);
+
+      const index = await provider.connection.getAccountInfo(
+        deriveMintPoolIndex(program.programId, tokenX.mint)
+      );
+      assert.isNull(index);
+    });
+  });
+
+  describe('Mint pair validation', () => {
+    it('Should reject the same mint on both sides', async () => {
+      const { tokenX } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'legacy'
+      );
+
+      await expectError(
+        initializePool(program, authority, tokenX, tokenX, fee),
+        'DuplicateMint'
+      );
+      await expectError(
+        initializePoolWithPdaVaults(program, authority, tokenX, tokenX, fee),
+        'DuplicateMint'
+      );
+    });
+
+    it('Should reject a token program that does not own its mint', async () => {
+      const { tokenX, tokenY } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'token2022'
+      );
+      // The legacy mint posing as a Token-2022 mint
+      const mismatched = { ...tokenX, tokenProgram: TOKEN_2022_PROGRAM_ID };
+
+      await expectError(
+        initializePool(program, authority, mismatched, tokenY, fee),
+        'TokenProgramMismatch'
+      );
+      await expectError(
+        initializePoolWithPdaVaults(
+          program,
+          authority,
+          tokenY,
+          mismatched,
+          fee
+        ),
+        'TokenProgramMismatch'
+      );
+    });
+  });
+
+  describe('Trading mint aliasing', () => {
+    it('Should reject the pool LP mint PDA as a trading mint', async () => {
+      const { tokenX, tokenY } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'legacy'
+      );
+      const seed = new anchor.BN(Math.floor(Math.random() * 1000000000));
+      const derived = derivePoolAccounts(
+        program.programId,
+        seed,
+        tokenX,
+        tokenY
+      );
+      // Pose the LP mint the pool is about to create as mint X
+      const aliased = { ...tokenX, mint: derived.lpMint };
+      const pool = derivePoolAccounts(program.programId, seed, aliased, tokenY);
+
+      await expectError(
+        program.methods
+          .initializePool(seed, fee, null, false, 2, 10000)
+          .accountsPartial({
+            authority: authority.publicKey,
+            config: pool.config,
+            poolState: pool.poolState,
+            mintX: aliased.mint,
+            mintY: tokenY.mint,
+            lpMint: pool.lpMint,
+            poolAuthority: pool.poolAuthority,
+            vaultAuthority: pool.vaultAuthority,
+            vaultX: pool.vaultX,
+            vaultY: pool.vaultY,
+            mintXPoolIndex: null,
+            mintYPoolIndex: null,
+            tokenProgramX: tokenX.tokenProgram,
+            tokenProgramY: tokenY.tokenProgram,
+            tokenProgramLp: TOKEN_PROGRAM_ID,
+          })
+          .signers([authority])
+          .rpc(),
+        // The LP mint does not exist before init, so loading it as a mint
+        // account refuses it before anything is created
+        'AccountNotInitialized'
+      );
+
+      assert.isNull(await provider.connection.getAccountInfo(pool.config));
+    });
+
+    it('Should reject one mint passed as both sides under different token programs', async () => {
+      const { tokenX } = await setupTestTokens(
+        provider.connection,
+        authority,
+        [],
+        'legacy',
+        'legacy'
+      );
+      // The same legacy mint posing as a Token-2022 mint on the Y side
+      const aliased = { ...tokenX, tokenProgram: TOKEN_2022_PROGRAM_ID };
+      const seed = new anchor.BN(Math.floor(Math.random() * 1000000000));
+      const pool = derivePoolAccounts(program.programId, seed, tokenX, aliased);
+
+      await expectError(
+        program.methods
+          .initializePool(seed, fee, null, false, 2, 10000)
+          .accountsPartial({
+            authority: authority.publicKey,
+            config: pool.config,
+            poolState: pool.poolState,
+            mintX: tokenX.mint,
+            mintY: aliased.mint,
+            lpMint: pool.lpMint,
+            poolAuthority: pool.poolAuthority,
+            vaultAuthority: pool.vaultAuthority,
+            vaultX: pool.vaultX,
+            vaultY: pool.vaultY,
+            mintXPoolIndex: null,
+            mintYPoolIndex: null,
+            tokenProgramX: tokenX.tokenProgram,
+            tokenProgramY: aliased.tokenProgram,
+            tokenProgramLp: TOKEN_PROGRAM_ID,
+          })
+          .signers([authority])
+          .rpc(),
+        // A mint address has a single owner, so this is the same mint twice and is
+        // refused before either vault is created
+        'DuplicateMint'
+      );
+
+      assert.isNull(await provider.connection.getAccountInfo(pool.config));
+    });
+  });
+});
@@ -0,0 +1,139 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { Keypair, SystemProgram } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { UserTokenAccounts } from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  expectError,
+  setupPool,
+  swapTokens,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Account Migration Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+  // Keep in sync with POOL_STATE_VERSION and CONFIG_VERSION
+  const poolStateVersion = 1;
+  const configVersion = 2;
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  let pool: PoolAccounts;
+  let userAccounts: Map<string, UserTokenAccounts>;
+
+  function migratePoolState(signer: Keypair) {
+    return program.methods
+      .migratePoolState()
+      .accountsPartial({
+        authority: signer.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  function migrateConfig(signer: Keypair) {
+    return program.methods
+      .migrateConfig()
+      .accountsPartial({
+        authority: signer.publicKey,
+        config: pool.config,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+
+    ({ pool, userAccounts } = await setupPool(
+      provider.connection,
+      program,
+      authority,
+      [user.publicKey],
+      'legacy',
+      'legacy',
+      fee
+    ));
+
+    await depositLiquidity(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      1000 * 10 ** 6,
+      1000 * 10 ** 6
+    );
+  });
+
+  it('Should create new pools at the current layout version', async () => {
+    const poolState = await program.account.poolState.fetch(pool.poolState);
+
+    assert.equal(poolState.version, poolStateVersion);
+  });
+
+  it('Should leave a pool already at the current version untouched', async () => {
+    const before = await provider.connection.getAccountInfo(pool.poolState);
+
+    await migratePoolState(authority);
+
+    const after = await provider.connection.getAccountInfo(pool.poolState);
+    assert.equal(after!.data.length, before!.data.length);
+    assert.equal(after!.lamports, before!.lamports);
+    assert.isTrue(after!.data.equals(before!.data));
+  });
+
+  it('Should keep the pool usable after a repeated migration', async () => {
+    await migratePoolState(authority);
+    await swapTokens(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      true,
+      10 * 10 ** 6
+    );
+
+    const poolState = await program.account.poolState.fetch(pool.poolState);
+    assert.equal(poolState.version, poolStateVersion);
+    assert.isTrue(poolState.cumulativeFeeX.gtn(0));
+  });
+
+  it('Should reject a signer other than the pool authority', async () => {
+    await expectError(migratePoolState(user), 'Unauthorized');
+  });
+
+  it('Should leave a config already at the current version untouched', async () => {
+    const before = await provider.connection.getAccountInfo(pool.config);
+    assert.equal(
+      (await program.account.config.fetch(pool.config)).version,
+      configVersion
+    );
+
+    await migrateConfig(authority);
+
+    const after = await provider.connection.getAccountInfo(pool.config);
+    assert.equal(after!.lamports, before!.lamports);
+    assert.isTrue(after!.data.equals(before!.data));
+  });
+
+  it('Should reject a config migration by anyone but the pool authority', async () => {
+    await expectError(migrateConfig(user), 'Unauthorized');
+  });
+});
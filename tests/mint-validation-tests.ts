@@ -0,0 +1,146 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { ExtensionType } from '@solana/spl-token';
+import { Keypair, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import {
+  createLegacyToken,
+  createToken2022WithTransferFee,
+  createToken2022WithTransferHook,
+  createToken2022WithUnsupportedExtension,
+} from './helpers/token-helper';
+import { initializePool } from './helpers/pool-helper';
+
+describe('Mint Validation Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  let authority: Keypair;
+
+  before(async () => {
+    authority = Keypair.generate();
+
+    await provider.connection.requestAirdrop(
+      authority.publicKey,
+      10 * LAMPORTS_PER_SOL
+    );
+
+    // Wait for airdrop to confirm
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+  });
+
+  describe('check_mint_supported', () => {
+    it('Should accept a plain SPL mint', async () => {
+      const token = await createLegacyToken(
+        provider.connection,
+        authority,
+        authority.publicKey
+      );
+
+      const result = await program.methods
+        .checkMintSupported()
+        .accounts({ mint: token.mint })
+        .view();
+
+      assert.isTrue(result.supported);
+      assert.isNull(result.extensionType);
+    });
+
+    it('Should accept a Token-2022 mint with transfer fee', async () => {
+      const token = await createToken2022WithTransferFee(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        authority.publicKey,
+        authority.publicKey
+      );
+
+      const result = await program.methods
+        .checkMintSupported()
+        .accounts({ mint: token.mint })
+        .view();
+
+      assert.isTrue(result.supported);
+      assert.isNull(result.extensionType);
+    });
+
+    it('Should reject a transfer-hook mint and report the extension', async () => {
+      const token = await createToken2022WithTransferHook(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        Keypair.generate().publicKey
+      );
+
+      const result = await program.methods
+        .checkMintSupported()
+        .accounts({ mint: token.mint })
+        .view();
+
+      assert.isFalse(result.supported);
+      assert.equal(result.extensionType, ExtensionType.TransferHook);
+    });
+  });
+
+  describe('Rejected extension reporting', () => {
+    const rejected = [
+      ExtensionType.TransferHook,
+      ExtensionType.PermanentDelegate,
+      ExtensionType.NonTransferable,
+      ExtensionType.MintCloseAuthority,
+    ] as const;
+
+    function createRejectedMint(extension: (typeof rejected)[number]) {
+      return extension === ExtensionType.TransferHook
+        ? createToken2022WithTransferHook(
+            provider.connection,
+            authority,
+            authority.publicKey,
+            Keypair.generate().publicKey
+          )
+        : createToken2022WithUnsupportedExtension(
+            provider.connection,
+            authority,
+            authority.publicKey,
+            extension
+          );
+    }
+
+    for (const extension of rejected) {
+      it(`Should name ${ExtensionType[extension]} when rejecting a mint`, async () => {
+        const token = await createRejectedMint(extension);
+        const other = await createLegacyToken(
+          provider.connection,
+          authority,
+          authority.publicKey
+        );
+
+        const result = await program.methods
+          .checkMintSupported()
+          .accounts({ mint: token.mint })
+          .view();
+        assert.isFalse(result.supported);
+        assert.equal(result.extensionType, extension);
+
+        // Pool creation fails with the generic error, the log names the extension
+        let error: any = null;
+        try {
+          await initializePool(program, authority, token, other, 300);
+        } catch (e) {
+          error = e;
+        }
+        assert.isNotNull(error);
+        const logs = (error.logs ?? []).join('\n');
+        assert.include(`${error} ${logs}`, 'NotAllowedTokenExtension');
+        assert.include(
+          logs,
+          `rejected due to ${ExtensionType[extension]} (extension type ${extension})`
+        );
+      });
+    }
+  });
+});
@@ -0,0 +1,138 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { UserTokenAccounts } from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  deriveRegistry,
+  expectError,
+  setGlobalPause,
+  setupPool,
+  swapTokens,
+  withdrawLiquidity,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Pause All Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+  let registry: PublicKey;
+
+  // Two pools run by the same authority
+  const pools: {
+    pool: PoolAccounts;
+    userAccounts: Map<string, UserTokenAccounts>;
+  }[] = [];
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+    registry = deriveRegistry(program.programId, authority.publicKey);
+
+    for (const tokenYType of ['legacy', 'token2022'] as const) {
+      const setup = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        tokenYType,
+        fee
+      );
+      await depositLiquidity(
+        program,
+        user,
+        setup.pool,
+        setup.userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+      pools.push(setup);
+    }
+  });
+
+  it('Should pause every pool of the authority in one call', async () => {
+    await setGlobalPause(program, authority, true);
+
+    const registryData = await program.account.authorityRegistry.fetch(
+      registry
+    );
+    assert.isTrue(registryData.authority.equals(authority.publicKey));
+    assert.isTrue(registryData.globalPaused);
+
+    for (const { pool, userAccounts } of pools) {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      await expectError(
+        swapTokens(program, user, pool, accounts, true, 10 ** 6, { registry }),
+        'GloballyPaused'
+      );
+      await expectError(
+        depositLiquidity(program, user, pool, accounts, 10 ** 6, 10 ** 6, {
+          registry,
+        }),
+        'GloballyPaused'
+      );
+      await expectError(
+        withdrawLiquidity(program, user, pool, accounts, 10 ** 6, {
+          registry,
+        }),
+        'GloballyPaused'
+      );
+    }
+  });
+
+  it("Should reject another authority's registry", async () => {
+    const other = Keypair.generate();
+    await airdrop(provider.connection, [other.publicKey]);
+    await setGlobalPause(program, other, false);
+
+    const { pool, userAccounts } = pools[0];
+    await expectError(
+      swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 ** 6,
+        { registry: deriveRegistry(program.programId, other.publicKey) }
+      ),
+      'ConstraintSeeds'
+    );
+  });
+
+  it('Should resume every pool once unpaused', async () => {
+    await setGlobalPause(program, authority, false);
+
+    for (const { pool, userAccounts } of pools) {
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 ** 6,
+        { registry }
+      );
+    }
+
+    const registryData = await program.account.authorityRegistry.fetch(
+      registry
+    );
+    assert.isFalse(registryData.globalPaused);
+  });
+});
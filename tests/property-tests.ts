@@ -0,0 +1,219 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { Keypair } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { getTokenBalance } from './helpers/balance-helper';
+import { UserTokenAccounts } from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  getLpTokenAddress,
+  setupPool,
+  swapTokens,
+  withdrawLiquidity,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+/**
+ * Small deterministic PRNG (mulberry32) so failing sequences can be replayed from the seed
+ */
+function mulberry32(seed: number): () => number {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+interface PoolValue {
+  reserveX: bigint;
+  reserveY: bigint;
+  lpSupply: bigint;
+  vaultX: bigint;
+  vaultY: bigint;
+}
+
+describe('Property Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+  const prngSeed = 0x5eed;
+  const steps = 40;
+
+  let authority: Keypair;
+  let users: Keypair[];
+
+  before(async () => {
+    authority = Keypair.generate();
+    users = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+
+    await airdrop(provider.connection, [
+      authority.publicKey,
+      ...users.map((user) => user.publicKey),
+    ]);
+  });
+
+  async function readPoolValue(pool: PoolAccounts): Promise<PoolValue> {
+    const [poolData, vaultX, vaultY] = await Promise.all([
+      program.account.poolState.fetch(pool.poolState),
+      getTokenBalance(
+        provider.connection,
+        pool.vaultX,
+        pool.tokenX.tokenProgram
+      ),
+      getTokenBalance(
+        provider.connection,
+        pool.vaultY,
+        pool.tokenY.tokenProgram
+      ),
+    ]);
+
+    return {
+      reserveX: BigInt(poolData.reserveX.toString()),
+      reserveY: BigInt(poolData.reserveY.toString()),
+      lpSupply: BigInt(poolData.lpSupply.toString()),
+      vaultX: vaultX.balance,
+      vaultY: vaultY.balance,
+    };
+  }
+
+  /**
+   * No value creation: the vaults always cover the reserves, and the value backing
+   * each LP token (sqrt(k) / lp_supply) never decreases across any operation
+   */
+  function assertNoValueCreated(
+    step: string,
+    before: PoolValue,
+    after: PoolValue
+  ): void {
+    assert.isTrue(
+      after.vaultX >= after.reserveX && after.vaultY >= after.reserveY,
+      `${step}: vaults no longer cover reserves`
+    );
+
+    if (before.lpSupply === BigInt(0) || after.lpSupply === BigInt(0)) {
+      return;
+    }
+
+    const kBefore = before.reserveX * before.reserveY;
+    const kAfter = after.reserveX * after.reserveY;
+    assert.isTrue(
+      kAfter * before.lpSupply * before.lpSupply >=
+        kBefore * after.lpSupply * after.lpSupply,
+      `${step}: value per LP token decreased`
+    );
+  }
+
+  function testRandomSequence(
+    pairName: string,
+    tokenXType: 'legacy' | 'token2022',
+    tokenYType: 'legacy' | 'token2022'
+  ) {
+    describe(`${pairName} random operation sequence`, () => {
+      let pool: PoolAccounts;
+      let userAccounts: Map<string, UserTokenAccounts>;
+
+      before(async () => {
+        ({ pool, userAccounts } = await setupPool(
+          provider.connection,
+          program,
+          authority,
+          users.map((user) => user.publicKey),
+          tokenXType,
+          tokenYType,
+          fee
+        ));
+
+        await depositLiquidity(
+          program,
+          users[0],
+          pool,
+          userAccounts.get(users[0].publicKey.toString())!,
+          500 * 10 ** 6,
+          1000 * 10 ** 6
+        );
+      });
+
+      it(`Should never create value over ${steps} random steps (seed ${prngSeed})`, async () => {
+        const random = mulberry32(prngSeed);
+        const randomInt = (min: number, max: number) =>
+          min + Math.floor(random() * (max - min + 1));
+
+        for (let i = 0; i < steps; i++) {
+          const user = users[randomInt(0, users.length - 1)];
+          const accounts = userAccounts.get(user.publicKey.toString())!;
+          const before = await readPoolValue(pool);
+          const operation = randomInt(0, 2);
+
+          let step: string;
+          if (operation === 0) {
+            // Deposit roughly at the pool ratio, skewed by up to +-20%
+            const amountX = randomInt(1, 100) * 10 ** 6;
+            const skew = 0.8 + random() * 0.4;
+            const amountY =
+              before.reserveX === BigInt(0)
+                ? amountX * 2
+                : Math.max(
+                    1,
+                    Math.floor(
+                      (amountX * Number(before.reserveY) * skew) /
+                        Number(before.reserveX)
+                    )
+                  );
+            step = `#${i} deposit ${amountX}/${amountY}`;
+            await depositLiquidity(
+              program,
+              user,
+              pool,
+              accounts,
+              amountX,
+              amountY
+            );
+          } else if (operation === 1) {
+            const xToY = random() < 0.5;
+            const amountIn = randomInt(1, 50) * 10 ** 6;
+            step = `#${i} swap ${xToY ? 'X->Y' : 'Y->X'} ${amountIn}`;
+            await swapTokens(program, user, pool, accounts, xToY, amountIn);
+          } else {
+            const lpBalance = (
+              await getTokenBalance(
+                provider.connection,
+                getLpTokenAddress(pool, user.publicKey),
+                TOKEN_PROGRAM_ID
+              )
+            ).balance;
+            const lpAmount =
+              (lpBalance * BigInt(randomInt(1, 100))) / BigInt(100);
+            if (lpAmount === BigInt(0)) {
+              continue;
+            }
+            step = `#${i} withdraw ${lpAmount}`;
+            await withdrawLiquidity(
+              program,
+              user,
+              pool,
+              accounts,
+              new anchor.BN(lpAmount.toString())
+            );
+          }
+
+          const after = await readPoolValue(pool);
+          assertNoValueCreated(step, before, after);
+        }
+      });
+    });
+  }
+
+  testRandomSequence('Legacy + Legacy', 'legacy', 'legacy');
+  testRandomSequence('Token-2022 + Legacy', 'token2022', 'legacy');
+  testRandomSequence('Token-2022 + Token-2022', 'token2022', 'token2022');
+});
@@ -0,0 +1,165 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import {
+  getAccount,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from '@solana/spl-token';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { createLegacyToken, UserTokenAccounts } from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  expectError,
+  setupPool,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Rescue Tokens Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  let pool: PoolAccounts;
+  let userAccounts: Map<string, UserTokenAccounts>;
+
+  async function tokenAccount(mint: PublicKey, owner: PublicKey) {
+    const account = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      owner,
+      true
+    );
+    return account.address;
+  }
+
+  function rescueTokens(
+    signer: Keypair,
+    mint: PublicKey,
+    strayToken: PublicKey,
+    authorityToken: PublicKey
+  ) {
+    return program.methods
+      .rescueTokens(mint)
+      .accountsPartial({
+        authority: signer.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        poolAuthority: pool.poolAuthority,
+        strayMint: mint,
+        strayToken,
+        authorityToken,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+
+    ({ pool, userAccounts } = await setupPool(
+      provider.connection,
+      program,
+      authority,
+      [user.publicKey],
+      'legacy',
+      'legacy',
+      fee
+    ));
+
+    await depositLiquidity(
+      program,
+      user,
+      pool,
+      userAccounts.get(user.publicKey.toString())!,
+      100 * 10 ** 6,
+      100 * 10 ** 6
+    );
+  });
+
+  it('Should return stray tokens held by the pool authority to the authority', async () => {
+    const stray = await createLegacyToken(
+      provider.connection,
+      authority,
+      authority.publicKey
+    );
+    const strayToken = await tokenAccount(stray.mint, pool.poolAuthority);
+    const authorityToken = await tokenAccount(
+      stray.mint,
+      authority.publicKey
+    );
+
+    // A user sending tokens to the pool authority instead of their own account
+    const amount = 5 * 10 ** 6;
+    await mintTo(
+      provider.connection,
+      authority,
+      stray.mint,
+      strayToken,
+      authority,
+      amount
+    );
+
+    await expectError(
+      rescueTokens(user, stray.mint, strayToken, authorityToken),
+      'Unauthorized'
+    );
+
+    await rescueTokens(authority, stray.mint, strayToken, authorityToken);
+
+    const strayAfter = await getAccount(provider.connection, strayToken);
+    const authorityAfter = await getAccount(
+      provider.connection,
+      authorityToken
+    );
+    assert.equal(strayAfter.amount.toString(), '0');
+    assert.equal(authorityAfter.amount.toString(), amount.toString());
+  });
+
+  it('Should refuse to touch the pool vaults', async () => {
+    for (const [mint, vault] of [
+      [pool.tokenX.mint, pool.vaultX],
+      [pool.tokenY.mint, pool.vaultY],
+    ]) {
+      const authorityToken = await tokenAccount(mint, authority.publicKey);
+      await expectError(
+        rescueTokens(authority, mint, vault, authorityToken),
+        'CannotRescuePoolAccount'
+      );
+    }
+
+    const poolData = await program.account.poolState.fetch(pool.poolState);
+    const vaultX = await getAccount(provider.connection, pool.vaultX);
+    const vaultY = await getAccount(provider.connection, pool.vaultY);
+    assert.equal(vaultX.amount.toString(), poolData.reserveX.toString());
+    assert.equal(vaultY.amount.toString(), poolData.reserveY.toString());
+  });
+
+  it('Should refuse to rescue LP tokens', async () => {
+    const strayToken = await tokenAccount(pool.lpMint, pool.poolAuthority);
+    const authorityToken = await tokenAccount(
+      pool.lpMint,
+      authority.publicKey
+    );
+
+    await expectError(
+      rescueTokens(authority, pool.lpMint, strayToken, authorityToken),
+      'CannotRescuePoolAccount'
+    );
+  });
+});
@@ -0,0 +1,409 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  transfer,
+} from '@solana/spl-token';
+import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import { getTokenBalance } from './helpers/balance-helper';
+import {
+  createLegacyToken,
+  createUserTokenAccount,
+  getTokenAddress,
+  mintTokensToUser,
+  TokenInfo,
+  UserTokenAccounts,
+} from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  expectError,
+  getLpTokenAddress,
+  setupPool,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('LP Reward Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+  const rewardRatePerSecond = 1_000_000; // 1 reward token per second
+
+  let authority: Keypair;
+  let user1: Keypair;
+  let user2: Keypair;
+
+  let pool: PoolAccounts;
+  let userAccounts: Map<string, UserTokenAccounts>;
+  let rewardToken: TokenInfo;
+  let rewardConfig: PublicKey;
+  let rewardVault: PublicKey;
+  let stakeVault: PublicKey;
+
+  const sleep = (seconds: number) =>
+    new Promise((resolve) => setTimeout(resolve, seconds * 1000));
+
+  function deriveUserReward(user: Keypair): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user_reward'),
+        rewardConfig.toBuffer(),
+        user.publicKey.toBuffer(),
+      ],
+      program.programId
+    )[0];
+  }
+
+  async function getLpBalance(user: Keypair): Promise<bigint> {
+    const lpBalance = await getTokenBalance(
+      provider.connection,
+      getLpTokenAddress(pool, user.publicKey),
+      TOKEN_PROGRAM_ID
+    );
+    return lpBalance.balance;
+  }
+
+  async function stakeLp(user: Keypair, amount: bigint) {
+    await program.methods
+      .stakeLp(new anchor.BN(amount.toString()))
+      .accountsPartial({
+        user: user.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        rewardConfig,
+        userReward: deriveUserReward(user),
+        lpMint: pool.lpMint,
+        userLpToken: getLpTokenAddress(pool, user.publicKey),
+        stakeVault,
+        tokenProgramLp: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+  }
+
+  async function unstakeLp(user: Keypair, amount: bigint) {
+    await program.methods
+      .unstakeLp(new anchor.BN(amount.toString()))
+      .accountsPartial({
+        user: user.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        poolAuthority: pool.poolAuthority,
+        rewardConfig,
+        userReward: deriveUserReward(user),
+        lpMint: pool.lpMint,
+        userLpToken: getLpTokenAddress(pool, user.publicKey),
+        stakeVault,
+        tokenProgramLp: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+  }
+
+  async function claimReward(user: Keypair): Promise<bigint> {
+    const userRewardToken = getTokenAddress(
+      rewardToken.mint,
+      user.publicKey,
+      TOKEN_PROGRAM_ID
+    );
+    const before = await getTokenBalance(
+      provider.connection,
+      userRewardToken,
+      TOKEN_PROGRAM_ID
+    );
+
+    await program.methods
+      .claimReward()
+      .accountsPartial({
+        user: user.publicKey,
+        config: pool.config,
+        poolAuthority: pool.poolAuthority,
+        rewardConfig,
+        userReward: deriveUserReward(user),
+        rewardMint: rewardToken.mint,
+        rewardVault,
+        userRewardToken,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const after = await getTokenBalance(
+      provider.connection,
+      userRewardToken,
+      TOKEN_PROGRAM_ID
+    );
+    return after.balance - before.balance;
+  }
+
+  async function getPendingRewards(
+    user: Keypair,
+    rewardsInitialized = true
+  ): Promise<bigint> {
+    const userReward = deriveUserReward(user);
+    const registered =
+      rewardsInitialized &&
+      (await provider.connection.getAccountInfo(userReward)) !== null;
+
+    const pending = await program.methods
+      .getPendingRewards(user.publicKey)
+      .accountsPartial({
+        config: pool.config,
+        rewardConfig: rewardsInitialized ? rewardConfig : null,
+        userReward: registered ? userReward : null,
+      })
+      .view();
+    return BigInt(pending.toString());
+  }
+
+  before(async () => {
+    authority = Keypair.generate();
+    user1 = Keypair.generate();
+    user2 = Keypair.generate();
+
+    await airdrop(provider.connection, [
+      authority.publicKey,
+      user1.publicKey,
+      user2.publicKey,
+    ]);
+
+    ({ pool, userAccounts } = await setupPool(
+      provider.connection,
+      program,
+      authority,
+      [user1.publicKey, user2.publicKey],
+      'legacy',
+      'legacy',
+      fee
+    ));
+
+    rewardToken = await createLegacyToken(
+      provider.connection,
+      authority,
+      authority.publicKey
+    );
+
+    [rewardConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from('reward'), pool.config.toBuffer()],
+      program.programId
+    );
+    [rewardVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('reward_vault'), pool.config.toBuffer()],
+      program.programId
+    );
+    [stakeVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('stake_vault'), pool.config.toBuffer()],
+      program.programId
+    );
+  });
+
+  it('Should report no pending reward before rewards are initialized', async () => {
+    await depositLiquidity(
+      program,
+      user2,
+      pool,
+      userAccounts.get(user2.publicKey.toString())!,
+      10 * 10 ** 6,
+      10 * 10 ** 6
+    );
+
+    assert.equal(await getPendingRewards(user2, false), BigInt(0));
+  });
+
+  it('Should initialize and fund the reward schedule', async () => {
+    await program.methods
+      .initializeReward(new anchor.BN(rewardRatePerSecond))
+      .accountsPartial({
+        authority: authority.publicKey,
+        config: pool.config,
+        poolState: pool.poolState,
+        poolAuthority: pool.poolAuthority,
+        rewardConfig,
+        rewardMint: rewardToken.mint,
+        rewardVault,
+        lpMint: pool.lpMint,
+        stakeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgramLp: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const authorityRewardToken = await createUserTokenAccount(
+      provider.connection,
+      authority,
+      rewardToken.mint,
+      authority.publicKey,
+      TOKEN_PROGRAM_ID
+    );
+    const fundAmount = 1_000_000 * 10 ** 6;
+    await mintTokensToUser(
+      provider.connection,
+      authority,
+      rewardToken,
+      authorityRewardToken,
+      authority,
+      fundAmount
+    );
+
+    await program.methods
+      .fundReward(new anchor.BN(fundAmount))
+      .accountsPartial({
+        authority: authority.publicKey,
+        config: pool.config,
+        rewardConfig,
+        rewardMint: rewardToken.mint,
+        rewardVault,
+        authorityRewardToken,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const rewardConfigData = await program.account.rewardConfig.fetch(
+      rewardConfig
+    );
+    assert.equal(
+      rewardConfigData.rewardRatePerSecond.toNumber(),
+      rewardRatePerSecond
+    );
+    const vaultBalance = await getTokenBalance(
+      provider.connection,
+      rewardVault,
+      TOKEN_PROGRAM_ID
+    );
+    assert.equal(vaultBalance.balance, BigInt(fundAmount));
+  });
+
+  it('Should reject funding from a non-authority signer', async () => {
+    const userRewardToken = await createUserTokenAccount(
+      provider.connection,
+      authority,
+      rewardToken.mint,
+      user1.publicKey,
+      TOKEN_PROGRAM_ID
+    );
+
+    await expectError(
+      program.methods
+        .fundReward(new anchor.BN(1))
+        .accountsPartial({
+          authority: user1.publicKey,
+          config: pool.config,
+          rewardConfig,
+          rewardMint: rewardToken.mint,
+          rewardVault,
+          authorityRewardToken: userRewardToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc(),
+      'Unauthorized'
+    );
+  });
+
+  it('Should accrue rewards to a single staker over time', async () => {
+    await depositLiquidity(
+      program,
+      user1,
+      pool,
+      userAccounts.get(user1.publicKey.toString())!,
+      100 * 10 ** 6,
+      100 * 10 ** 6
+    );
+    await stakeLp(user1, await getLpBalance(user1));
+
+    await sleep(3);
+
+    // Sole staker earns the whole emission since staking
+    const claimed = await claimReward(user1);
+    assert.isTrue(claimed >= BigInt(2 * rewardRatePerSecond));
+  });
+
+  it('Should split rewards by stake between two stakers', async () => {
+    // user2 stakes a bit over three times user1's LP
+    await depositLiquidity(
+      program,
+      user2,
+      pool,
+      userAccounts.get(user2.publicKey.toString())!,
+      300 * 10 ** 6,
+      300 * 10 ** 6
+    );
+    await stakeLp(user2, await getLpBalance(user2));
+    await claimReward(user1);
+
+    await sleep(4);
+
+    const claimed1 = await claimReward(user1);
+    const claimed2 = await claimReward(user2);
+    assert.isTrue(claimed1 > BigInt(0));
+
+    // user2 stakes 3.1x the LP and claims a bit later, so earns at least 3x
+    assert.isTrue(claimed2 >= claimed1 * BigInt(3));
+    assert.isTrue(claimed2 <= claimed1 * BigInt(5));
+  });
+
+  it('Should report the pending reward a claim then pays out', async () => {
+    await claimReward(user2);
+    await sleep(3);
+
+    const pending = await getPendingRewards(user2);
+    assert.isTrue(pending > BigInt(0));
+
+    // The claim lands a moment later, so it can only have accrued a little more
+    const claimed = await claimReward(user2);
+    assert.isTrue(claimed >= pending);
+    assert.isTrue(claimed - pending <= BigInt(2 * rewardRatePerSecond));
+
+    // Viewing did not checkpoint, the claim did
+    assert.isTrue((await getPendingRewards(user2)) < pending);
+  });
+
+  it('Should not pay LP moved to another wallet twice', async () => {
+    const staked = (
+      await program.account.userReward.fetch(deriveUserReward(user1))
+    ).staked;
+    await unstakeLp(user1, BigInt(staked.toString()));
+
+    // Earned while staked stays claimable after unstaking
+    assert.isTrue((await claimReward(user1)) > BigInt(0));
+
+    // The unstaked LP changes hands, it earns nothing until staked again
+    await transfer(
+      provider.connection,
+      user1,
+      getLpTokenAddress(pool, user1.publicKey),
+      getLpTokenAddress(pool, user2.publicKey),
+      user1,
+      BigInt(staked.toString())
+    );
+    await sleep(2);
+
+    assert.equal(await claimReward(user1), BigInt(0));
+    const rewardConfigData = await program.account.rewardConfig.fetch(
+      rewardConfig
+    );
+    assert.equal(
+      rewardConfigData.totalStaked.toString(),
+      (
+        await program.account.userReward.fetch(deriveUserReward(user2))
+      ).staked.toString()
+    );
+  });
+
+  it('Should reject unstaking more than is staked', async () => {
+    await expectError(unstakeLp(user1, BigInt(1)), 'InsufficientStakedLp');
+  });
+});
@@ -0,0 +1,274 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { getAccount } from '@solana/spl-token';
+import { AccountMeta, Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import {
+  createLegacyToken,
+  createToken2022WithTransferFee,
+  createUserTokenAccount,
+  mintTokensToUser,
+  TokenInfo,
+} from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  deriveRegistry,
+  expectError,
+  initializePool,
+  PoolAccounts,
+  setGlobalPause,
+} from './helpers/pool-helper';
+
+describe('Swap Route Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+  const liquidity = 1000 * 10 ** 6;
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  // A -> B -> C -> D through three pools; no pool holds both A and D
+  let tokens: TokenInfo[];
+  let pools: PoolAccounts[];
+  const userTokens = new Map<string, PublicKey>();
+
+  const userToken = (token: TokenInfo) =>
+    userTokens.get(token.mint.toString())!;
+
+  function hopAccounts(pool: PoolAccounts, from: TokenInfo, to: TokenInfo) {
+    const inIsX = pool.tokenX.mint.equals(from.mint);
+    const meta = (pubkey: PublicKey, isWritable = false): AccountMeta => ({
+      pubkey,
+      isSigner: false,
+      isWritable,
+    });
+    return [
+      meta(pool.config),
+      meta(pool.poolState, true),
+      meta(pool.vaultAuthority),
+      meta(to.mint),
+      meta(inIsX ? pool.vaultX : pool.vaultY, true),
+      meta(inIsX ? pool.vaultY : pool.vaultX, true),
+      meta(userToken(to), true),
+      meta(to.tokenProgram),
+    ];
+  }
+
+  function swapRoute(
+    hops: [PoolAccounts, TokenInfo, TokenInfo][],
+    amountIn: number,
+    minAmountOut: number,
+    registry: PublicKey | null = null
+  ) {
+    const [, first] = hops[0];
+    return program.methods
+      .swapRoute(
+        hops.map(([, from, to]) => ({ mintIn: from.mint, mintOut: to.mint })),
+        new anchor.BN(amountIn),
+        new anchor.BN(minAmountOut)
+      )
+      .accountsPartial({
+        user: user.publicKey,
+        mintIn: first.mint,
+        userTokenIn: userToken(first),
+        tokenProgramIn: first.tokenProgram,
+        registry,
+      })
+      .remainingAccounts(
+        hops.flatMap(([pool, from, to]) => hopAccounts(pool, from, to))
+      )
+      .signers([user])
+      .rpc();
+  }
+
+  const balance = async (token: TokenInfo) =>
+    (
+      await getAccount(
+        provider.connection,
+        userToken(token),
+        undefined,
+        token.tokenProgram
+      )
+    ).amount;
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+
+    const legacy = () =>
+      createLegacyToken(provider.connection, authority, authority.publicKey);
+    tokens = [
+      await legacy(),
+      // An intermediate token with a transfer fee, paid on every move of B
+      await createToken2022WithTransferFee(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        authority.publicKey,
+        authority.publicKey
+      ),
+      await legacy(),
+      await legacy(),
+    ];
+
+    for (const token of tokens) {
+      const account = await createUserTokenAccount(
+        provider.connection,
+        authority,
+        token.mint,
+        user.publicKey,
+        token.tokenProgram
+      );
+      userTokens.set(token.mint.toString(), account);
+      await mintTokensToUser(
+        provider.connection,
+        authority,
+        token,
+        account,
+        authority,
+        10000 * 10 ** 6
+      );
+    }
+
+    pools = [];
+    for (let i = 0; i < 3; i++) {
+      const [tokenX, tokenY] = [tokens[i], tokens[i + 1]];
+      const pool = await initializePool(
+        program,
+        authority,
+        tokenX,
+        tokenY,
+        fee
+      );
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        { tokenX: userToken(tokenX), tokenY: userToken(tokenY) },
+        liquidity,
+        liquidity
+      );
+      pools.push(pool);
+    }
+  });
+
+  it('Should route across three pools and pass intermediate tokens through', async () => {
+    const [a, b, c, d] = tokens;
+    const amountIn = 10 * 10 ** 6;
+    const before = await Promise.all(tokens.map(balance));
+    const reservesBefore = await Promise.all(
+      pools.map((pool) => program.account.poolState.fetch(pool.poolState))
+    );
+
+    await swapRoute(
+      [
+        [pools[0], a, b],
+        [pools[1], b, c],
+        [pools[2], c, d],
+      ],
+      amountIn,
+      1
+    );
+
+    const after = await Promise.all(tokens.map(balance));
+    assert.equal((before[0] - after[0]).toString(), amountIn.toString());
+    // Whatever a hop pays out is spent in full by the next one
+    assert.equal(after[1].toString(), before[1].toString());
+    assert.equal(after[2].toString(), before[2].toString());
+    assert.isTrue(after[3] > before[3]);
+
+    // Every pool took its input side and paid out its output side
+    for (let i = 0; i < 3; i++) {
+      const poolData = await program.account.poolState.fetch(
+        pools[i].poolState
+      );
+      assert.isTrue(poolData.reserveX.gt(reservesBefore[i].reserveX));
+      assert.isTrue(poolData.reserveY.lt(reservesBefore[i].reserveY));
+    }
+  });
+
+  it('Should enforce the minimum output on the final hop only', async () => {
+    const [a, b, c, d] = tokens;
+
+    await expectError(
+      swapRoute(
+        [
+          [pools[0], a, b],
+          [pools[1], b, c],
+          [pools[2], c, d],
+        ],
+        10 * 10 ** 6,
+        10 * 10 ** 6
+      ),
+      'SwapSlippageExceeded'
+    );
+  });
+
+  it('Should reject hops that do not connect', async () => {
+    const [a, b, c, d] = tokens;
+
+    await expectError(
+      swapRoute(
+        [
+          [pools[0], a, b],
+          [pools[2], c, d],
+        ],
+        10 * 10 ** 6,
+        1
+      ),
+      'InvalidRoute'
+    );
+  });
+
+  it('Should refuse the route while the pools are globally paused', async () => {
+    const [a, b, c] = tokens;
+    const registry = deriveRegistry(program.programId, authority.publicKey);
+    const route: [PoolAccounts, TokenInfo, TokenInfo][] = [
+      [pools[0], a, b],
+      [pools[1], b, c],
+    ];
+
+    await setGlobalPause(program, authority, true);
+    await expectError(
+      swapRoute(route, 10 * 10 ** 6, 1, registry),
+      'GloballyPaused'
+    );
+
+    await setGlobalPause(program, authority, false);
+    await swapRoute(route, 10 * 10 ** 6, 1, registry);
+  });
+
+  it('Should reject a registry of another authority', async () => {
+    const [a, b, c] = tokens;
+    const outsider = Keypair.generate();
+    await airdrop(provider.connection, [outsider.publicKey]);
+    // An active registry the outsider owns, standing in for the paused one of the pools
+    await setGlobalPause(program, outsider, false);
+    const route: [PoolAccounts, TokenInfo, TokenInfo][] = [
+      [pools[0], a, b],
+      [pools[1], b, c],
+    ];
+
+    await setGlobalPause(program, authority, true);
+    await expectError(
+      swapRoute(
+        route,
+        10 * 10 ** 6,
+        1,
+        deriveRegistry(program.programId, outsider.publicKey)
+      ),
+      'InvalidRoute'
+    );
+
+    await setGlobalPause(program, authority, false);
+  });
+});
@@ -0,0 +1,1952 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  getAccount,
+} from '@solana/spl-token';
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+} from '@solana/web3.js';
+import { assert } from 'chai';
+
+import {
+  createLegacyToken,
+  createToken2022WithTransferFee,
+  getTokenAddress,
+  createUserTokenAccounts,
+  UserTokenAccounts,
+} from './helpers/token-helper';
+import {
+  airdrop,
+  compoundProtocolFees,
+  depositLiquidity,
+  executeFeeWithdrawal,
+  expectError,
+  getEvents,
+  getLpTokenAddress,
+  initializePool,
+  queueFeeWithdrawal,
+  NO_SWAP_GUARDS,
+  setupPool,
+  setupPoolWithTokens,
+  swapTokens,
+  updateConfig,
+  withdrawProtocolFeesBatch,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Swap Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+  });
+
+  describe('Zero net output guard', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      const tokenX = await createLegacyToken(
+        provider.connection,
+        authority,
+        authority.publicKey
+      );
+      // 50% transfer fee with no practical cap on the output token
+      const tokenY = await createToken2022WithTransferFee(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        authority.publicKey,
+        authority.publicKey,
+        5000,
+        BigInt(10 ** 15)
+      );
+
+      ({ pool, userAccounts } = await setupPoolWithTokens(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        tokenX,
+        tokenY,
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should reject a swap whose output is fully eaten by the transfer fee', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenX
+      );
+
+      // 3 units in yields a gross output of a single unit, withheld entirely as transfer fee
+      await expectError(
+        swapTokens(program, user, pool, accounts, true, 3, {
+          minAmountOut: new anchor.BN(0),
+        }),
+        'InsufficientOutputAmount'
+      );
+
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenX
+      );
+      assert.equal(balanceAfter.amount, balanceBefore.amount);
+    });
+  });
+
+  describe('Swap size cap relative to reserves', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      // At most 10% of the input reserve per swap
+      await updateConfig(program, authority, pool, { maxSwapRatioBps: 1000 });
+    });
+
+    it('Should reject a swap above the ratio cap', async () => {
+      await expectError(
+        swapTokens(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          true,
+          101 * 10 ** 6
+        ),
+        'SwapTooLarge'
+      );
+    });
+
+    it('Should allow a swap at or below the ratio cap', async () => {
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        100 * 10 ** 6
+      );
+    });
+
+    it('Should allow any size once the cap is disabled', async () => {
+      await updateConfig(program, authority, pool, { maxSwapRatioBps: 10000 });
+
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        false,
+        500 * 10 ** 6
+      );
+    });
+  });
+
+  describe('Vault and mint binding', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should reject a swap with the vaults crossed against the mints', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+
+      // X -> Y by mint, but pointing vault_in at the Y vault and vault_out at the X vault
+      await expectError(
+        program.methods
+          .swap(new anchor.BN(10 * 10 ** 6), new anchor.BN(1), NO_SWAP_GUARDS)
+          .accountsPartial({
+            user: user.publicKey,
+            config: pool.config,
+            poolState: pool.poolState,
+            poolAuthority: pool.poolAuthority,
+            vaultAuthority: pool.vaultAuthority,
+            mintIn: pool.tokenX.mint,
+            mintOut: pool.tokenY.mint,
+            vaultIn: pool.vaultY,
+            vaultOut: pool.vaultX,
+            userTokenIn: accounts.tokenX,
+            userTokenOut: accounts.tokenY,
+            userLpToken: null,
+            tokenProgramX: pool.tokenX.tokenProgram,
+            tokenProgramY: pool.tokenY.tokenProgram,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc(),
+        'ConstraintRaw'
+      );
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(
+        poolAfter.reserveX.toString(),
+        poolBefore.reserveX.toString()
+      );
+      assert.equal(
+        poolAfter.reserveY.toString(),
+        poolBefore.reserveY.toString()
+      );
+    });
+
+    async function swapXToYWithVaults(vaultIn: PublicKey, vaultOut: PublicKey) {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      return program.methods
+        .swap(new anchor.BN(10 * 10 ** 6), new anchor.BN(1), NO_SWAP_GUARDS)
+        .accountsPartial({
+          user: user.publicKey,
+          config: pool.config,
+          poolState: pool.poolState,
+          poolAuthority: pool.poolAuthority,
+          vaultAuthority: pool.vaultAuthority,
+          mintIn: pool.tokenX.mint,
+          mintOut: pool.tokenY.mint,
+          vaultIn,
+          vaultOut,
+          userTokenIn: accounts.tokenX,
+          userTokenOut: accounts.tokenY,
+          userLpToken: null,
+          tokenProgramX: pool.tokenX.tokenProgram,
+          tokenProgramY: pool.tokenY.tokenProgram,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    it('Should reject a vault of the right mint and owner that is not the pool vault', async () => {
+      // Anyone can open a token account owned by the pool's vault authority
+      const decoy = await createAccount(
+        provider.connection,
+        user,
+        pool.tokenY.mint,
+        pool.vaultAuthority,
+        Keypair.generate(),
+        undefined,
+        pool.tokenY.tokenProgram
+      );
+
+      await expectError(
+        swapXToYWithVaults(pool.vaultX, decoy),
+        'InvalidVault'
+      );
+    });
+
+    it("Should reject another pool's vault for the same mint", async () => {
+      const otherPool = await initializePool(
+        program,
+        authority,
+        pool.tokenX,
+        pool.tokenY,
+        fee
+      );
+
+      await expectError(
+        swapXToYWithVaults(pool.vaultX, otherPool.vaultY),
+        'ConstraintRaw'
+      );
+      await expectError(
+        swapXToYWithVaults(otherPool.vaultX, pool.vaultY),
+        'ConstraintRaw'
+      );
+    });
+
+    it('Should reject the LP mint as the swap input', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      await expectError(
+        program.methods
+          .swap(new anchor.BN(10 * 10 ** 6), new anchor.BN(1), NO_SWAP_GUARDS)
+          .accountsPartial({
+            user: user.publicKey,
+            config: pool.config,
+            poolState: pool.poolState,
+            poolAuthority: pool.poolAuthority,
+            vaultAuthority: pool.vaultAuthority,
+            mintIn: pool.lpMint,
+            mintOut: pool.tokenY.mint,
+            vaultIn: pool.vaultX,
+            vaultOut: pool.vaultY,
+            userTokenIn: getLpTokenAddress(pool, user.publicKey),
+            userTokenOut: accounts.tokenY,
+            userLpToken: null,
+            tokenProgramX: pool.tokenX.tokenProgram,
+            tokenProgramY: pool.tokenY.tokenProgram,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc(),
+        'InvalidMint'
+      );
+    });
+  });
+
+  describe('Price bound', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    // Input paid per unit of output, scaled by 1e9
+    const pricePrecision = 1_000_000_000;
+
+    function swapWithPriceBound(amountIn: number, maxPrice: number) {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      return program.methods
+        .swapWithPriceBound(new anchor.BN(amountIn), new anchor.BN(maxPrice))
+        .accountsPartial({
+          user: user.publicKey,
+          config: pool.config,
+          poolState: pool.poolState,
+          poolAuthority: pool.poolAuthority,
+          vaultAuthority: pool.vaultAuthority,
+          mintIn: pool.tokenX.mint,
+          mintOut: pool.tokenY.mint,
+          vaultIn: pool.vaultX,
+          vaultOut: pool.vaultY,
+          userTokenIn: accounts.tokenX,
+          userTokenOut: accounts.tokenY,
+          userLpToken: null,
+          tokenProgramX: pool.tokenX.tokenProgram,
+          tokenProgramY: pool.tokenY.tokenProgram,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should reject a swap executing worse than the price bound', async () => {
+      // 10 X into a 1000/1000 pool with a 3% fee averages ~1.041 X per Y
+      await expectError(
+        swapWithPriceBound(10 * 10 ** 6, 1.03 * pricePrecision),
+        'PriceBoundExceeded'
+      );
+    });
+
+    it('Should allow a swap within the price bound', async () => {
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+
+      await swapWithPriceBound(10 * 10 ** 6, 1.05 * pricePrecision);
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.isTrue(poolAfter.reserveX.gt(poolBefore.reserveX));
+      assert.isTrue(poolAfter.reserveY.lt(poolBefore.reserveY));
+    });
+  });
+
+  describe('LP holder fee discount', () => {
+    const discountBps = 5000; // LP holders pay half the fee
+
+    let trader: Keypair;
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    async function swapAndCheckFee(
+      swapper: Keypair,
+      expectedFeeBps: number,
+      options: { userLpToken?: PublicKey } = {}
+    ) {
+      const accounts = userAccounts.get(swapper.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+
+      const amountIn = BigInt(10 * 10 ** 6);
+      await swapTokens(
+        program,
+        swapper,
+        pool,
+        accounts,
+        true,
+        Number(amountIn),
+        options
+      );
+
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+      const reserveIn = BigInt(poolBefore.reserveX.toString());
+      const reserveOut = BigInt(poolBefore.reserveY.toString());
+      const feeAdjustedIn = amountIn * BigInt(10000 - expectedFeeBps);
+      const expectedOut =
+        (feeAdjustedIn * reserveOut) /
+        (reserveIn * BigInt(10000) + feeAdjustedIn);
+
+      assert.equal(balanceAfter.amount - balanceBefore.amount, expectedOut);
+    }
+
+    before(async () => {
+      trader = Keypair.generate();
+      await airdrop(provider.connection, [trader.publicKey]);
+
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey, trader.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, {
+        lpDiscountThreshold: new anchor.BN(1),
+        lpDiscountBps: discountBps,
+      });
+    });
+
+    it('Should charge an LP holder the discounted fee', async () => {
+      await swapAndCheckFee(user, (fee * (10000 - discountBps)) / 10000, {
+        userLpToken: getLpTokenAddress(pool, user.publicKey),
+      });
+    });
+
+    it('Should charge a non-holder the full fee', async () => {
+      await swapAndCheckFee(trader, fee);
+    });
+
+    it('Should check the quoted fee against the discounted fee it resolves to', async () => {
+      const discountedFee = (fee * (10000 - discountBps)) / 10000;
+      const userLpToken = getLpTokenAddress(pool, user.publicKey);
+
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6,
+        { expectedFee: discountedFee, userLpToken }
+      );
+      await expectError(
+        swapTokens(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          true,
+          10 * 10 ** 6,
+          { expectedFee: fee, userLpToken }
+        ),
+        'FeeChanged'
+      );
+    });
+
+    it("Should reject another user's LP account as proof of holdings", async () => {
+      await expectError(
+        swapTokens(
+          program,
+          trader,
+          pool,
+          userAccounts.get(trader.publicKey.toString())!,
+          true,
+          10 * 10 ** 6,
+          { userLpToken: getLpTokenAddress(pool, user.publicKey) }
+        ),
+        'ConstraintRaw'
+      );
+    });
+  });
+
+  describe('Privileged market maker fee', () => {
+    const mmFeeBps = 5; // 0.05%
+
+    let marketMaker: Keypair;
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    async function swapAndCheckFee(swapper: Keypair, expectedFeeBps: number) {
+      const accounts = userAccounts.get(swapper.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+
+      const amountIn = BigInt(10 * 10 ** 6);
+      await swapTokens(program, swapper, pool, accounts, true, Number(amountIn));
+
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+      const reserveIn = BigInt(poolBefore.reserveX.toString());
+      const reserveOut = BigInt(poolBefore.reserveY.toString());
+      const feeAdjustedIn = amountIn * BigInt(10000 - expectedFeeBps);
+      const expectedOut =
+        (feeAdjustedIn * reserveOut) /
+        (reserveIn * BigInt(10000) + feeAdjustedIn);
+
+      assert.equal(balanceAfter.amount - balanceBefore.amount, expectedOut);
+    }
+
+    before(async () => {
+      marketMaker = Keypair.generate();
+      await airdrop(provider.connection, [marketMaker.publicKey]);
+
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey, marketMaker.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, {
+        privilegedMm: marketMaker.publicKey,
+        mmFeeBps,
+      });
+    });
+
+    it('Should charge the privileged market maker its own fee', async () => {
+      await swapAndCheckFee(marketMaker, mmFeeBps);
+    });
+
+    it('Should charge everyone else the regular fee', async () => {
+      await swapAndCheckFee(user, fee);
+    });
+
+    it('Should reject a market maker fee above the maximum', async () => {
+      await expectError(
+        updateConfig(program, authority, pool, { mmFeeBps: 1001 }),
+        'InvalidAmount'
+      );
+    });
+  });
+
+  describe('Mint re-verification cost', () => {
+    async function swapAndGetCu(
+      tokenXType: 'legacy' | 'token2022',
+      tokenYType: 'legacy' | 'token2022'
+    ): Promise<number> {
+      const { pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        tokenXType,
+        tokenYType,
+        fee
+      );
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      const signature = await swapTokens(
+        program,
+        user,
+        pool,
+        accounts,
+        true,
+        10 * 10 ** 6
+      );
+      await provider.connection.confirmTransaction(signature, 'confirmed');
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: 'confirmed',
+        maxSupportedTransactionVersion: 0,
+      });
+
+      assert.isDefined(tx?.meta?.computeUnitsConsumed);
+      return tx!.meta!.computeUnitsConsumed!;
+    }
+
+    it('Should skip parsing legacy mints on swap', async () => {
+      const legacyCu = await swapAndGetCu('legacy', 'legacy');
+      const token2022Cu = await swapAndGetCu('token2022', 'token2022');
+      console.log(
+        `Swap compute: ${legacyCu} CU legacy, ${token2022Cu} CU Token-2022`
+      );
+
+      // The program does not log its own cost, so the whole transaction is
+      // measured: the cached legacy path parses no mint data and stays cheaper
+      assert.isAbove(token2022Cu, legacyCu);
+    });
+  });
+
+  describe('Protocol fee volume threshold', () => {
+    const protocolFeeBps = 2000; // 20% of the swap fee
+    const threshold = 10 * 10 ** 6;
+
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    async function swapAndGetRouting(amountIn: number) {
+      const before = await program.account.poolState.fetch(pool.poolState);
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        amountIn
+      );
+      const after = await program.account.poolState.fetch(pool.poolState);
+
+      return {
+        protocolFee: after.protocolFeesX.sub(before.protocolFeesX).toNumber(),
+        toReserve: after.reserveX.sub(before.reserveX).toNumber(),
+      };
+    }
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, {
+        protocolFeeBps,
+        protocolFeeMinAmount: new anchor.BN(threshold),
+      });
+    });
+
+    it('Should leave the whole fee to LPs just below the threshold', async () => {
+      const amountIn = threshold - 1;
+      const { protocolFee, toReserve } = await swapAndGetRouting(amountIn);
+
+      assert.equal(protocolFee, 0);
+      assert.equal(toReserve, amountIn);
+    });
+
+    it('Should route the protocol share at the threshold', async () => {
+      const amountIn = threshold;
+      const { protocolFee, toReserve } = await swapAndGetRouting(amountIn);
+
+      const feeAmount = Math.floor((amountIn * fee) / 10000);
+      const expectedProtocolFee = Math.floor(
+        (feeAmount * protocolFeeBps) / 10000
+      );
+      assert.equal(protocolFee, expectedProtocolFee);
+      assert.equal(toReserve, amountIn - expectedProtocolFee);
+    });
+
+    it('Should take at most the whole swap fee, never principal', async () => {
+      await updateConfig(program, authority, pool, {
+        protocolFeeBps: 10000,
+        protocolFeeMinAmount: new anchor.BN(0),
+      });
+
+      const amountIn = 12_345_678;
+      const { protocolFee, toReserve } = await swapAndGetRouting(amountIn);
+
+      const feeAmount = Math.floor((amountIn * fee) / 10000);
+      assert.equal(protocolFee, feeAmount);
+      assert.equal(toReserve, amountIn - feeAmount);
+    });
+  });
+
+  describe('Protocol fee withdrawal timelock', () => {
+    const delay = 3;
+
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    const sleep = (seconds: number) =>
+      new Promise((resolve) => setTimeout(resolve, seconds * 1000));
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, {
+        protocolFeeBps: 5000,
+        feeWithdrawalDelay: new anchor.BN(delay),
+      });
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        100 * 10 ** 6
+      );
+    });
+
+    it('Should reject queuing more than the accrued fees', async () => {
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+
+      await expectError(
+        queueFeeWithdrawal(
+          program,
+          authority,
+          pool,
+          poolData.protocolFeesX.addn(1),
+          0
+        ),
+        'InsufficientLiquidity'
+      );
+    });
+
+    it('Should reject execution before anything is queued', async () => {
+      await expectError(
+        executeFeeWithdrawal(program, authority, pool, user.publicKey),
+        'NoFeeWithdrawalQueued'
+      );
+    });
+
+    it('Should reject execution before the delay and allow it after', async () => {
+      const recipients = userAccounts.get(user.publicKey.toString())!;
+      const before = await program.account.poolState.fetch(pool.poolState);
+      const amountX = before.protocolFeesX;
+      assert.isAbove(amountX.toNumber(), 0);
+
+      await queueFeeWithdrawal(program, authority, pool, amountX, 0);
+      await expectError(
+        executeFeeWithdrawal(program, authority, pool, user.publicKey),
+        'FeeWithdrawalLocked'
+      );
+
+      await sleep(delay + 1);
+
+      const balanceBefore = await getAccount(
+        provider.connection,
+        recipients.tokenX
+      );
+      const signature = await executeFeeWithdrawal(
+        program,
+        authority,
+        pool,
+        user.publicKey
+      );
+      const balanceAfter = await getAccount(
+        provider.connection,
+        recipients.tokenX
+      );
+
+      const event = (await getEvents(program, signature)).find(
+        (e) => e.name.toLowerCase() === 'feewithdrawalexecutedevent'
+      );
+      assert.isDefined(event);
+      assert.isTrue(event!.data.config.equals(pool.config));
+      assert.equal(event!.data.amountX.toString(), amountX.toString());
+      assert.equal(event!.data.amountY.toNumber(), 0);
+      assert.isTrue(event!.data.recipientX.equals(recipients.tokenX));
+      assert.isTrue(event!.data.recipientY.equals(recipients.tokenY));
+
+      const after = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(
+        (balanceAfter.amount - balanceBefore.amount).toString(),
+        amountX.toString()
+      );
+      assert.equal(after.protocolFeesX.toNumber(), 0);
+      assert.equal(after.reserveX.toString(), before.reserveX.toString());
+
+      // The queue is cleared, so the same withdrawal cannot run twice
+      await expectError(
+        executeFeeWithdrawal(program, authority, pool, user.publicKey),
+        'NoFeeWithdrawalQueued'
+      );
+    });
+
+    it('Should create the recipient token accounts on withdrawal', async () => {
+      const recipient = Keypair.generate().publicKey;
+      const recipientX = getTokenAddress(
+        pool.tokenX.mint,
+        recipient,
+        pool.tokenX.tokenProgram
+      );
+      const recipientY = getTokenAddress(
+        pool.tokenY.mint,
+        recipient,
+        pool.tokenY.tokenProgram
+      );
+      assert.isNull(await provider.connection.getAccountInfo(recipientX));
+      assert.isNull(await provider.connection.getAccountInfo(recipientY));
+
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        100 * 10 ** 6
+      );
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      const amountX = poolData.protocolFeesX;
+      assert.isAbove(amountX.toNumber(), 0);
+
+      await queueFeeWithdrawal(program, authority, pool, amountX, 0);
+      await sleep(delay + 1);
+      await executeFeeWithdrawal(program, authority, pool, recipient);
+
+      const accountX = await getAccount(provider.connection, recipientX);
+      const accountY = await getAccount(provider.connection, recipientY);
+      assert.isTrue(accountX.owner.equals(recipient));
+      assert.isTrue(accountY.owner.equals(recipient));
+      assert.equal(accountX.amount.toString(), amountX.toString());
+      assert.equal(accountY.amount.toString(), '0');
+    });
+
+    it('Should hold a lowered delay back until the current delay has passed', async () => {
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        100 * 10 ** 6
+      );
+      await updateConfig(program, authority, pool, {
+        feeWithdrawalDelay: new anchor.BN(0),
+      });
+
+      const configData = await program.account.config.fetch(pool.config);
+      assert.equal(configData.feeWithdrawalDelay.toNumber(), delay);
+      assert.equal(configData.pendingFeeWithdrawalDelay.toNumber(), 0);
+      assert.isAbove(configData.feeWithdrawalDelayEffectiveAt.toNumber(), 0);
+
+      // Queued right away, the withdrawal still waits out the old delay
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      await queueFeeWithdrawal(
+        program,
+        authority,
+        pool,
+        poolData.protocolFeesX,
+        0
+      );
+      await expectError(
+        executeFeeWithdrawal(program, authority, pool, user.publicKey),
+        'FeeWithdrawalLocked'
+      );
+    });
+  });
+
+  describe('Compound protocol fees', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        accounts,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, { protocolFeeBps: 10000 });
+      await swapTokens(program, user, pool, accounts, true, 100 * 10 ** 6);
+      await swapTokens(program, user, pool, accounts, false, 100 * 10 ** 6);
+    });
+
+    it('Should reject callers other than the authority', async () => {
+      await expectError(
+        compoundProtocolFees(program, user, pool),
+        'Unauthorized'
+      );
+    });
+
+    it('Should mint LP to the authority and clear the fee counters', async () => {
+      const before = await program.account.poolState.fetch(pool.poolState);
+      assert.isAbove(before.protocolFeesX.toNumber(), 0);
+      assert.isAbove(before.protocolFeesY.toNumber(), 0);
+
+      await compoundProtocolFees(program, authority, pool);
+
+      const after = await program.account.poolState.fetch(pool.poolState);
+      const lpAccount = await getAccount(
+        provider.connection,
+        getLpTokenAddress(pool, authority.publicKey)
+      );
+      const minted = after.lpSupply.sub(before.lpSupply);
+      assert.isAbove(minted.toNumber(), 0);
+      assert.equal(lpAccount.amount.toString(), minted.toString());
+
+      // Matched fees move into the reserves; only the excess over the pool ratio stays accrued
+      const usedX = before.protocolFeesX.sub(after.protocolFeesX);
+      const usedY = before.protocolFeesY.sub(after.protocolFeesY);
+      assert.isAbove(usedX.toNumber(), 0);
+      assert.isAbove(usedY.toNumber(), 0);
+      assert.equal(
+        after.reserveX.toString(),
+        before.reserveX.add(usedX).toString()
+      );
+      assert.equal(
+        after.reserveY.toString(),
+        before.reserveY.add(usedY).toString()
+      );
+    });
+
+    it('Should reject compounding when nothing is left to match', async () => {
+      await expectError(
+        compoundProtocolFees(program, authority, pool),
+        'InvalidAmount'
+      );
+    });
+  });
+
+  describe('Batch protocol fee withdrawal', () => {
+    const pools: PoolAccounts[] = [];
+    let authorityAccounts: UserTokenAccounts;
+
+    before(async () => {
+      const { pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      );
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      pools.push(
+        pool,
+        await initializePool(program, authority, pool.tokenX, pool.tokenY, fee),
+        await initializePool(program, authority, pool.tokenX, pool.tokenY, fee)
+      );
+
+      // The pools share their mints, so all fees land in the same accounts
+      authorityAccounts = await createUserTokenAccounts(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        pool.tokenX,
+        pool.tokenY
+      );
+
+      for (const batchPool of pools) {
+        await depositLiquidity(
+          program,
+          user,
+          batchPool,
+          accounts,
+          1000 * 10 ** 6,
+          1000 * 10 ** 6
+        );
+        await updateConfig(program, authority, batchPool, {
+          protocolFeeBps: 5000,
+        });
+        await swapTokens(program, user, batchPool, accounts, true, 10 ** 8);
+        await swapTokens(program, user, batchPool, accounts, false, 10 ** 8);
+      }
+    });
+
+    it('Should reject a signer that does not own every pool', async () => {
+      await expectError(
+        withdrawProtocolFeesBatch(program, user, pools),
+        'Unauthorized'
+      );
+    });
+
+    it('Should sweep the fees of three pools in one call', async () => {
+      const before = await Promise.all(
+        pools.map((pool) => program.account.poolState.fetch(pool.poolState))
+      );
+      const owedX = before.reduce(
+        (sum, poolData) => sum + poolData.protocolFeesX.toNumber(),
+        0
+      );
+      const owedY = before.reduce(
+        (sum, poolData) => sum + poolData.protocolFeesY.toNumber(),
+        0
+      );
+      for (const poolData of before) {
+        assert.isAbove(poolData.protocolFeesX.toNumber(), 0);
+      }
+
+      const balanceXBefore = await getAccount(
+        provider.connection,
+        authorityAccounts.tokenX
+      );
+      const balanceYBefore = await getAccount(
+        provider.connection,
+        authorityAccounts.tokenY
+      );
+
+      const signature = await withdrawProtocolFeesBatch(
+        program,
+        authority,
+        pools
+      );
+
+      for (const [index, pool] of pools.entries()) {
+        const after = await program.account.poolState.fetch(pool.poolState);
+        assert.equal(after.protocolFeesX.toNumber(), 0);
+        assert.equal(after.protocolFeesY.toNumber(), 0);
+        // Only the protocol's share leaves; the reserves are untouched
+        assert.equal(
+          after.reserveX.toString(),
+          before[index].reserveX.toString()
+        );
+      }
+
+      const balanceXAfter = await getAccount(
+        provider.connection,
+        authorityAccounts.tokenX
+      );
+      const balanceYAfter = await getAccount(
+        provider.connection,
+        authorityAccounts.tokenY
+      );
+      assert.equal(balanceXAfter.amount - balanceXBefore.amount, BigInt(owedX));
+      assert.equal(balanceYAfter.amount - balanceYBefore.amount, BigInt(owedY));
+
+      const events = (await getEvents(program, signature)).filter(
+        (e) => e.name.toLowerCase() === 'feewithdrawalexecutedevent'
+      );
+      assert.equal(events.length, pools.length);
+    });
+
+    it('Should leave timelocked pools to the queued withdrawal', async () => {
+      await updateConfig(program, authority, pools[2], {
+        feeWithdrawalDelay: new anchor.BN(60),
+      });
+
+      await expectError(
+        withdrawProtocolFeesBatch(program, authority, pools),
+        'FeeWithdrawalLocked'
+      );
+    });
+  });
+
+  describe('Circuit breaker', () => {
+    const circuitBreakerBps = 2000; // 20% spot price move
+
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    async function resetCircuitBreaker(signer: Keypair) {
+      return program.methods
+        .resetCircuitBreaker()
+        .accountsPartial({ authority: signer.publicKey, config: pool.config })
+        .signers([signer])
+        .rpc();
+    }
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, { circuitBreakerBps });
+    });
+
+    it('Should let swaps within the breaker through', async () => {
+      // About a 2% move
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6
+      );
+
+      const configData = await program.account.config.fetch(pool.config);
+      assert.isFalse(configData.locked);
+    });
+
+    it('Should lock the pool without taking the input of a price-shattering swap', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const before = await program.account.poolState.fetch(pool.poolState);
+      const balanceInBefore = await getAccount(
+        provider.connection,
+        accounts.tokenX
+      );
+      const balanceOutBefore = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+
+      // Half the reserve in moves the price by more than half
+      const amountIn = 500 * 10 ** 6;
+      const signature = await swapTokens(
+        program,
+        user,
+        pool,
+        accounts,
+        true,
+        amountIn
+      );
+
+      const configData = await program.account.config.fetch(pool.config);
+      assert.isTrue(configData.locked);
+
+      // Nothing moves: the reserves, the protocol fees and the user's balances stay put
+      const after = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(after.reserveX.toString(), before.reserveX.toString());
+      assert.equal(after.reserveY.toString(), before.reserveY.toString());
+      assert.equal(
+        after.protocolFeesX.toString(),
+        before.protocolFeesX.toString()
+      );
+      const balanceInAfter = await getAccount(
+        provider.connection,
+        accounts.tokenX
+      );
+      const balanceOutAfter = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+      assert.equal(balanceInAfter.amount, balanceInBefore.amount);
+      assert.equal(balanceOutAfter.amount, balanceOutBefore.amount);
+
+      const event = (await getEvents(program, signature)).find(
+        (e) => e.name.toLowerCase() === 'circuitbreakerevent'
+      );
+      assert.isDefined(event);
+      assert.isAbove(event!.data.priceMoveBps, circuitBreakerBps);
+      assert.equal(event!.data.amountIn.toNumber(), amountIn);
+
+      await expectError(
+        swapTokens(program, user, pool, accounts, true, 10 ** 6),
+        'PoolLocked'
+      );
+    });
+
+    it('Should only let the authority reset the breaker', async () => {
+      await expectError(resetCircuitBreaker(user), 'Unauthorized');
+
+      const signature = await resetCircuitBreaker(authority);
+      const configData = await program.account.config.fetch(pool.config);
+      assert.isFalse(configData.locked);
+
+      const event = (await getEvents(program, signature)).find(
+        (e) => e.name.toLowerCase() === 'circuitbreakerresetevent'
+      );
+      assert.isDefined(event);
+      assert.isTrue(event!.data.config.equals(pool.config));
+
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 ** 6
+      );
+    });
+  });
+
+  describe('Expected fee guard', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should swap when the fee matches the quote', async () => {
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6,
+        { expectedFee: fee }
+      );
+    });
+
+    it('Should reject the swap when the fee changed after the quote', async () => {
+      const quotedFee = (await program.account.config.fetch(pool.config)).fee;
+
+      await updateConfig(program, authority, pool, { fee: 500 });
+
+      await expectError(
+        swapTokens(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          true,
+          10 * 10 ** 6,
+          { expectedFee: quotedFee }
+        ),
+        'FeeChanged'
+      );
+    });
+  });
+
+  describe('Reserve snapshot guard', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should swap when the reserves match the snapshot', async () => {
+      const poolState = await program.account.poolState.fetch(pool.poolState);
+
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6,
+        {
+          expectedReserveX: poolState.reserveX,
+          expectedReserveY: poolState.reserveY,
+          reserveToleranceBps: 10,
+        }
+      );
+    });
+
+    it('Should reject the swap when the reserves moved past the tolerance', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const snapshot = await program.account.poolState.fetch(pool.poolState);
+
+      // Another trader moves the pool between building and executing the swap
+      await swapTokens(program, user, pool, accounts, true, 100 * 10 ** 6);
+
+      await expectError(
+        swapTokens(program, user, pool, accounts, true, 10 * 10 ** 6, {
+          expectedReserveX: snapshot.reserveX,
+          expectedReserveY: snapshot.reserveY,
+          reserveToleranceBps: 10,
+        }),
+        'ReservesMoved'
+      );
+    });
+  });
+
+  describe('Transfer fee epoch guard', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'token2022',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should swap when executed in the quoted epoch', async () => {
+      const { epoch } = await provider.connection.getEpochInfo();
+
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6,
+        { expectedEpoch: new anchor.BN(epoch) }
+      );
+    });
+
+    it('Should reject the swap when the epoch differs from the quote', async () => {
+      // Localnet epochs are too long to wait out, so pin an epoch the validator is not in
+      const { epoch } = await provider.connection.getEpochInfo();
+      const quotedEpoch = new anchor.BN(epoch + 1);
+
+      await expectError(
+        swapTokens(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          true,
+          10 * 10 ** 6,
+          { expectedEpoch: quotedEpoch }
+        ),
+        'EpochChanged'
+      );
+    });
+  });
+
+  describe('Launch fee decay', () => {
+    const launchFee = 1000;
+    const targetFee = 100;
+    const decayWindow = 20; // seconds of decay left after configuring it
+
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+    let createdAt: number;
+    let decayDuration: number;
+
+    // Validator clock, read from the Clock sysvar (unix_timestamp is at offset 32)
+    async function clockTime(): Promise<number> {
+      const info = await provider.connection.getAccountInfo(
+        SYSVAR_CLOCK_PUBKEY,
+        'processed'
+      );
+      return Number(info!.data.readBigInt64LE(32));
+    }
+
+    function interpolatedFee(now: number): number {
+      const elapsed = Math.max(now - createdAt, 0);
+      if (elapsed >= decayDuration) {
+        return targetFee;
+      }
+      return Math.floor(
+        launchFee - ((launchFee - targetFee) * elapsed) / decayDuration
+      );
+    }
+
+    // The fee the program charges now, bracketed by the clock before and after reading it
+    async function expectInterpolatedFee() {
+      const before = await clockTime();
+      const fee = await program.methods
+        .getEffectiveFee(new anchor.BN(10 * 10 ** 6), user.publicKey)
+        .accountsPartial({
+          config: pool.config,
+          poolState: pool.poolState,
+          userLpToken: null,
+        })
+        .view();
+      const after = await clockTime();
+
+      assert.isAtMost(fee, interpolatedFee(before));
+      assert.isAtLeast(fee, interpolatedFee(after));
+      return fee;
+    }
+
+    const sleep = (seconds: number) =>
+      new Promise((resolve) => setTimeout(resolve, seconds * 1000));
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      // Decay is measured from creation, so leave `decayWindow` seconds of it to observe
+      createdAt = (
+        await program.account.config.fetch(pool.config)
+      ).createdAt.toNumber();
+      decayDuration = (await clockTime()) - createdAt + decayWindow;
+
+      await updateConfig(program, authority, pool, {
+        launchFee,
+        targetFee,
+        decayDuration: new anchor.BN(decayDuration),
+      });
+    });
+
+    it('Should charge close to the launch fee early in the window', async () => {
+      const fee = await expectInterpolatedFee();
+      assert.isAbove(fee, targetFee);
+    });
+
+    it('Should reject updating the fee the decay overrides', async () => {
+      await expectError(
+        updateConfig(program, authority, pool, { fee: 50 }),
+        'FeeDecayConfigured'
+      );
+    });
+
+    it('Should decay the fee linearly through the window', async () => {
+      const early = await expectInterpolatedFee();
+      await sleep(decayWindow / 4);
+      const middle = await expectInterpolatedFee();
+
+      assert.isBelow(middle, early);
+      assert.isAbove(middle, targetFee);
+    });
+
+    it('Should settle on the target fee after the window', async () => {
+      await sleep(decayWindow);
+      assert.equal(await expectInterpolatedFee(), targetFee);
+
+      // The swap itself is charged the target fee too
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6
+      );
+    });
+  });
+
+  describe('Vault coverage grace', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'token2022',
+        'token2022',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should tolerate one unit of rounding by default', async () => {
+      const config = await program.account.config.fetch(pool.config);
+      assert.equal(config.vaultCoverageGrace.toNumber(), 1);
+    });
+
+    it('Should keep the output vault covering the reserve with a strict check', async () => {
+      await updateConfig(program, authority, pool, {
+        vaultCoverageGrace: new anchor.BN(0),
+      });
+
+      for (const xToY of [true, false]) {
+        await swapTokens(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          xToY,
+          10 * 10 ** 6
+        );
+      }
+
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      const vaultX = await getAccount(
+        provider.connection,
+        pool.vaultX,
+        undefined,
+        pool.tokenX.tokenProgram
+      );
+      const vaultY = await getAccount(
+        provider.connection,
+        pool.vaultY,
+        undefined,
+        pool.tokenY.tokenProgram
+      );
+      assert.isTrue(vaultX.amount >= BigInt(poolData.reserveX.toString()));
+      assert.isTrue(vaultY.amount >= BigInt(poolData.reserveY.toString()));
+    });
+
+    it('Should reject a grace above the maximum', async () => {
+      await expectError(
+        updateConfig(program, authority, pool, {
+          vaultCoverageGrace: new anchor.BN(11),
+        }),
+        'InvalidAmount'
+      );
+    });
+  });
+
+  describe('Effective fee floor', () => {
+    const floorBps = 50; // 0.5%
+
+    let marketMaker: Keypair;
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    async function swapAndCheckFee(
+      swapper: Keypair,
+      expectedFeeBps: number,
+      options: { userLpToken?: PublicKey } = {}
+    ) {
+      const accounts = userAccounts.get(swapper.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+
+      const amountIn = BigInt(10 * 10 ** 6);
+      await swapTokens(
+        program,
+        swapper,
+        pool,
+        accounts,
+        true,
+        Number(amountIn),
+        options
+      );
+
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+      const reserveIn = BigInt(poolBefore.reserveX.toString());
+      const reserveOut = BigInt(poolBefore.reserveY.toString());
+      const feeAdjustedIn = amountIn * BigInt(10000 - expectedFeeBps);
+      const expectedOut =
+        (feeAdjustedIn * reserveOut) /
+        (reserveIn * BigInt(10000) + feeAdjustedIn);
+
+      assert.equal(balanceAfter.amount - balanceBefore.amount, expectedOut);
+    }
+
+    before(async () => {
+      marketMaker = Keypair.generate();
+      await airdrop(provider.connection, [marketMaker.publicKey]);
+
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey, marketMaker.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+
+      // A full LP discount and a free market maker would both swap at zero fee
+      await updateConfig(program, authority, pool, {
+        lpDiscountThreshold: new anchor.BN(1),
+        lpDiscountBps: 10000,
+        privilegedMm: marketMaker.publicKey,
+        mmFeeBps: 0,
+        minEffectiveFeeBps: floorBps,
+      });
+    });
+
+    it('Should raise a fully discounted LP holder to the floor', async () => {
+      await swapAndCheckFee(user, floorBps, {
+        userLpToken: getLpTokenAddress(pool, user.publicKey),
+      });
+    });
+
+    it('Should raise the privileged market maker to the floor', async () => {
+      await swapAndCheckFee(marketMaker, floorBps);
+    });
+
+    it('Should leave fees above the floor unchanged', async () => {
+      await swapAndCheckFee(user, fee);
+    });
+
+    it('Should reject a floor above the maximum fee', async () => {
+      await expectError(
+        updateConfig(program, authority, pool, { minEffectiveFeeBps: 1001 }),
+        'InvalidAmount'
+      );
+    });
+  });
+
+  describe('Minimum input by decimals', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      // 0-decimal X against 9-decimal Y, at 10^6 raw units of Y per whole X
+      const tokenX = await createLegacyToken(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        0
+      );
+      const tokenY = await createLegacyToken(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        9
+      );
+
+      ({ pool, userAccounts } = await setupPoolWithTokens(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        tokenX,
+        tokenY,
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000,
+        10 ** 9
+      );
+    });
+
+    it('Should reject a 9-decimal input worth less than a unit of the 0-decimal output', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+
+      await expectError(
+        swapTokens(program, user, pool, accounts, false, 10 ** 6 - 1),
+        'InsufficientInputAmount'
+      );
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(
+        poolAfter.reserveY.toString(),
+        poolBefore.reserveY.toString()
+      );
+    });
+
+    it('Should accept a 9-decimal input buying a unit of the 0-decimal output', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+
+      await swapTokens(program, user, pool, accounts, false, 2 * 10 ** 6);
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(
+        poolAfter.reserveY.sub(poolBefore.reserveY).toNumber(),
+        2 * 10 ** 6
+      );
+      assert.equal(poolBefore.reserveX.sub(poolAfter.reserveX).toNumber(), 1);
+    });
+
+    it('Should accept a single unit of a 0-decimal input', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+
+      // One whole X buys most of 10^6 raw units of Y
+      await swapTokens(program, user, pool, accounts, true, 1);
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(poolAfter.reserveX.sub(poolBefore.reserveX).toNumber(), 1);
+    });
+  });
+
+  describe('Slippage guard', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should report a swap paying out too little as a swap slippage', async () => {
+      // Fees and price impact keep the output below the input on a 1:1 pool
+      await expectError(
+        swapTokens(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          true,
+          10 * 10 ** 6,
+          { minAmountOut: new anchor.BN(10 * 10 ** 6) }
+        ),
+        'SwapSlippageExceeded'
+      );
+    });
+  });
+
+  describe('Zero output rounding', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      // Highest fee a pool may charge, 10%
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        1000
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should reject an input whose fee-adjusted output rounds to zero', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenX
+      );
+
+      // 1 unit at 90% after the fee buys 0.9 of a unit on a 1:1 pool
+      await expectError(
+        swapTokens(program, user, pool, accounts, true, 1),
+        'SwapOutputRoundsToZero'
+      );
+
+      const poolAfter = await program.account.poolState.fetch(pool.poolState);
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenX
+      );
+      assert.equal(
+        poolAfter.reserveX.toString(),
+        poolBefore.reserveX.toString()
+      );
+      assert.equal(balanceAfter.amount, balanceBefore.amount);
+    });
+
+    it('Should still accept the smallest input producing a unit of output', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+
+      await swapTokens(program, user, pool, accounts, true, 2);
+
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(
+        poolData.reserveY.toString(),
+        (1000 * 10 ** 6 - 1).toString()
+      );
+    });
+  });
+});
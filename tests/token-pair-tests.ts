@@ -33,6 +33,7 @@ import {
   TokenInfo,
   UserTokenAccounts,
 } from './helpers/token-helper';
+import { NO_DEPOSIT_GUARDS, NO_SWAP_GUARDS } from './helpers/pool-helper';
 
 describe('Token Pair Tests - All Combinations', () => {
   const provider = anchor.AnchorProvider.env();
@@ -166,7 +167,7 @@ describe('Token Pair Tests - All Combinations', () => {
 
         // Initialize pool with dual token programs
         const tx = await program.methods
-          .initializePool(seed, fee, null)
+          .initializePool(seed, fee, null, false, 2, 10000)
           .accountsPartial({
             authority: authority.publicKey,
             config,
@@ -175,8 +176,11 @@ describe('Token Pair Tests - All Combinations', () => {
             mintY: tokenY.mint,
             lpMint,
             poolAuthority,
+            vaultAuthority: poolAuthority,
             vaultX,
             vaultY,
+            mintXPoolIndex: null,
+            mintYPoolIndex: null,
             tokenProgramX: tokenX.tokenProgram,
             tokenProgramY: tokenY.tokenProgram,
             tokenProgramLp: TOKEN_PROGRAM_ID, // Use legacy token for LP tokens
@@ -232,12 +236,13 @@ describe('Token Pair Tests - All Combinations', () => {
 
         // Execute deposit
         const tx = await program.methods
-          .deposit(amountX, amountY, minLpOut)
+          .deposit(amountX, amountY, minLpOut, NO_DEPOSIT_GUARDS, false)
           .accountsPartial({
             user: user1.publicKey,
             config,
             poolState,
             poolAuthority,
+            vaultAuthority: poolAuthority,
             mintX: tokenX.mint,
             mintY: tokenY.mint,
             vaultX,
@@ -320,18 +325,20 @@ describe('Token Pair Tests - All Combinations', () => {
 
         // Execute swap
         const tx = await program.methods
-          .swap(amountIn, minAmountOut)
+          .swap(amountIn, minAmountOut, NO_SWAP_GUARDS)
           .accountsPartial({
             user: user2.publicKey,
             config,
             poolState,
             poolAuthority,
+            vaultAuthority: poolAuthority,
             mintIn: tokenX.mint,
             mintOut: tokenY.mint,
             vaultIn: vaultX,
             vaultOut: vaultY,
             userTokenIn: user2Accounts.tokenX,
             userTokenOut: user2Accounts.tokenY,
+            userLpToken: null,
             tokenProgramX: tokenX.tokenProgram,
             tokenProgramY: tokenY.tokenProgram,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -400,18 +407,20 @@ describe('Token Pair Tests - All Combinations', () => {
 
         // Execute swap
         const tx = await program.methods
-          .swap(amountIn, minAmountOut)
+          .swap(amountIn, minAmountOut, NO_SWAP_GUARDS)
           .accountsPartial({
             user: user2.publicKey,
             config,
             poolState,
             poolAuthority,
+            vaultAuthority: poolAuthority,
             mintIn: tokenY.mint,
             mintOut: tokenX.mint,
             vaultIn: vaultY,
             vaultOut: vaultX,
             userTokenIn: user2Accounts.tokenY,
             userTokenOut: user2Accounts.tokenX,
+            userLpToken: null,
             tokenProgramX: tokenX.tokenProgram,
             tokenProgramY: tokenY.tokenProgram,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -480,12 +489,13 @@ describe('Token Pair Tests - All Combinations', () => {
 
         // Execute withdrawal
         const tx = await program.methods
-          .withdraw(lpAmount, minAmountX, minAmountY)
+          .withdraw(lpAmount, minAmountX, minAmountY, false, false)
           .accountsPartial({
             user: user1.publicKey,
             config,
             poolState,
             poolAuthority,
+            vaultAuthority: poolAuthority,
             mintX: tokenX.mint,
             mintY: tokenY.mint,
             vaultX,
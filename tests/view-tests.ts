@@ -0,0 +1,933 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { getAccount, transfer } from '@solana/spl-token';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import {
+  createToken2022WithTransferFee,
+  UserTokenAccounts,
+} from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  expectError,
+  getLpTokenAddress,
+  setupPool,
+  setupPoolWithTokens,
+  swapTokens,
+  updateConfig,
+  withdrawLiquidity,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Read-only Instruction Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+  const pricePrecision = 10 ** 9;
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  let pool: PoolAccounts;
+  let userAccounts: Map<string, UserTokenAccounts>;
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+
+    ({ pool, userAccounts } = await setupPool(
+      provider.connection,
+      program,
+      authority,
+      [user.publicKey],
+      'legacy',
+      'legacy',
+      fee
+    ));
+  });
+
+  describe('get_tvl', () => {
+    async function getTvl() {
+      return program.methods
+        .getTvl()
+        .accountsPartial({
+          config: pool.config,
+          poolState: pool.poolState,
+          mintX: pool.tokenX.mint,
+          mintY: pool.tokenY.mint,
+        })
+        .view();
+    }
+
+    it('Should return zeros for an empty pool', async () => {
+      const tvl = await getTvl();
+
+      assert.equal(tvl.reserveX.toNumber(), 0);
+      assert.equal(tvl.reserveY.toNumber(), 0);
+      assert.equal(tvl.priceXInY.toNumber(), 0);
+    });
+
+    it('Should match the pool accounts for a seeded pool', async () => {
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        200 * 10 ** 6
+      );
+
+      const tvl = await getTvl();
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+
+      assert.equal(tvl.reserveX.toString(), poolData.reserveX.toString());
+      assert.equal(tvl.reserveY.toString(), poolData.reserveY.toString());
+      assert.equal(tvl.decimalsX, pool.tokenX.decimals);
+      assert.equal(tvl.decimalsY, pool.tokenY.decimals);
+      assert.equal(tvl.priceXInY.toString(), (2 * pricePrecision).toString());
+    });
+  });
+
+  describe('get_effective_fee', () => {
+    const discountBps = 5000;
+    const amountIn = 10 * 10 ** 6;
+
+    async function getEffectiveFee(userLpToken: PublicKey | null) {
+      return program.methods
+        .getEffectiveFee(new anchor.BN(amountIn), user.publicKey)
+        .accountsPartial({
+          config: pool.config,
+          poolState: pool.poolState,
+          userLpToken,
+        })
+        .view();
+    }
+
+    before(async () => {
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        200 * 10 ** 6
+      );
+      await updateConfig(program, authority, pool, {
+        lpDiscountThreshold: new anchor.BN(1),
+        lpDiscountBps: discountBps,
+      });
+    });
+
+    it('Should report the base fee without LP holdings', async () => {
+      assert.equal(await getEffectiveFee(null), fee);
+    });
+
+    it('Should report the fee an LP holder actually pays on a swap', async () => {
+      const userLpToken = getLpTokenAddress(pool, user.publicKey);
+      const reportedFee = await getEffectiveFee(userLpToken);
+      assert.equal(reportedFee, (fee * (10000 - discountBps)) / 10000);
+
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const poolBefore = await program.account.poolState.fetch(pool.poolState);
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+
+      await swapTokens(program, user, pool, accounts, true, amountIn, {
+        userLpToken,
+      });
+
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenY
+      );
+      const reserveIn = BigInt(poolBefore.reserveX.toString());
+      const reserveOut = BigInt(poolBefore.reserveY.toString());
+      const feeAdjustedIn = BigInt(amountIn) * BigInt(10000 - reportedFee);
+      const expectedOut =
+        (feeAdjustedIn * reserveOut) /
+        (reserveIn * BigInt(10000) + feeAdjustedIn);
+
+      assert.equal(balanceAfter.amount - balanceBefore.amount, expectedOut);
+    });
+  });
+
+  describe('check_pool_health', () => {
+    async function checkPoolHealth() {
+      return program.methods
+        .checkPoolHealth()
+        .accountsPartial({
+          config: pool.config,
+          poolState: pool.poolState,
+          vaultX: pool.vaultX,
+          vaultY: pool.vaultY,
+        })
+        .view();
+    }
+
+    it('Should report vault minus reserve deltas as healthy', async () => {
+      const health = await checkPoolHealth();
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      const vaultX = await getAccount(provider.connection, pool.vaultX);
+      const vaultY = await getAccount(provider.connection, pool.vaultY);
+
+      assert.equal(
+        health.deltaX.toString(),
+        (vaultX.amount - BigInt(poolData.reserveX.toString())).toString()
+      );
+      assert.equal(
+        health.deltaY.toString(),
+        (vaultY.amount - BigInt(poolData.reserveY.toString())).toString()
+      );
+      assert.isTrue(health.healthy);
+    });
+
+    it('Should report a vault desynced from its reserve', async () => {
+      const before = await checkPoolHealth();
+
+      // Tokens sent straight to the vault bypass the reserve accounting
+      const donation = 5 * 10 ** 6;
+      await transfer(
+        provider.connection,
+        user,
+        userAccounts.get(user.publicKey.toString())!.tokenX,
+        pool.vaultX,
+        user,
+        donation
+      );
+
+      const after = await checkPoolHealth();
+      assert.equal(
+        after.deltaX.sub(before.deltaX).toNumber(),
+        donation
+      );
+      assert.equal(after.deltaY.toString(), before.deltaY.toString());
+      assert.isTrue(after.healthy);
+    });
+  });
+
+  describe('is_solvent', () => {
+    async function isSolvent() {
+      return program.methods
+        .isSolvent()
+        .accountsPartial({
+          config: pool.config,
+          poolState: pool.poolState,
+          vaultX: pool.vaultX,
+          vaultY: pool.vaultY,
+        })
+        .view();
+    }
+
+    it('Should report a healthy pool as solvent', async () => {
+      const solvency = await isSolvent();
+
+      assert.isTrue(solvency.solvent);
+      assert.equal(solvency.shortfallX.toNumber(), 0);
+      assert.equal(solvency.shortfallY.toNumber(), 0);
+    });
+
+    it('Should stay solvent with protocol fees accrued in the vaults', async () => {
+      await updateConfig(program, authority, pool, { protocolFeeBps: 5000 });
+      await swapTokens(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        true,
+        10 * 10 ** 6
+      );
+      await updateConfig(program, authority, pool, { protocolFeeBps: 0 });
+
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      const vaultX = await getAccount(provider.connection, pool.vaultX);
+      assert.isAbove(poolData.protocolFeesX.toNumber(), 0);
+      assert.isTrue(
+        vaultX.amount >=
+          BigInt(poolData.reserveX.add(poolData.protocolFeesX).toString())
+      );
+
+      const solvency = await isSolvent();
+      assert.isTrue(solvency.solvent);
+      assert.equal(solvency.shortfallX.toNumber(), 0);
+    });
+  });
+
+  describe('get_fee_schedule', () => {
+    it('Should return every fee component stored on the config', async () => {
+      const marketMaker = Keypair.generate().publicKey;
+      await updateConfig(program, authority, pool, {
+        imbalanceFeeBps: 150,
+        protocolFeeBps: 2000,
+        protocolFeeMinAmount: new anchor.BN(1000),
+        launchFee: 800,
+        targetFee: 200,
+        privilegedMm: marketMaker,
+        mmFeeBps: 10,
+        minEffectiveFeeBps: 5,
+      });
+
+      const schedule = await program.methods
+        .getFeeSchedule()
+        .accountsPartial({ config: pool.config })
+        .view();
+      const configData = await program.account.config.fetch(pool.config);
+
+      assert.equal(schedule.fee, configData.fee);
+      // No launch decay configured, so the configured fee applies
+      assert.equal(schedule.currentBaseFee, configData.fee);
+      assert.equal(schedule.launchFee, 800);
+      assert.equal(schedule.targetFee, 200);
+      assert.equal(
+        schedule.createdAt.toString(),
+        configData.createdAt.toString()
+      );
+      assert.equal(
+        schedule.decayDuration.toString(),
+        configData.decayDuration.toString()
+      );
+      assert.equal(schedule.imbalanceFeeBps, 150);
+      assert.equal(schedule.protocolFeeBps, 2000);
+      assert.equal(schedule.protocolFeeMinAmount.toNumber(), 1000);
+      assert.equal(
+        schedule.lpDiscountThreshold.toString(),
+        configData.lpDiscountThreshold.toString()
+      );
+      assert.equal(schedule.lpDiscountBps, configData.lpDiscountBps);
+      assert.isTrue(schedule.privilegedMm.equals(marketMaker));
+      assert.equal(schedule.mmFeeBps, 10);
+      assert.equal(schedule.minEffectiveFeeBps, 5);
+    });
+  });
+
+  describe('get_config_compact', () => {
+    it("Should return the config's core trading fields", async () => {
+      const compact = await program.methods
+        .getConfigCompact()
+        .accountsPartial({ config: pool.config })
+        .view();
+      const configData = await program.account.config.fetch(pool.config);
+
+      assert.equal(compact.seed.toString(), configData.seed.toString());
+      assert.isTrue(compact.mintX.equals(configData.mintX));
+      assert.isTrue(compact.mintY.equals(configData.mintY));
+      assert.equal(compact.fee, configData.fee);
+      assert.equal(compact.locked, configData.locked);
+    });
+  });
+
+  describe('get_fee_stats', () => {
+    let feePool: PoolAccounts;
+    let feeUserAccounts: Map<string, UserTokenAccounts>;
+
+    async function getFeeStats() {
+      return program.methods
+        .getFeeStats()
+        .accountsPartial({
+          config: feePool.config,
+          poolState: feePool.poolState,
+        })
+        .view();
+    }
+
+    before(async () => {
+      ({ pool: feePool, userAccounts: feeUserAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        feePool,
+        feeUserAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should start with no fees accrued', async () => {
+      const stats = await getFeeStats();
+
+      assert.equal(stats.cumulativeFeeX.toString(), '0');
+      assert.equal(stats.cumulativeFeeY.toString(), '0');
+    });
+
+    it('Should accrue the LP fee of each swap in its input token', async () => {
+      const accounts = feeUserAccounts.get(user.publicKey.toString())!;
+      const swaps: [boolean, number][] = [
+        [true, 10 * 10 ** 6],
+        [true, 7 * 10 ** 6],
+        [false, 5 * 10 ** 6],
+      ];
+
+      let expectedX = 0;
+      let expectedY = 0;
+      for (const [xToY, amountIn] of swaps) {
+        await swapTokens(program, user, feePool, accounts, xToY, amountIn);
+        const lpFee = Math.floor((amountIn * fee) / 10000);
+        if (xToY) {
+          expectedX += lpFee;
+        } else {
+          expectedY += lpFee;
+        }
+      }
+
+      const stats = await getFeeStats();
+      const configData = await program.account.config.fetch(feePool.config);
+      const now = Math.floor(Date.now() / 1000);
+
+      assert.equal(stats.cumulativeFeeX.toString(), expectedX.toString());
+      assert.equal(stats.cumulativeFeeY.toString(), expectedY.toString());
+      assert.approximately(
+        stats.elapsed.toNumber(),
+        now - configData.createdAt.toNumber(),
+        300
+      );
+    });
+  });
+
+  describe('cumulative transfer fees', () => {
+    let feePool: PoolAccounts;
+    let accounts: UserTokenAccounts;
+
+    // Transfer fees are withheld out of the destination's balance, so whatever
+    // leaves the user + vault total of a token is what the pool should count
+    async function heldAmount(tokenX: boolean): Promise<bigint> {
+      const [token, userToken, vault] = tokenX
+        ? [feePool.tokenX, accounts.tokenX, feePool.vaultX]
+        : [feePool.tokenY, accounts.tokenY, feePool.vaultY];
+      const [userAccount, vaultAccount] = await Promise.all([
+        getAccount(
+          provider.connection,
+          userToken,
+          undefined,
+          token.tokenProgram
+        ),
+        getAccount(provider.connection, vault, undefined, token.tokenProgram),
+      ]);
+      return userAccount.amount + vaultAccount.amount;
+    }
+
+    before(async () => {
+      let feeUserAccounts: Map<string, UserTokenAccounts>;
+      ({ pool: feePool, userAccounts: feeUserAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'token2022',
+        'token2022',
+        fee
+      ));
+      accounts = feeUserAccounts.get(user.publicKey.toString())!;
+    });
+
+    it('Should count the transfer fees of deposits, swaps and withdrawals', async () => {
+      const heldXBefore = await heldAmount(true);
+      const heldYBefore = await heldAmount(false);
+
+      await depositLiquidity(
+        program,
+        user,
+        feePool,
+        accounts,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+      const afterDeposit = await program.account.poolState.fetch(
+        feePool.poolState
+      );
+      assert.isTrue(afterDeposit.cumulativeTransferFeeX.gtn(0));
+      assert.isTrue(afterDeposit.cumulativeTransferFeeY.gtn(0));
+
+      await swapTokens(program, user, feePool, accounts, true, 10 * 10 ** 6);
+      await swapTokens(program, user, feePool, accounts, false, 5 * 10 ** 6);
+      const lpBalance = await getAccount(
+        provider.connection,
+        getLpTokenAddress(feePool, user.publicKey)
+      );
+      await withdrawLiquidity(
+        program,
+        user,
+        feePool,
+        accounts,
+        new anchor.BN((lpBalance.amount / 2n).toString())
+      );
+
+      const stats = await program.methods
+        .getFeeStats()
+        .accountsPartial({
+          config: feePool.config,
+          poolState: feePool.poolState,
+        })
+        .view();
+
+      assert.isTrue(
+        stats.cumulativeTransferFeeX.gt(afterDeposit.cumulativeTransferFeeX)
+      );
+      assert.isTrue(
+        stats.cumulativeTransferFeeY.gt(afterDeposit.cumulativeTransferFeeY)
+      );
+      assert.equal(
+        stats.cumulativeTransferFeeX.toString(),
+        (heldXBefore - (await heldAmount(true))).toString()
+      );
+      assert.equal(
+        stats.cumulativeTransferFeeY.toString(),
+        (heldYBefore - (await heldAmount(false))).toString()
+      );
+    });
+  });
+
+  describe('get_apr', () => {
+    let aprPool: PoolAccounts;
+    let aprUserAccounts: Map<string, UserTokenAccounts>;
+
+    async function getApr() {
+      return program.methods
+        .getApr()
+        .accountsPartial({
+          config: aprPool.config,
+          poolState: aprPool.poolState,
+        })
+        .view();
+    }
+
+    before(async () => {
+      ({ pool: aprPool, userAccounts: aprUserAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        aprPool,
+        aprUserAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should report no yield before any fee is earned', async () => {
+      const apr = await getApr();
+
+      assert.equal(apr.aprXBps.toString(), '0');
+      assert.equal(apr.aprYBps.toString(), '0');
+    });
+
+    it('Should annualize the fees earned per token against its reserve', async () => {
+      const accounts = aprUserAccounts.get(user.publicKey.toString())!;
+      await swapTokens(program, user, aprPool, accounts, true, 20 * 10 ** 6);
+      await swapTokens(program, user, aprPool, accounts, false, 10 * 10 ** 6);
+
+      const apr = await getApr();
+      const poolState = await program.account.poolState.fetch(
+        aprPool.poolState
+      );
+      const secondsPerYear = BigInt(365 * 24 * 60 * 60);
+      const elapsed = BigInt(apr.elapsed.toString());
+      const expectedApr = (cumulativeFee: anchor.BN, reserve: anchor.BN) =>
+        elapsed > 0n
+          ? (BigInt(cumulativeFee.toString()) * 10000n * secondsPerYear) /
+            (BigInt(reserve.toString()) * elapsed)
+          : 0n;
+
+      assert.isTrue(apr.aprXBps.gtn(0));
+      assert.isTrue(apr.aprYBps.gtn(0));
+      // X earned twice the fees of Y on comparable reserves
+      assert.isTrue(apr.aprXBps.gt(apr.aprYBps));
+      assert.equal(
+        apr.aprXBps.toString(),
+        expectedApr(poolState.cumulativeFeeX, poolState.reserveX).toString()
+      );
+      assert.equal(
+        apr.aprYBps.toString(),
+        expectedApr(poolState.cumulativeFeeY, poolState.reserveY).toString()
+      );
+    });
+  });
+
+  describe('get_price_impact', () => {
+    let impactPool: PoolAccounts;
+
+    async function getPriceImpact(amountIn: anchor.BN, xToY: boolean) {
+      const [mintIn, mintOut] = xToY
+        ? [impactPool.tokenX.mint, impactPool.tokenY.mint]
+        : [impactPool.tokenY.mint, impactPool.tokenX.mint];
+
+      return program.methods
+        .getPriceImpact(amountIn)
+        .accountsPartial({
+          config: impactPool.config,
+          poolState: impactPool.poolState,
+          mintIn,
+          mintOut,
+        })
+        .view();
+    }
+
+    before(async () => {
+      let impactUserAccounts: Map<string, UserTokenAccounts>;
+      ({ pool: impactPool, userAccounts: impactUserAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        impactPool,
+        impactUserAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        2000 * 10 ** 6
+      );
+    });
+
+    it('Should match the mid vs execution price at several sizes', async () => {
+      const poolData = await program.account.poolState.fetch(
+        impactPool.poolState
+      );
+
+      for (const xToY of [true, false]) {
+        const [reserveIn, reserveOut] = (
+          xToY
+            ? [poolData.reserveX, poolData.reserveY]
+            : [poolData.reserveY, poolData.reserveX]
+        ).map((reserve) => reserve.toNumber());
+
+        for (const amountIn of [10 ** 6, 50 * 10 ** 6, 500 * 10 ** 6]) {
+          const amountOut = (amountIn * reserveOut) / (reserveIn + amountIn);
+          const midPrice = reserveOut / reserveIn;
+          const executionPrice = amountOut / amountIn;
+          const expected = ((midPrice - executionPrice) / midPrice) * 10000;
+
+          const impact = await getPriceImpact(new anchor.BN(amountIn), xToY);
+          assert.approximately(impact, expected, 1);
+        }
+      }
+    });
+
+    it('Should report no impact for dust and near-total impact for huge swaps', async () => {
+      assert.equal(await getPriceImpact(new anchor.BN(1), true), 0);
+
+      const huge = await getPriceImpact(
+        new anchor.BN('18446744073709551615'),
+        true
+      );
+      assert.isAtLeast(huge, 9999);
+      assert.isAtMost(huge, 10000);
+    });
+  });
+
+  describe('quote_withdraw_exact', () => {
+    let quotePool: PoolAccounts;
+    let quoteUserAccounts: Map<string, UserTokenAccounts>;
+
+    function quoteWithdrawExact(amountX: anchor.BN, amountY: anchor.BN) {
+      return program.methods
+        .quoteWithdrawExact(amountX, amountY)
+        .accountsPartial({
+          config: quotePool.config,
+          poolState: quotePool.poolState,
+        })
+        .view();
+    }
+
+    before(async () => {
+      ({ pool: quotePool, userAccounts: quoteUserAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        quotePool,
+        quoteUserAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        2000 * 10 ** 6
+      );
+    });
+
+    it('Should quote the LP a withdrawal of the exact amounts burns', async () => {
+      const amountX = new anchor.BN(100 * 10 ** 6);
+      const amountY = new anchor.BN(200 * 10 ** 6);
+      const accounts = quoteUserAccounts.get(user.publicKey.toString())!;
+
+      const lpAmount = await quoteWithdrawExact(amountX, amountY);
+
+      const lpToken = getLpTokenAddress(quotePool, user.publicKey);
+      const [xBefore, yBefore, lpBefore] = await Promise.all(
+        [accounts.tokenX, accounts.tokenY, lpToken].map((account) =>
+          getAccount(provider.connection, account)
+        )
+      );
+      await withdrawLiquidity(program, user, quotePool, accounts, lpAmount);
+      const [xAfter, yAfter, lpAfter] = await Promise.all(
+        [accounts.tokenX, accounts.tokenY, lpToken].map((account) =>
+          getAccount(provider.connection, account)
+        )
+      );
+
+      assert.equal(
+        (lpBefore.amount - lpAfter.amount).toString(),
+        lpAmount.toString()
+      );
+      assert.equal(
+        (xAfter.amount - xBefore.amount).toString(),
+        amountX.toString()
+      );
+      assert.equal(
+        (yAfter.amount - yBefore.amount).toString(),
+        amountY.toString()
+      );
+    });
+
+    it('Should reject amounts off the reserve ratio', async () => {
+      await expectError(
+        quoteWithdrawExact(
+          new anchor.BN(100 * 10 ** 6),
+          new anchor.BN(250 * 10 ** 6)
+        ),
+        'NonProportionalAmounts'
+      );
+    });
+  });
+
+  describe('get_pool_accounts', () => {
+    it('Should return the keys derived client-side from the seed', async () => {
+      const addresses = await program.methods
+        .getPoolAccounts()
+        .accountsPartial({
+          config: pool.config,
+          poolState: pool.poolState,
+        })
+        .view();
+
+      // `pool` comes from derivePoolAccounts: PDAs from the seeds, vaults as ATAs of the
+      // vault authority
+      const expected = {
+        config: pool.config,
+        poolState: pool.poolState,
+        poolAuthority: pool.poolAuthority,
+        vaultAuthority: pool.vaultAuthority,
+        lpMint: pool.lpMint,
+        mintX: pool.tokenX.mint,
+        mintY: pool.tokenY.mint,
+        vaultX: pool.vaultX,
+        vaultY: pool.vaultY,
+      };
+      for (const [name, key] of Object.entries(expected)) {
+        assert.isTrue(
+          addresses[name].equals(key),
+          `${name}: ${addresses[name]} != ${key}`
+        );
+      }
+    });
+  });
+
+  describe('cheapest_direction', () => {
+    let feePool: PoolAccounts;
+    let feeUserAccounts: Map<string, UserTokenAccounts>;
+
+    async function cheapestDirection(amount: anchor.BN) {
+      return program.methods
+        .cheapestDirection(amount)
+        .accountsPartial({
+          config: feePool.config,
+          poolState: feePool.poolState,
+          mintX: feePool.tokenX.mint,
+          mintY: feePool.tokenY.mint,
+        })
+        .view();
+    }
+
+    before(async () => {
+      // 0.1% transfer fee on X, 5% on Y, neither capped in practice
+      const tokenX = await createToken2022WithTransferFee(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        authority.publicKey,
+        authority.publicKey,
+        10,
+        BigInt(10 ** 15)
+      );
+      const tokenY = await createToken2022WithTransferFee(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        authority.publicKey,
+        authority.publicKey,
+        500,
+        BigInt(10 ** 15)
+      );
+
+      ({ pool: feePool, userAccounts: feeUserAccounts } =
+        await setupPoolWithTokens(
+          provider.connection,
+          program,
+          authority,
+          [user.publicKey],
+          tokenX,
+          tokenY,
+          fee
+        ));
+      await depositLiquidity(
+        program,
+        user,
+        feePool,
+        feeUserAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        1000 * 10 ** 6
+      );
+    });
+
+    it('Should pick the leg that pays the large transfer fee only once', async () => {
+      // X -> Y pays Y's 5% on the way out of the vault and again on receipt, Y -> X pays
+      // it once on the way in
+      const quote = await cheapestDirection(new anchor.BN(10 * 10 ** 6));
+
+      assert.isFalse(quote.xToY);
+      assert.isBelow(quote.feeDragBpsYToX, quote.feeDragBpsXToY);
+      assert.isAbove(quote.netOutYToX.toNumber(), 0);
+      assert.isAbove(quote.netOutXToY.toNumber(), 0);
+    });
+
+    it('Should quote what the chosen swap actually delivers', async () => {
+      const amount = 10 * 10 ** 6;
+      const quote = await cheapestDirection(new anchor.BN(amount));
+      const accounts = feeUserAccounts.get(user.publicKey.toString())!;
+
+      const balanceBefore = await getAccount(
+        provider.connection,
+        accounts.tokenX,
+        undefined,
+        feePool.tokenX.tokenProgram
+      );
+      await swapTokens(program, user, feePool, accounts, false, amount);
+      const balanceAfter = await getAccount(
+        provider.connection,
+        accounts.tokenX,
+        undefined,
+        feePool.tokenX.tokenProgram
+      );
+
+      assert.equal(
+        (balanceAfter.amount - balanceBefore.amount).toString(),
+        quote.netOutYToX.toString()
+      );
+    });
+  });
+
+  describe('snapshot', () => {
+    let snapshotPool: PoolAccounts;
+    let snapshotUserAccounts: Map<string, UserTokenAccounts>;
+
+    async function takeSnapshot(): Promise<PublicKey> {
+      const poolSnapshot = Keypair.generate();
+      await program.methods
+        .snapshot()
+        .accountsPartial({
+          payer: user.publicKey,
+          config: snapshotPool.config,
+          poolState: snapshotPool.poolState,
+          poolSnapshot: poolSnapshot.publicKey,
+        })
+        .signers([user, poolSnapshot])
+        .rpc();
+      return poolSnapshot.publicKey;
+    }
+
+    before(async () => {
+      ({ pool: snapshotPool, userAccounts: snapshotUserAccounts } =
+        await setupPool(
+          provider.connection,
+          program,
+          authority,
+          [user.publicKey],
+          'legacy',
+          'legacy',
+          fee
+        ));
+      await depositLiquidity(
+        program,
+        user,
+        snapshotPool,
+        snapshotUserAccounts.get(user.publicKey.toString())!,
+        1000 * 10 ** 6,
+        2000 * 10 ** 6
+      );
+    });
+
+    it('Should record the pool state at each snapshot', async () => {
+      const stateBefore = await program.account.poolState.fetch(
+        snapshotPool.poolState
+      );
+      const first = await takeSnapshot();
+
+      await swapTokens(
+        program,
+        user,
+        snapshotPool,
+        snapshotUserAccounts.get(user.publicKey.toString())!,
+        true,
+        100 * 10 ** 6
+      );
+      const stateAfter = await program.account.poolState.fetch(
+        snapshotPool.poolState
+      );
+      const second = await takeSnapshot();
+
+      const [firstData, secondData] = await Promise.all([
+        program.account.poolSnapshot.fetch(first),
+        program.account.poolSnapshot.fetch(second),
+      ]);
+
+      for (const [snapshot, state] of [
+        [firstData, stateBefore],
+        [secondData, stateAfter],
+      ] as const) {
+        assert.isTrue(snapshot.config.equals(snapshotPool.config));
+        assert.equal(snapshot.reserveX.toString(), state.reserveX.toString());
+        assert.equal(snapshot.reserveY.toString(), state.reserveY.toString());
+        assert.equal(snapshot.lpSupply.toString(), state.lpSupply.toString());
+      }
+
+      // The swap moved the reserves between the two
+      assert.notEqual(
+        firstData.reserveX.toString(),
+        secondData.reserveX.toString()
+      );
+      assert.isTrue(secondData.slot.gt(firstData.slot));
+      assert.isTrue(secondData.timestamp.gte(firstData.timestamp));
+    });
+  });
+});
@@ -0,0 +1,314 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Token2022Amm } from '../target/types/token2022_amm';
+import { getAccount } from '@solana/spl-token';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+
+import {
+  createUserTokenAccounts,
+  UserTokenAccounts,
+} from './helpers/token-helper';
+import {
+  airdrop,
+  depositLiquidity,
+  expectError,
+  getLpTokenAddress,
+  setupPool,
+  updateConfig,
+  withdrawLiquidity,
+  withdrawSplit,
+  PoolAccounts,
+} from './helpers/pool-helper';
+
+describe('Withdraw Tests', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Token2022Amm as Program<Token2022Amm>;
+
+  const fee = 300; // 3% AMM fee
+
+  let authority: Keypair;
+  let user: Keypair;
+
+  before(async () => {
+    authority = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(provider.connection, [authority.publicKey, user.publicKey]);
+  });
+
+  describe('Minimum reserve floor', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      // 1:1 pool so LP, X and Y amounts line up: 100 tokens each
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+
+      await updateConfig(program, authority, pool, {
+        minReserve: new anchor.BN(10 * 10 ** 6),
+      });
+    });
+
+    it('Should allow withdrawing down to the floor', async () => {
+      await withdrawLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        90 * 10 ** 6
+      );
+
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(poolData.reserveX.toNumber(), 10 * 10 ** 6);
+      assert.equal(poolData.reserveY.toNumber(), 10 * 10 ** 6);
+    });
+
+    it('Should reject a partial withdrawal below the floor', async () => {
+      await expectError(
+        withdrawLiquidity(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          1 * 10 ** 6
+        ),
+        'BelowMinReserve'
+      );
+    });
+
+    it('Should allow a full exit of all LP', async () => {
+      await withdrawLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        10 * 10 ** 6
+      );
+
+      const poolData = await program.account.poolState.fetch(pool.poolState);
+      assert.equal(poolData.lpSupply.toNumber(), 0);
+      assert.equal(poolData.reserveX.toNumber(), 0);
+      assert.equal(poolData.reserveY.toNumber(), 0);
+    });
+
+    it('Should reject config updates from a non-authority signer', async () => {
+      await expectError(
+        updateConfig(program, user, pool, {
+          minReserve: new anchor.BN(0),
+        }),
+        'Unauthorized'
+      );
+    });
+  });
+
+  describe('Closing the empty LP account', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+    });
+
+    it('Should keep the LP account while LP remains', async () => {
+      const lpToken = getLpTokenAddress(pool, user.publicKey);
+      const lpBalance = await getAccount(provider.connection, lpToken);
+
+      await withdrawLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        new anchor.BN((lpBalance.amount / BigInt(2)).toString()),
+        { closeLpAccount: true }
+      );
+
+      assert.isNotNull(await provider.connection.getAccountInfo(lpToken));
+    });
+
+    it('Should close the LP account and return rent on a full withdrawal', async () => {
+      const lpToken = getLpTokenAddress(pool, user.publicKey);
+      const lpBalance = await getAccount(provider.connection, lpToken);
+      const lamportsBefore = await provider.connection.getBalance(
+        user.publicKey
+      );
+
+      await withdrawLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        new anchor.BN(lpBalance.amount.toString()),
+        { closeLpAccount: true }
+      );
+
+      assert.isNull(await provider.connection.getAccountInfo(lpToken));
+      const lamportsAfter = await provider.connection.getBalance(
+        user.publicKey
+      );
+      // The returned rent outweighs the transaction fee
+      assert.isTrue(lamportsAfter > lamportsBefore);
+    });
+  });
+
+  describe('Split withdrawal', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+    let treasury: UserTokenAccounts;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+
+      treasury = await createUserTokenAccounts(
+        provider.connection,
+        authority,
+        Keypair.generate().publicKey,
+        pool.tokenX,
+        pool.tokenY
+      );
+
+      // 1:1 pool so LP, X and Y amounts line up
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+    });
+
+    it('Should split token X 70/30 between the user and the treasury', async () => {
+      const accounts = userAccounts.get(user.publicKey.toString())!;
+      const balance = async (account: PublicKey) =>
+        (await getAccount(provider.connection, account)).amount;
+
+      const userXBefore = await balance(accounts.tokenX);
+      const userYBefore = await balance(accounts.tokenY);
+
+      await withdrawSplit(
+        program,
+        user,
+        pool,
+        accounts,
+        10 * 10 ** 6,
+        treasury.tokenX,
+        treasury.tokenY,
+        [7000, 3000],
+        [10000, 0]
+      );
+
+      assert.equal(
+        (await balance(accounts.tokenX)) - userXBefore,
+        BigInt(7 * 10 ** 6)
+      );
+      assert.equal(await balance(treasury.tokenX), BigInt(3 * 10 ** 6));
+      assert.equal(
+        (await balance(accounts.tokenY)) - userYBefore,
+        BigInt(10 * 10 ** 6)
+      );
+      assert.equal(await balance(treasury.tokenY), BigInt(0));
+    });
+
+    it('Should reject splits that do not sum to 10000 bps', async () => {
+      await expectError(
+        withdrawSplit(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          10 * 10 ** 6,
+          treasury.tokenX,
+          treasury.tokenY,
+          [7000, 2000],
+          [10000, 0]
+        ),
+        'InvalidSplit'
+      );
+    });
+  });
+
+  describe('Slippage guard', () => {
+    let pool: PoolAccounts;
+    let userAccounts: Map<string, UserTokenAccounts>;
+
+    before(async () => {
+      ({ pool, userAccounts } = await setupPool(
+        provider.connection,
+        program,
+        authority,
+        [user.publicKey],
+        'legacy',
+        'legacy',
+        fee
+      ));
+      await depositLiquidity(
+        program,
+        user,
+        pool,
+        userAccounts.get(user.publicKey.toString())!,
+        100 * 10 ** 6,
+        100 * 10 ** 6
+      );
+    });
+
+    it('Should report a withdrawal paying out too little as a withdraw slippage', async () => {
+      // 10 LP of a 1:1 pool pays 10 of each token, one unit short of the minimum
+      await expectError(
+        withdrawLiquidity(
+          program,
+          user,
+          pool,
+          userAccounts.get(user.publicKey.toString())!,
+          10 * 10 ** 6,
+          { minAmountY: new anchor.BN(10 * 10 ** 6 + 1) }
+        ),
+        'WithdrawSlippageExceeded'
+      );
+    });
+  });
+});